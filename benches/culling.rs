@@ -0,0 +1,78 @@
+//! How long the audio thread takes to render sources beyond their `MaxAudibleDistance` versus
+//! the same sources in range: `cargo bench --features native-tests --bench culling`.
+
+use bevy::{
+    audio::{Decodable, GlobalVolume},
+    prelude::*,
+};
+use bevy_steam_audio::{
+    culling::MaxAudibleDistance,
+    source::{Listener, SpatialAudioPlugin, SteamAudio, SteamDecoder},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const SOURCES: usize = 32;
+
+/// The decoders of [`SOURCES`] sines 50 units away from the listener, each with `max_distance`.
+fn decoders(max_distance: MaxAudibleDistance) -> Vec<SteamDecoder> {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin))
+        .init_asset::<SteamAudio>()
+        .init_asset::<Mesh>()
+        .init_resource::<GlobalVolume>()
+        .add_plugins(SpatialAudioPlugin::default());
+    app.world_mut().spawn((Listener, Transform::default()));
+    let audio = app
+        .world_mut()
+        .resource_mut::<Assets<SteamAudio>>()
+        .add(SteamAudio::sine(440.0));
+
+    let entities: Vec<Entity> = (0..SOURCES)
+        .map(|index| {
+            let angle = index as f32 / SOURCES as f32 * std::f32::consts::TAU;
+            app.world_mut()
+                .spawn((
+                    AudioPlayer(audio.clone()),
+                    PlaybackSettings::LOOP,
+                    Transform::from_xyz(angle.cos() * 50.0, 0.0, angle.sin() * 50.0),
+                    max_distance,
+                ))
+                .id()
+        })
+        .collect();
+    app.update();
+    app.update();
+
+    let assets = app.world().resource::<Assets<SteamAudio>>();
+    entities
+        .into_iter()
+        .map(|entity| {
+            let player = app.world().get::<AudioPlayer<SteamAudio>>(entity).unwrap();
+            assets.get(&player.0).unwrap().decoder()
+        })
+        .collect()
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for (name, max_distance) in [
+        ("in_range", MaxAudibleDistance::default()),
+        ("beyond_max_distance", MaxAudibleDistance(10.0)),
+    ] {
+        let mut decoders = decoders(max_distance);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                // A couple of blocks of stereo frames from every source.
+                for decoder in &mut decoders {
+                    for _ in 0..4096 {
+                        black_box(decoder.next());
+                    }
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
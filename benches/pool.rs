@@ -0,0 +1,77 @@
+//! How long starting a voice takes with a decoder from the `AudioSourcePool` versus one built
+//! from scratch: `cargo bench --features native-tests --bench pool`.
+
+use bevy::{
+    audio::{Decodable, GlobalVolume},
+    prelude::*,
+};
+use bevy_steam_audio::{
+    pool::{AudioSourcePool, PooledAudio},
+    source::{Listener, SpatialAudioPlugin, SteamAudio},
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::{Duration, Instant};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin))
+        .init_asset::<SteamAudio>()
+        .init_asset::<Mesh>()
+        .init_resource::<GlobalVolume>()
+        .insert_resource(AudioSourcePool { pool_size: 16 })
+        .add_plugins(SpatialAudioPlugin::default());
+    app.world_mut().spawn((Listener, Transform::default()));
+    app.update();
+    app
+}
+
+/// Queues a voice of `audio` and tops the pool back up, returning the handle its decoder is
+/// built from.
+fn queue(app: &mut App, audio: &Handle<SteamAudio>, pooled: bool) -> Handle<SteamAudio> {
+    let mut entity = app.world_mut().spawn((
+        AudioPlayer(audio.clone()),
+        PlaybackSettings::DESPAWN,
+        Transform::from_xyz(1.0, 0.0, -2.0),
+    ));
+    if pooled {
+        entity.insert(PooledAudio);
+    }
+    let entity = entity.id();
+    app.update();
+    app.world()
+        .get::<AudioPlayer<SteamAudio>>(entity)
+        .unwrap()
+        .0
+        .clone()
+}
+
+fn play(c: &mut Criterion) {
+    let mut group = c.benchmark_group("play");
+    for (name, pooled) in [("fresh", false), ("pooled", true)] {
+        let mut app = app();
+        let audio = app
+            .world_mut()
+            .resource_mut::<Assets<SteamAudio>>()
+            .add(SteamAudio::sine(440.0));
+
+        group.bench_function(name, |b| {
+            b.iter_custom(|iterations| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iterations {
+                    let voice = queue(&mut app, &audio, pooled);
+                    let assets = app.world().resource::<Assets<SteamAudio>>();
+                    let started = Instant::now();
+                    let decoder = assets.get(&voice).unwrap().decoder();
+                    total += started.elapsed();
+                    // Back to the pool before the next one is taken.
+                    drop(decoder);
+                }
+                total
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, play);
+criterion_main!(benches);
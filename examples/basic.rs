@@ -1,16 +1,24 @@
 /// This example creates a scene with a camera (the listener) and a sound source in the middle.
 /// The sound is spatialized with the Steam Audio HRTF
 /// Fly around with W,A,S,D,Shift,Space and the mouse
-/// Press F to start the sound again
-use std::sync::{Arc, Mutex};
-
+/// Press F to play another copy of the sound, alternating left and right of the middle, a line
+/// is logged whenever a sound finishes
+/// Press M to play it unspatialized, like background music
+/// Press P to pause or resume every playing sound, they pick up where they left off
+/// Pass `--speakers [stereo|quad|5.1|7.1]` to pan to speakers instead of using the HRTF
 use bevy::audio::AddAudioSource;
 use bevy::audio::AudioPlugin;
 
 use bevy::audio::SpatialScale;
 use bevy::prelude::*;
+use bevy_steam_audio::mix::SpatialBlend;
+use bevy_steam_audio::output::OutputMode;
+use bevy_steam_audio::playback::{
+    SpatialAudioBundle, SpatialAudioCommands, SpatialPlaybackControl, SpatialPlaybackFinished,
+};
 use bevy_steam_audio::source::SpatialAudioPlugin;
-use bevy_steam_audio::source::SteamAudio;
+use bevy_steam_audio::source::{Listener, SteamAudio};
+use steam_audio::prelude::SpeakerLayout;
 
 use smooth_bevy_cameras::{
     controllers::fps::{FpsCameraBundle, FpsCameraController, FpsCameraPlugin},
@@ -22,8 +30,21 @@ struct AudioHandles {
     eduardo: Handle<SteamAudio>,
 }
 
-#[derive(Component)]
-struct ListenerSteam;
+/// The output mode picked with `--speakers`, binaural without it.
+fn output_mode() -> OutputMode {
+    let mut args = std::env::args().skip_while(|arg| arg != "--speakers");
+    if args.next().is_none() {
+        return OutputMode::Binaural;
+    }
+
+    let layout = match args.next().as_deref() {
+        Some("quad") => SpeakerLayout::Quadraphonic,
+        Some("5.1") => SpeakerLayout::Surround5_1,
+        Some("7.1") => SpeakerLayout::Surround7_1,
+        _ => SpeakerLayout::Stereo,
+    };
+    OutputMode::Panning(layout)
+}
 
 fn main() {
     App::new()
@@ -32,12 +53,15 @@ fn main() {
             default_spatial_scale: SpatialScale::new(1.0),
         }))
         .add_audio_source::<SteamAudio>()
-        .add_plugins(SpatialAudioPlugin)
+        .add_plugins(SpatialAudioPlugin {
+            output_mode: output_mode(),
+            ..default()
+        })
         .add_plugins(LookTransformPlugin)
         .add_plugins(FpsCameraPlugin::default())
         .add_systems(Startup, setup_sources)
         .add_systems(Startup, setup_scene)
-        .add_systems(Update, (update_sound_direction, play_new_sound))
+        .add_systems(Update, (play_new_sound, toggle_pause, log_finished_sounds))
         .insert_resource(AudioHandles {
             eduardo: Handle::default(),
         })
@@ -49,59 +73,44 @@ fn setup_sources(
     mut handles: ResMut<AudioHandles>,
     mut commands: Commands,
 ) {
-    let source_direction: Arc<Mutex<Vec3>> = Arc::new(Mutex::new(Vec3::default()));
-    let source_direction_ = source_direction.clone();
-
-    let source_position: Arc<Mutex<Vec3>> = Arc::new(Mutex::new(Vec3::default()));
-    let source_position_ = source_position.clone();
-
-    let listener_position: Arc<Mutex<Vec3>> = Arc::new(Mutex::new(Vec3::default()));
-    let listener_position_ = listener_position.clone();
-
-    let audio_handle = assets.add(SteamAudio {
-        path: "assets/eduardo.ogg".to_owned(),
-        direction: source_direction_,
-        source_position: source_position_,
-        listener_position: listener_position_,
-    });
-
-    handles.eduardo = audio_handle.clone();
-
-    commands.spawn(AudioPlayer(audio_handle));
+    let eduardo = SpatialAudioBundle::new(&mut assets, "eduardo.ogg", Transform::default());
+    handles.eduardo = eduardo.player.0.clone();
+    commands.spawn((eduardo, SpatialBlend(1.0)));
 }
 
 fn play_new_sound(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     handles: Res<AudioHandles>,
     mut commands: Commands,
+    mut left: Local<bool>,
 ) {
+    // Every copy shares the handle but is heard from its own entity.
     if keyboard_input.just_pressed(KeyCode::KeyF) {
-        commands.spawn(AudioPlayer(handles.eduardo.clone_weak()));
+        *left = !*left;
+        let side = if *left { -2.0 } else { 2.0 };
+        commands.play_spatial(handles.eduardo.clone(), Vec3::new(side, 0.0, 0.0));
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        commands.spawn((AudioPlayer(handles.eduardo.clone_weak()), SpatialBlend(0.0)));
     }
 }
 
-fn update_sound_direction(
-    handles: Res<AudioHandles>,
-    assets: Res<Assets<SteamAudio>>,
-    listener_query: Query<&GlobalTransform, With<ListenerSteam>>,
+fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Query<&SpatialPlaybackControl>,
 ) {
-    let source_transform = GlobalTransform::default(); // Todo
-    let listener_transform = listener_query.get_single().unwrap();
-    let local_transform = source_transform.reparented_to(listener_transform);
-
-    let handle = assets.get(&handles.eduardo).unwrap();
-
-    let binding = handle.direction.clone();
-    let mut direction = binding.lock().unwrap();
-    *direction = local_transform.translation.normalize_or_zero();
-
-    let binding = handle.source_position.clone();
-    let mut source_position = binding.lock().unwrap();
-    *source_position = source_transform.translation();
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        for control in controls.iter() {
+            control.toggle();
+        }
+    }
+}
 
-    let binding = handle.listener_position.clone();
-    let mut listener_position = binding.lock().unwrap();
-    *listener_position = listener_transform.translation();
+fn log_finished_sounds(mut finished: EventReader<SpatialPlaybackFinished>) {
+    for event in finished.read() {
+        info!("sound on {:?} finished playing", event.entity);
+    }
 }
 
 fn setup_scene(
@@ -132,7 +141,7 @@ fn setup_scene(
     // camera
     commands
         .spawn(Camera3d::default())
-        .insert(ListenerSteam)
+        .insert(Listener)
         .insert(FpsCameraBundle::new(
             FpsCameraController::default(),
             Vec3::new(-2.0, 5.0, 5.0),
@@ -0,0 +1,65 @@
+/// This example draws the Steam Audio sources and the listener with gizmos.
+/// Two looping sounds are played, one of them circles the listener and turns as it goes.
+/// Each source shows its facing direction, its directivity and its audible range, the circling
+/// one is a cardioid and also shows its directivity as a solid lobe.
+/// Run with `--features audio-debug`.
+use bevy::audio::AddAudioSource;
+use bevy::prelude::*;
+use bevy_steam_audio::prelude::{
+    AudioDirectivity, MaxAudibleDistance, ShowDirectivityLobe, SteamAudioGizmosPlugin,
+};
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio};
+
+#[derive(Component)]
+struct Orbit;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_audio_source::<SteamAudio>()
+        .add_plugins(SpatialAudioPlugin::default())
+        .add_plugins(SteamAudioGizmosPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, orbit)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let eduardo = asset_server.load::<SteamAudio>("eduardo.ogg");
+
+    commands.spawn((
+        AudioPlayer(eduardo.clone()),
+        PlaybackSettings::LOOP,
+        MaxAudibleDistance(6.0),
+        AudioDirectivity {
+            dipole_weight: 0.5,
+            dipole_power: 2.0,
+        },
+        ShowDirectivityLobe::default(),
+        Transform::from_xyz(3.0, 0.0, 0.0),
+        Visibility::default(),
+        Orbit,
+    ));
+
+    commands.spawn((
+        AudioPlayer(eduardo),
+        PlaybackSettings::LOOP,
+        MaxAudibleDistance(3.0),
+        Transform::from_xyz(-2.0, 0.0, -4.0),
+    ));
+
+    commands.spawn((Transform::IDENTITY, Visibility::default(), Listener));
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 8.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+fn orbit(time: Res<Time>, mut sources: Query<&mut Transform, With<Orbit>>) {
+    let angle = time.elapsed_secs() * 0.5;
+    for mut transform in &mut sources {
+        transform.translation = Vec3::new(angle.cos(), 0.0, angle.sin()) * 3.0;
+        transform.rotate_y(time.delta_secs());
+    }
+}
@@ -0,0 +1,45 @@
+/// This example plays a looping sound and logs the Steam Audio diagnostics to the console:
+/// the number of active sources, the last simulation time and the blocks processed per second.
+use bevy::audio::AddAudioSource;
+use bevy::diagnostic::LogDiagnosticsPlugin;
+use bevy::prelude::*;
+use bevy_steam_audio::prelude::{ReflectionConfig, SteamAudioDiagnosticsPlugin};
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_audio_source::<SteamAudio>()
+        .add_plugins(SpatialAudioPlugin {
+            reflections: ReflectionConfig {
+                enabled: true,
+                ..default()
+            },
+            ..default()
+        })
+        .add_plugins(SteamAudioDiagnosticsPlugin)
+        .add_plugins(LogDiagnosticsPlugin::filtered(vec![
+            SteamAudioDiagnosticsPlugin::ACTIVE_VOICES,
+            SteamAudioDiagnosticsPlugin::VIRTUAL_VOICES,
+            SteamAudioDiagnosticsPlugin::ACTIVE_SOURCES,
+            SteamAudioDiagnosticsPlugin::SIM_TIME,
+            SteamAudioDiagnosticsPlugin::BLOCKS_PER_SECOND,
+            SteamAudioDiagnosticsPlugin::BLOCK_TIME,
+            SteamAudioDiagnosticsPlugin::UNDERRUNS,
+            SteamAudioDiagnosticsPlugin::REFLECTION_LATENCY,
+        ]))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let eduardo = asset_server.load::<SteamAudio>("eduardo.ogg");
+
+    commands.spawn((
+        AudioPlayer(eduardo),
+        PlaybackSettings::LOOP,
+        Transform::from_xyz(2.0, 0.0, 0.0),
+    ));
+
+    commands.spawn((Camera3d::default(), Listener));
+}
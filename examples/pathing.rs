@@ -0,0 +1,180 @@
+/// This example builds two rooms joined by a doorway, with the sound playing in the far room.
+/// A probe volume covering both rooms is baked with pathing, so the sound reaches the listener
+/// through the door instead of through the wall.
+/// Press B to bake the probes and E to open or close the door.
+/// The camera is the listener, fly around with W,A,S,D,Shift,Space and the mouse
+use bevy::audio::AddAudioSource;
+use bevy::prelude::*;
+use bevy_steam_audio::prelude::{
+    AudioMaterial, AudioObstacle, AudioPortal, BakeReflections, BakeVariation, DoorOpen,
+    PathingConfig, PathingSource, ProbeVolume,
+};
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio};
+use std::f32::consts::FRAC_PI_2;
+
+use smooth_bevy_cameras::{
+    controllers::fps::{FpsCameraBundle, FpsCameraController, FpsCameraPlugin},
+    LookTransformPlugin,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_audio_source::<SteamAudio>()
+        .add_plugins(SpatialAudioPlugin {
+            pathing: PathingConfig {
+                enabled: true,
+                ..default()
+            },
+            ..default()
+        })
+        .add_plugins(LookTransformPlugin)
+        .add_plugins(FpsCameraPlugin::default())
+        .add_systems(Startup, (setup_rooms, setup_source))
+        .add_systems(Update, (bake_probes, toggle_door))
+        .run();
+}
+
+#[derive(Component)]
+struct Door;
+
+fn setup_rooms(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let wall = materials.add(Color::srgb(0.6, 0.6, 0.6));
+    let floor = materials.add(Color::srgb(0.5, 0.2, 0.2));
+    let door = materials.add(Color::srgb(0.4, 0.25, 0.1));
+
+    // floor and ceiling, shared by both rooms
+    for (y, audio_material) in [(0.0, AudioMaterial::Carpet), (4.0, AudioMaterial::Plaster)] {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::new(20.0, 0.2, 10.0))),
+            MeshMaterial3d(floor.clone()),
+            Transform::from_xyz(0.0, y, 0.0),
+            AudioObstacle,
+            audio_material,
+        ));
+    }
+
+    // outer walls, then the wall between the rooms with a 1x2 doorway in the middle
+    let walls = [
+        (Vec3::new(10.0, 2.0, 0.0), Vec3::new(0.2, 4.0, 10.0)),
+        (Vec3::new(-10.0, 2.0, 0.0), Vec3::new(0.2, 4.0, 10.0)),
+        (Vec3::new(0.0, 2.0, 5.0), Vec3::new(20.0, 4.0, 0.2)),
+        (Vec3::new(0.0, 2.0, -5.0), Vec3::new(20.0, 4.0, 0.2)),
+        (Vec3::new(0.0, 2.0, 2.75), Vec3::new(0.2, 4.0, 4.5)),
+        (Vec3::new(0.0, 2.0, -2.75), Vec3::new(0.2, 4.0, 4.5)),
+        (Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.2, 2.0, 1.0)),
+    ];
+    for (position, size) in walls {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::from_size(size))),
+            MeshMaterial3d(wall.clone()),
+            Transform::from_translation(position),
+            AudioObstacle,
+            AudioMaterial::Concrete,
+        ));
+    }
+
+    // the door, facing along X to fill the doorway, hidden while open
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(1.0, 2.0, 0.05))),
+        MeshMaterial3d(door),
+        Transform::from_xyz(0.0, 1.0, 0.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2)),
+        Visibility::Hidden,
+        AudioPortal::default(),
+        DoorOpen,
+        Door,
+    ));
+
+    // probes covering both rooms
+    commands.spawn((
+        ProbeVolume {
+            half_extents: Vec3::new(10.0, 2.0, 5.0),
+            spacing: 1.5,
+        },
+        Transform::from_xyz(0.0, 2.0, 0.0),
+    ));
+
+    // lights
+    for x in [-5.0, 5.0] {
+        commands.spawn((
+            PointLight {
+                intensity: 1500.0,
+                shadows_enabled: true,
+                ..default()
+            },
+            Transform::from_xyz(x, 3.5, 0.0),
+        ));
+    }
+
+    // camera, in the room without the sound
+    commands
+        .spawn(Camera3d::default())
+        .insert(Listener)
+        .insert(FpsCameraBundle::new(
+            FpsCameraController::default(),
+            Vec3::new(-7.0, 1.7, 3.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::Y,
+        ));
+}
+
+fn setup_source(
+    mut commands: Commands,
+    mut assets: ResMut<Assets<SteamAudio>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let eduardo = assets.add(SteamAudio::from_asset_path("eduardo.ogg"));
+
+    // in the corner of the far room, out of sight of the doorway
+    commands.spawn((
+        AudioPlayer(eduardo),
+        PlaybackSettings::LOOP,
+        PathingSource,
+        Mesh3d(meshes.add(Cuboid::new(0.2, 0.2, 0.2))),
+        MeshMaterial3d(materials.add(Color::srgb(0.8, 0.7, 0.6))),
+        Transform::from_xyz(7.0, 1.0, -3.5),
+    ));
+}
+
+fn bake_probes(
+    keys: Res<ButtonInput<KeyCode>>,
+    volumes: Query<Entity, With<ProbeVolume>>,
+    mut bake: EventWriter<BakeReflections>,
+) {
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    for volume in volumes.iter() {
+        info!("baking probes, pathing starts once the bake finishes");
+        bake.send(BakeReflections {
+            volume,
+            variation: BakeVariation::Reverb,
+        });
+    }
+}
+
+fn toggle_door(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut doors: Query<(Entity, &mut Visibility, Has<DoorOpen>), With<Door>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+
+    for (entity, mut visibility, open) in doors.iter_mut() {
+        if open {
+            commands.entity(entity).remove::<DoorOpen>();
+            *visibility = Visibility::Inherited;
+        } else {
+            commands.entity(entity).insert(DoorOpen);
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
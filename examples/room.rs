@@ -0,0 +1,112 @@
+/// This example builds a small room out of AudioObstacles for the simulator to trace against.
+/// The walls are concrete, the floor carpet and one wall is a glass pane.
+/// Reflections are enabled, walk up to a wall to hear its early reflections.
+/// The camera is the listener, fly around with W,A,S,D,Shift,Space and the mouse
+use bevy::audio::AddAudioSource;
+use bevy::prelude::*;
+use bevy_steam_audio::prelude::{AudioMaterial, AudioObstacle, ReflectionConfig};
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio};
+
+use smooth_bevy_cameras::{
+    controllers::fps::{FpsCameraBundle, FpsCameraController, FpsCameraPlugin},
+    LookTransformPlugin,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_audio_source::<SteamAudio>()
+        .add_plugins(SpatialAudioPlugin {
+            reflections: ReflectionConfig {
+                enabled: true,
+                ..default()
+            },
+            ..default()
+        })
+        .add_plugins(LookTransformPlugin)
+        .add_plugins(FpsCameraPlugin::default())
+        .add_systems(Startup, (setup_room, setup_source))
+        .run();
+}
+
+fn setup_room(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let wall = materials.add(Color::srgb(0.6, 0.6, 0.6));
+    let glass = materials.add(Color::srgba(0.6, 0.8, 1.0, 0.3));
+    let floor = materials.add(Color::srgb(0.5, 0.2, 0.2));
+
+    // floor and ceiling
+    for (y, audio_material) in [(0.0, AudioMaterial::Carpet), (4.0, AudioMaterial::Plaster)] {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::new(10.0, 0.2, 10.0))),
+            MeshMaterial3d(floor.clone()),
+            Transform::from_xyz(0.0, y, 0.0),
+            AudioObstacle,
+            audio_material,
+        ));
+    }
+
+    // walls, the one facing +X is glass
+    let walls = [
+        (Vec3::new(5.0, 2.0, 0.0), Vec3::new(0.2, 4.0, 10.0), true),
+        (Vec3::new(-5.0, 2.0, 0.0), Vec3::new(0.2, 4.0, 10.0), false),
+        (Vec3::new(0.0, 2.0, 5.0), Vec3::new(10.0, 4.0, 0.2), false),
+        (Vec3::new(0.0, 2.0, -5.0), Vec3::new(10.0, 4.0, 0.2), false),
+    ];
+    for (position, size, is_glass) in walls {
+        let (material, audio_material) = if is_glass {
+            (glass.clone(), AudioMaterial::Glass)
+        } else {
+            (wall.clone(), AudioMaterial::Concrete)
+        };
+
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::from_size(size))),
+            MeshMaterial3d(material),
+            Transform::from_translation(position),
+            AudioObstacle,
+            audio_material,
+        ));
+    }
+
+    // light
+    commands.spawn((
+        PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(0.0, 3.5, 0.0),
+    ));
+
+    // camera
+    commands
+        .spawn(Camera3d::default())
+        .insert(Listener)
+        .insert(FpsCameraBundle::new(
+            FpsCameraController::default(),
+            Vec3::new(-3.0, 1.7, 3.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::Y,
+        ));
+}
+
+fn setup_source(
+    mut commands: Commands,
+    mut assets: ResMut<Assets<SteamAudio>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let eduardo = assets.add(SteamAudio::from_asset_path("eduardo.ogg"));
+
+    commands.spawn((
+        AudioPlayer(eduardo),
+        PlaybackSettings::LOOP,
+        Mesh3d(meshes.add(Cuboid::new(0.2, 0.2, 0.2))),
+        MeshMaterial3d(materials.add(Color::srgb(0.8, 0.7, 0.6))),
+        Transform::from_xyz(0.0, 1.0, 0.0),
+    ));
+}
@@ -0,0 +1,55 @@
+/// This example builds its scene in centimeters, with 100 Bevy units to the meter.
+/// The sound plays 50 units from the listener, which Steam Audio hears as half a meter away,
+/// just as loud as a source 0.5 units away in a scene built in meters.
+/// Press Up and Down to move the source closer or further away.
+use bevy::audio::AddAudioSource;
+use bevy::prelude::*;
+use bevy_steam_audio::prelude::AudioUnitsPerMeter;
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio};
+
+#[derive(Component)]
+struct Emitter;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_audio_source::<SteamAudio>()
+        .add_plugins(SpatialAudioPlugin::default())
+        .insert_resource(AudioUnitsPerMeter(100.0))
+        .add_systems(Startup, setup)
+        .add_systems(Update, move_emitter)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let eduardo = asset_server.load::<SteamAudio>("eduardo.ogg");
+
+    commands.spawn((
+        AudioPlayer(eduardo),
+        PlaybackSettings::LOOP,
+        Transform::from_xyz(50.0, 0.0, 0.0),
+        Emitter,
+    ));
+
+    commands.spawn((Camera3d::default(), Listener));
+}
+
+fn move_emitter(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut emitters: Query<&mut Transform, With<Emitter>>,
+) {
+    // A meter per second.
+    let step = 100.0 * time.delta_secs();
+    for mut transform in &mut emitters {
+        if keys.pressed(KeyCode::ArrowUp) {
+            transform.translation.x = (transform.translation.x - step).max(0.0);
+        }
+        if keys.pressed(KeyCode::ArrowDown) {
+            transform.translation.x += step;
+        }
+        if keys.just_pressed(KeyCode::ArrowUp) || keys.just_pressed(KeyCode::ArrowDown) {
+            info!("{:.2} m away", transform.translation.x / 100.0);
+        }
+    }
+}
@@ -0,0 +1,506 @@
+use bevy::{
+    asset::Asset,
+    audio::{Decodable, Source},
+    log::warn,
+    math::Vec3,
+    prelude::{
+        Assets, AudioPlayer, Commands, Component, Reflect, ReflectComponent, ReflectResource, Res,
+        ResMut, Resource,
+    },
+    reflect::TypePath,
+    utils::Duration,
+};
+use std::sync::{Arc, Mutex};
+use steam_audio::{
+    hrtf::{AudioSettings, HRTF},
+    prelude::{
+        AmbisonicsDecodeEffect, AmbisonicsDecodeParams, AmbisonicsEncodeEffect,
+        AmbisonicsEncodeParams, AmbisonicsRotationEffect, AmbisonicsRotationParams, Context,
+        ContextSettings, DeinterleavedFrame, SpeakerLayout,
+    },
+};
+
+use crate::{
+    coords::bevy_to_phonon,
+    eq::HeadphoneEqFilter,
+    output::OutputMode,
+    params::SharedParams,
+    settings::SharedHrtf,
+    source::{SourceOrientation, SpatialAudioSettings},
+};
+
+/// Renders a source through Ambisonics instead of the binaural effect.
+///
+/// The direct effect output is encoded into an Ambisonics sound field of this order and decoded
+/// to stereo with the listener's orientation. Orders are clamped to `1..=3`. Read when the voice
+/// starts, changing it afterwards has no effect on playing sounds.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct AmbisonicsOrder(pub u32);
+
+impl Default for AmbisonicsOrder {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl AmbisonicsOrder {
+    pub fn order(&self) -> u32 {
+        self.0.clamp(1, 3)
+    }
+
+    /// Channels in a sound field of this order, 4 for first order, 9 for second, 16 for third.
+    pub fn channels(&self) -> usize {
+        let order = self.order() as usize;
+        (order + 1) * (order + 1)
+    }
+}
+
+/// Whether an [`AmbisonicsOrder`] source is decoded through the HRTF (the default) or panned
+/// to stereo speakers.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct AmbisonicsHrtf(pub bool);
+
+impl Default for AmbisonicsHrtf {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// The encode and decode stages of a voice rendered through Ambisonics.
+pub(crate) struct AmbisonicsPipeline {
+    order: AmbisonicsOrder,
+    binaural: bool,
+    audio_settings: AudioSettings,
+    encode_effect: AmbisonicsEncodeEffect,
+    decode_effect: AmbisonicsDecodeEffect,
+}
+
+impl AmbisonicsPipeline {
+    pub(crate) fn new(
+        context: &Context,
+        audio_settings: &AudioSettings,
+        hrtf: &HRTF,
+        order: AmbisonicsOrder,
+        binaural: bool,
+    ) -> Self {
+        let encode_effect = AmbisonicsEncodeEffect::new(context, audio_settings, order.order())
+            .expect("could not build steam audio ambisonics encode effect");
+        let decode_effect = AmbisonicsDecodeEffect::new(
+            context,
+            audio_settings,
+            hrtf,
+            SpeakerLayout::Stereo,
+            order.order(),
+        )
+        .expect("could not build steam audio ambisonics decode effect");
+
+        Self {
+            order,
+            binaural,
+            audio_settings: audio_settings.clone(),
+            encode_effect,
+            decode_effect,
+        }
+    }
+
+    /// Rebuilds the decode stage after the HRTF was swapped.
+    pub(crate) fn set_hrtf(&mut self, context: &Context, hrtf: &HRTF) {
+        match AmbisonicsDecodeEffect::new(
+            context,
+            &self.audio_settings,
+            hrtf,
+            SpeakerLayout::Stereo,
+            self.order.order(),
+        ) {
+            Ok(effect) => self.decode_effect = effect,
+            Err(err) => warn!("Could not rebuild steam audio ambisonics decode effect: {err:?}"),
+        }
+    }
+
+    /// Encodes the mono `input` arriving from world space `direction` and decodes it into the
+    /// stereo `output` for a listener with `listener` orientation.
+    pub(crate) fn apply(
+        &mut self,
+        input: &mut DeinterleavedFrame,
+        direction: Vec3,
+        listener: SourceOrientation,
+        output: &mut DeinterleavedFrame,
+    ) {
+        let mut sound_field = DeinterleavedFrame::new(
+            self.audio_settings.frame_size() as usize,
+            self.order.channels(),
+            self.audio_settings.sampling_rate(),
+        );
+
+        let encode_params = AmbisonicsEncodeParams {
+            direction: bevy_to_phonon(direction),
+            order: self.order.order(),
+        };
+        self.encode_effect
+            .apply_to_buffer(&encode_params, input, &mut sound_field)
+            .unwrap();
+
+        let decode_params = AmbisonicsDecodeParams {
+            order: self.order.order(),
+            orientation: listener.into(),
+            binaural: self.binaural,
+        };
+        self.decode_effect
+            .apply_to_buffer(&decode_params, &mut sound_field, output)
+            .unwrap();
+    }
+}
+
+/// Order of the shared Ambisonics bed the reflections of every voice are mixed into, 1 for
+/// mobile and 2 or 3 on PC.
+///
+/// Instead of decoding its reflections to stereo itself, each voice adds them to the bed, which
+/// is rotated with the listener and decoded through the HRTF once per block. Reflections
+/// simulated at a higher [`ReflectionConfig::order`](crate::reflections::ReflectionConfig) are
+/// truncated to this order. Read when the plugin is built.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct AmbisonicsConfig {
+    pub order: u8,
+}
+
+impl Default for AmbisonicsConfig {
+    fn default() -> Self {
+        Self { order: 1 }
+    }
+}
+
+impl AmbisonicsConfig {
+    pub fn order(&self) -> AmbisonicsOrder {
+        AmbisonicsOrder(self.order as u32)
+    }
+}
+
+/// The sound field the voices mix their reflections into, taken by the bed once per block.
+///
+/// Only ever locked from the audio thread, where voices and the bed take turns.
+pub(crate) struct AmbisonicsBus {
+    order: AmbisonicsOrder,
+    field: Mutex<BusField>,
+}
+
+struct BusField {
+    channels: Vec<Vec<f32>>,
+    /// Whether anything was mixed in since the bed last took the field.
+    mixed: bool,
+}
+
+impl AmbisonicsBus {
+    pub(crate) fn new(order: AmbisonicsOrder, frame_size: usize) -> Self {
+        Self {
+            order,
+            field: Mutex::new(BusField {
+                channels: vec![vec![0.0; frame_size]; order.channels()],
+                mixed: false,
+            }),
+        }
+    }
+
+    /// Adds a voice's sound field scaled by a gain ramping from `from` to `to` over the block,
+    /// dropping channels above the bus order.
+    pub(crate) fn mix(&self, sound_field: &DeinterleavedFrame, from: f32, to: f32) {
+        let mut field = self.field.lock().unwrap();
+        for (bus, channel) in field.channels.iter_mut().zip(&sound_field.current_frame) {
+            let len = channel.len() as f32;
+            for (index, (bus, sample)) in bus.iter_mut().zip(channel).enumerate() {
+                let t = (index as f32 + 1.0) / len;
+                *bus += (from + (to - from) * t) * sample;
+            }
+        }
+        field.mixed = true;
+    }
+
+    /// Swaps the mixed field into `channels` and clears the bus, `false` if nothing was mixed in
+    /// since the last call.
+    fn take(&self, channels: &mut [Vec<f32>]) -> bool {
+        let mut field = self.field.lock().unwrap();
+        if !field.mixed {
+            return false;
+        }
+
+        for (bus, channel) in field.channels.iter_mut().zip(channels) {
+            std::mem::swap(bus, channel);
+            bus.fill(0.0);
+        }
+        field.mixed = false;
+        true
+    }
+}
+
+/// Plays the shared [`AmbisonicsBus`], spawned once by the plugin.
+#[derive(TypePath, Asset)]
+pub struct AmbisonicsBed {
+    bus: Arc<AmbisonicsBus>,
+    listener_orientation: SharedParams<SourceOrientation>,
+    hrtf: Arc<SharedHrtf>,
+    headphone_eq: SharedParams<[f32; 3]>,
+    output_mode: SharedParams<OutputMode>,
+    /// Matches the frame size of the voices mixing into the bus.
+    audio_settings: AudioSettings,
+}
+
+pub(crate) fn spawn_ambisonics_bed(
+    mut commands: Commands,
+    mut beds: ResMut<Assets<AmbisonicsBed>>,
+    settings: Res<SpatialAudioSettings>,
+) {
+    let bed = beds.add(AmbisonicsBed {
+        bus: settings.ambisonics_bus.clone(),
+        listener_orientation: settings.listener_orientation.clone(),
+        hrtf: settings.shared_hrtf.clone(),
+        headphone_eq: settings.headphone_eq.clone(),
+        output_mode: settings.output_mode.clone(),
+        audio_settings: settings.audio_settings.clone(),
+    });
+    commands.spawn(AudioPlayer(bed));
+}
+
+/// Rotates the bed into listener space and decodes it to the [`OutputMode`].
+pub struct AmbisonicsBedDecoder {
+    bus: Arc<AmbisonicsBus>,
+    order: AmbisonicsOrder,
+    listener_orientation: SharedParams<SourceOrientation>,
+    current_listener: SourceOrientation,
+    shared_hrtf: Arc<SharedHrtf>,
+    hrtf_generation: u32,
+    hrtf: HRTF,
+    headphone_eq_gains: SharedParams<[f32; 3]>,
+    headphone_eq: HeadphoneEqFilter,
+    shared_output_mode: SharedParams<OutputMode>,
+    output_mode: OutputMode,
+    audio_settings: AudioSettings,
+    context: Context,
+    rotation_effect: AmbisonicsRotationEffect,
+    decode_effect: AmbisonicsDecodeEffect,
+    field: Vec<Vec<f32>>,
+    current_channel: usize,
+    current_block_offset: usize,
+    /// One block per output channel.
+    current_blocks: Vec<Vec<f32>>,
+}
+
+impl AmbisonicsBedDecoder {
+    fn new(bed: &AmbisonicsBed) -> Self {
+        let audio_settings = bed.audio_settings.clone();
+        let context =
+            Context::new(&ContextSettings::default()).expect("could not build steam audio context");
+        let hrtf_generation = bed.hrtf.generation();
+        let hrtf = HRTF::new(&context, &audio_settings, &bed.hrtf.settings())
+            .expect("could not build steam audio hrtf");
+
+        let order = bed.bus.order;
+        let output_mode = bed.output_mode.load();
+        let rotation_effect =
+            AmbisonicsRotationEffect::new(&context, &audio_settings, order.order())
+                .expect("could not build steam audio ambisonics rotation effect");
+        let decode_effect = AmbisonicsDecodeEffect::new(
+            &context,
+            &audio_settings,
+            &hrtf,
+            output_mode.speaker_layout(),
+            order.order(),
+        )
+        .expect("could not build steam audio ambisonics decode effect");
+
+        let frame_size = audio_settings.frame_size() as usize;
+        Self {
+            bus: bed.bus.clone(),
+            order,
+            current_listener: bed.listener_orientation.load(),
+            listener_orientation: bed.listener_orientation.clone(),
+            shared_hrtf: bed.hrtf.clone(),
+            hrtf_generation,
+            hrtf,
+            headphone_eq: HeadphoneEqFilter::new(
+                &context,
+                &audio_settings,
+                bed.headphone_eq.load(),
+            ),
+            headphone_eq_gains: bed.headphone_eq.clone(),
+            shared_output_mode: bed.output_mode.clone(),
+            output_mode,
+            rotation_effect,
+            decode_effect,
+            field: vec![vec![0.0; frame_size]; order.channels()],
+            current_channel: 0,
+            current_block_offset: 0,
+            current_blocks: Vec::new(),
+            audio_settings,
+            context,
+        }
+    }
+
+    /// Rebuilds the decode stage for the current HRTF and output mode.
+    fn rebuild_decode(&mut self) {
+        match AmbisonicsDecodeEffect::new(
+            &self.context,
+            &self.audio_settings,
+            &self.hrtf,
+            self.output_mode.speaker_layout(),
+            self.order.order(),
+        ) {
+            Ok(effect) => self.decode_effect = effect,
+            Err(err) => warn!("Could not rebuild steam audio ambisonics decode effect: {err:?}"),
+        }
+    }
+
+    /// Decodes whatever the voices mixed into the bus since the last block.
+    fn next_block(&mut self) {
+        let frame_size = self.audio_settings.frame_size() as usize;
+        let sampling_rate = self.audio_settings.sampling_rate();
+        self.current_block_offset = 0;
+        self.current_channel = 0;
+
+        if let Some(listener) = self.listener_orientation.try_load() {
+            self.current_listener = listener;
+        }
+        if let Some(gains) = self.headphone_eq_gains.try_load() {
+            self.headphone_eq.set_gains(gains);
+        }
+        let generation = self.shared_hrtf.generation();
+        if generation != self.hrtf_generation {
+            self.hrtf_generation = generation;
+            match HRTF::new(
+                &self.context,
+                &self.audio_settings,
+                &self.shared_hrtf.settings(),
+            ) {
+                Ok(hrtf) => {
+                    self.hrtf = hrtf;
+                    self.rebuild_decode();
+                }
+                Err(err) => warn!("Could not swap steam audio hrtf, keeping the old one: {err:?}"),
+            }
+        }
+        if let Some(mode) = self.shared_output_mode.try_load() {
+            if mode != self.output_mode {
+                self.output_mode = mode;
+                self.rebuild_decode();
+            }
+        }
+
+        let channels = self.output_mode.channels() as usize;
+        // Nobody is reflecting, skip the decode.
+        if !self.bus.take(&mut self.field) {
+            self.current_blocks = vec![vec![0.0; frame_size]; channels];
+            return;
+        }
+
+        let mut sound_field = DeinterleavedFrame::new(frame_size, self.field.len(), sampling_rate);
+        for (channel, field) in sound_field.current_frame.iter_mut().zip(&self.field) {
+            channel.clone_from(field);
+        }
+        let mut rotated = DeinterleavedFrame::new(frame_size, self.field.len(), sampling_rate);
+        let rotation_params = AmbisonicsRotationParams {
+            orientation: self.current_listener.into(),
+            order: self.order.order(),
+        };
+        self.rotation_effect
+            .apply_to_buffer(&rotation_params, &mut sound_field, &mut rotated)
+            .unwrap();
+
+        // Already in listener space.
+        let binaural = self.output_mode == OutputMode::Binaural;
+        let mut output = DeinterleavedFrame::new(frame_size, channels, sampling_rate);
+        let decode_params = AmbisonicsDecodeParams {
+            order: self.order.order(),
+            orientation: SourceOrientation::default().into(),
+            binaural,
+        };
+        self.decode_effect
+            .apply_to_buffer(&decode_params, &mut rotated, &mut output)
+            .unwrap();
+
+        if binaural {
+            if let [left, right] = &mut output.current_frame[..] {
+                self.headphone_eq.apply(left, right);
+            }
+        }
+        self.current_blocks = output.current_frame.clone();
+    }
+
+    fn block_exhausted(&self) -> bool {
+        self.current_block_offset >= self.current_blocks.first().map_or(0, Vec::len)
+    }
+}
+
+impl Iterator for AmbisonicsBedDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.block_exhausted() {
+            self.next_block();
+        }
+
+        let sample = self.current_blocks[self.current_channel][self.current_block_offset];
+        self.current_channel += 1;
+        if self.current_channel == self.current_blocks.len() {
+            self.current_channel = 0;
+            self.current_block_offset += 1;
+
+            // Like the voices, loaded right away so `channels` describes the next frame.
+            if self.block_exhausted() {
+                self.next_block();
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl Source for AmbisonicsBedDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.block_exhausted() {
+            let frame_size = self.audio_settings.frame_size() as usize;
+            return Some(frame_size * self.output_mode.channels() as usize);
+        }
+
+        let remaining = self.current_blocks[0].len() - self.current_block_offset;
+        Some(remaining * self.current_blocks.len() - self.current_channel)
+    }
+
+    fn channels(&self) -> u16 {
+        self.output_mode.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.audio_settings.sampling_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for AmbisonicsBed {
+    type DecoderItem = <AmbisonicsBedDecoder as Iterator>::Item;
+
+    type Decoder = AmbisonicsBedDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        AmbisonicsBedDecoder::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sound_fields_have_a_channel_per_harmonic() {
+        assert_eq!(AmbisonicsOrder(1).channels(), 4);
+        assert_eq!(AmbisonicsOrder(2).channels(), 9);
+        assert_eq!(AmbisonicsOrder(3).channels(), 16);
+
+        // Clamped to the orders Steam Audio supports.
+        assert_eq!(AmbisonicsOrder(0).channels(), 4);
+        assert_eq!(AmbisonicsOrder(5).channels(), 16);
+        assert_eq!(AmbisonicsConfig::default().order().channels(), 4);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::{
+    asset::Handle,
+    math::Vec3,
+    prelude::{
+        Component, Entity, GlobalTransform, Query, Reflect, ReflectComponent, ReflectResource, Res,
+        ResMut, Resource, With,
+    },
+};
+
+use crate::{
+    probe::BakedDataAsset,
+    source::{PrimaryListener, SpatialAudioSettings},
+};
+
+/// A reverb zone, blending the listener's reverb towards `reverb_ir` while they're inside the
+/// `half_extents` box around the entity.
+///
+/// The blend fades out over `blend_radius` outside the box. Where areas overlap, the one with the
+/// higher `priority` takes its share first and the rest is split by proximity. Once any area
+/// exists, reflections are only heard inside areas, and only while
+/// [`ReflectionConfig::enabled`](crate::reflections::ReflectionConfig::enabled) is on.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct AudioArea {
+    /// Reverb baked with [`BakeVariation::Reverb`](crate::probe::BakeVariation::Reverb).
+    pub reverb_ir: Handle<BakedDataAsset>,
+    pub half_extents: Vec3,
+    pub blend_radius: f32,
+    pub priority: i32,
+}
+
+impl AudioArea {
+    /// How far `position` is into the area placed at `transform`, 1 inside the box fading to 0 at
+    /// `blend_radius` outside of it.
+    pub fn weight(&self, transform: &GlobalTransform, position: Vec3) -> f32 {
+        let local = transform.affine().inverse().transform_point3(position);
+        let outside = (local.abs() - self.half_extents).max(Vec3::ZERO).length();
+        if self.blend_radius <= 0.0 {
+            return if outside > 0.0 { 0.0 } else { 1.0 };
+        }
+
+        (1.0 - outside / self.blend_radius).clamp(0.0, 1.0)
+    }
+}
+
+/// The [`AudioArea`]s the [`PrimaryListener`] is currently blending between.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct ListenerReverbState {
+    /// Each area's share of the reverb, highest priority first.
+    pub blends: Vec<(Entity, f32)>,
+    /// The summed shares, what reflections are scaled by.
+    pub wet: f32,
+}
+
+impl Default for ListenerReverbState {
+    fn default() -> Self {
+        Self {
+            blends: Vec::new(),
+            wet: 1.0,
+        }
+    }
+}
+
+impl ListenerReverbState {
+    /// The area with the largest share, whose baked reverb is simulated.
+    pub fn dominant(&self) -> Option<Entity> {
+        self.blends
+            .iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .filter(|(_, share)| *share > 0.0)
+            .map(|(entity, _)| *entity)
+    }
+}
+
+pub fn update_listener_reverb(
+    mut state: ResMut<ListenerReverbState>,
+    settings: Res<SpatialAudioSettings>,
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    areas: Query<(Entity, &AudioArea, &GlobalTransform)>,
+) {
+    let Some(listener) = listener.iter().next().map(GlobalTransform::translation) else {
+        return;
+    };
+
+    state.blends.clear();
+    if areas.is_empty() {
+        // Without areas the reflections are left as simulated.
+        state.wet = 1.0;
+    } else {
+        let mut weights: Vec<_> = areas
+            .iter()
+            .map(|(entity, area, transform)| {
+                (entity, area.priority, area.weight(transform, listener))
+            })
+            .filter(|(.., weight)| *weight > 0.0)
+            .collect();
+        weights.sort_by(|(_, a_priority, a), (_, b_priority, b)| {
+            b_priority.cmp(a_priority).then(b.total_cmp(a))
+        });
+
+        let mut remaining = 1.0;
+        for (entity, _, weight) in weights {
+            state.blends.push((entity, weight * remaining));
+            remaining *= 1.0 - weight;
+        }
+        state.wet = 1.0 - remaining;
+    }
+
+    settings.reverb_wet.store(state.wet);
+}
@@ -0,0 +1,293 @@
+use bevy::{
+    asset::{io::Reader, Asset, AssetLoader, Handle, LoadContext},
+    prelude::{Component, Reflect, ReflectComponent},
+};
+use steam_audio::simulation::source::{AirAbsorptionModel, Directivity};
+
+use crate::settings::GlobalAudioSettings;
+
+/// How a source's volume falls off with distance from the listener.
+///
+/// Sources without this component use [`DistanceAttenuation::Physical`].
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub enum DistanceAttenuation {
+    /// Steam Audio's default inverse distance model.
+    #[default]
+    Physical,
+    /// Full volume up to `min_distance`, then `(min_distance / distance)^rolloff`.
+    ///
+    /// The gain is held at its `max_distance` value past `max_distance`, or faded linearly to
+    /// silence between the two distances when `linear_clamp` is set.
+    Curve {
+        min_distance: f32,
+        max_distance: f32,
+        rolloff: f32,
+        linear_clamp: bool,
+    },
+}
+
+impl DistanceAttenuation {
+    /// Gain for a listener `distance` away, `None` when Steam Audio should calculate it.
+    ///
+    /// Never exceeds 1.0, even when source and listener share a position.
+    pub fn gain(&self, distance: f32) -> Option<f32> {
+        match *self {
+            Self::Physical => None,
+            Self::Curve {
+                min_distance,
+                max_distance,
+                rolloff,
+                linear_clamp,
+            } => {
+                if distance <= min_distance || min_distance <= 0.0 {
+                    return Some(1.0);
+                }
+
+                let distance = distance.min(max_distance.max(min_distance));
+                let mut gain = (min_distance / distance).powf(rolloff);
+                if linear_clamp && max_distance > min_distance {
+                    gain *= 1.0 - (distance - min_distance) / (max_distance - min_distance);
+                }
+
+                Some(gain.clamp(0.0, 1.0))
+            }
+        }
+    }
+}
+
+/// The radius of a large source like an engine or a crowd, sound is attenuated from its surface
+/// instead of its centre.
+///
+/// Inside the radius the source is heard at full level and comes from all around the listener,
+/// its direction fading out towards the centre. Sources without it are points.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct SourceRadius(pub f32);
+
+impl SourceRadius {
+    /// Distance from a listener `distance` away from the centre to the surface, `0.0` inside.
+    pub fn effective_distance(&self, distance: f32) -> f32 {
+        (distance - self.0.max(0.0)).max(0.0)
+    }
+
+    /// How much of the source's direction a listener `distance` away from the centre hears,
+    /// `1.0` outside the radius down to `0.0` at the centre.
+    pub fn directionality(&self, distance: f32) -> f32 {
+        if self.0 <= 0.0 {
+            return 1.0;
+        }
+
+        (distance / self.0).clamp(0.0, 1.0)
+    }
+}
+
+/// How much quieter a source is away from the direction it faces, Steam Audio's weighted
+/// dipole.
+///
+/// `dipole_weight` blends from omnidirectional at `0.0` over a cardioid at `0.5` to a figure
+/// eight at `1.0`, `dipole_power` sharpens the lobe. Sources without it are omnidirectional.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AudioDirectivity {
+    pub dipole_weight: f32,
+    pub dipole_power: f32,
+}
+
+impl Default for AudioDirectivity {
+    fn default() -> Self {
+        Self {
+            dipole_weight: 0.0,
+            dipole_power: 1.0,
+        }
+    }
+}
+
+impl AudioDirectivity {
+    /// Gain towards a listener at an angle with cosine `cos` to the direction the source faces.
+    pub fn gain(&self, cos: f32) -> f32 {
+        ((1.0 - self.dipole_weight) + self.dipole_weight * cos)
+            .abs()
+            .powf(self.dipole_power)
+    }
+}
+
+impl From<AudioDirectivity> for Directivity {
+    fn from(directivity: AudioDirectivity) -> Self {
+        Self {
+            dipole_weight: directivity.dipole_weight,
+            dipole_power: directivity.dipole_power,
+        }
+    }
+}
+
+/// How air absorption is simulated for a source, sources without it use
+/// [`AirAbsorption::Default`].
+///
+/// An [`AirAbsorptionOverride`] replaces the simulated absorption altogether.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub enum AirAbsorption {
+    /// The coefficients of [`GlobalAudioSettings`], following its
+    /// [`EnvironmentPreset`](crate::settings::EnvironmentPreset).
+    #[default]
+    Default,
+    /// Nothing is absorbed, however far away the source is.
+    Disabled,
+    /// Absorption coefficients per meter for the low, mid and high bands.
+    Custom { low: f32, mid: f32, high: f32 },
+}
+
+impl AirAbsorption {
+    /// The model the source is simulated with.
+    pub fn model(&self, global: &GlobalAudioSettings) -> AirAbsorptionModel {
+        match *self {
+            Self::Default => global.air_absorption_model(),
+            Self::Disabled => AirAbsorptionModel::Exponential {
+                coefficients: [0.0; 3],
+            },
+            Self::Custom { low, mid, high } => AirAbsorptionModel::Exponential {
+                coefficients: [low, mid, high],
+            },
+        }
+    }
+}
+
+/// Fixed air absorption of a source, replacing the distance based absorption of
+/// [`GlobalAudioSettings`](crate::settings::GlobalAudioSettings).
+///
+/// Each band is the fraction absorbed, `0.0` leaves it untouched and `1.0` silences it. Handy for
+/// sounds that should always be muffled, like underwater ones, or always bright, like lasers.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AirAbsorptionOverride {
+    pub low: f32,
+    pub mid: f32,
+    pub high: f32,
+}
+
+impl AirAbsorptionOverride {
+    /// Gains of the low, mid and high bands.
+    pub fn gains(&self) -> [f32; 3] {
+        [self.low, self.mid, self.high].map(|absorbed| (1.0 - absorbed).clamp(0.0, 1.0))
+    }
+}
+
+/// An artist authored roll-off, `(distance, gain)` points linearly interpolated in between.
+///
+/// The gain before the first point and after the last is held. As a component it's used
+/// directly, loaded from a `.attenuation.json` file of `[[distance, gain], ...]` it's used
+/// through an [`AttenuationCurveAsset`]. Either overrides [`DistanceAttenuation`].
+#[derive(Asset, Component, Reflect, Debug, Default, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct DistanceAttenuationCurve {
+    points: Vec<(f32, f32)>,
+}
+
+impl DistanceAttenuationCurve {
+    pub fn new(mut points: Vec<(f32, f32)>) -> Self {
+        points.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self { points }
+    }
+
+    /// The points sorted by distance.
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Gain for a listener `distance` away, `None` for a curve without points.
+    pub fn gain(&self, distance: f32) -> Option<f32> {
+        let after = self.points.partition_point(|(point, _)| *point <= distance);
+
+        let gain = match (after.checked_sub(1), self.points.get(after)) {
+            (Some(before), Some(&(to_distance, to_gain))) => {
+                let (from_distance, from_gain) = self.points[before];
+                let t = (distance - from_distance) / (to_distance - from_distance);
+                from_gain + (to_gain - from_gain) * t
+            }
+            (Some(before), None) => self.points[before].1,
+            (None, Some(&(_, first))) => first,
+            (None, None) => return None,
+        };
+
+        Some(gain.max(0.0))
+    }
+}
+
+/// A [`DistanceAttenuationCurve`] loaded through the asset server, the source uses its
+/// [`DistanceAttenuation`] until it has loaded.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct AttenuationCurveAsset(pub Handle<DistanceAttenuationCurve>);
+
+#[derive(Default)]
+pub struct DistanceAttenuationCurveLoader;
+
+impl AssetLoader for DistanceAttenuationCurveLoader {
+    type Asset = DistanceAttenuationCurve;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        let points: Vec<(f32, f32)> = serde_json::from_slice(&data)?;
+        Ok(DistanceAttenuationCurve::new(points))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["attenuation.json"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolloff_follows_the_exponent() {
+        let attenuation = DistanceAttenuation::Curve {
+            min_distance: 2.0,
+            max_distance: 100.0,
+            rolloff: 1.5,
+            linear_clamp: false,
+        };
+
+        let gain = attenuation.gain(4.0).unwrap();
+        assert!((gain - 0.5f32.powf(1.5)).abs() < 1e-6, "gain {gain}");
+        // Held past the max distance.
+        assert_eq!(attenuation.gain(200.0), attenuation.gain(100.0));
+        assert_eq!(DistanceAttenuation::Physical.gain(4.0), None);
+    }
+
+    #[test]
+    fn rolloff_never_amplifies() {
+        let attenuation = DistanceAttenuation::Curve {
+            min_distance: 1.0,
+            max_distance: 10.0,
+            rolloff: 2.0,
+            linear_clamp: true,
+        };
+
+        // Source and listener in the same place.
+        assert_eq!(attenuation.gain(0.0), Some(1.0));
+        assert_eq!(attenuation.gain(0.5), Some(1.0));
+        assert_eq!(attenuation.gain(10.0), Some(0.0));
+    }
+
+    #[test]
+    fn curve_is_interpolated_between_points() {
+        // Flat up to 5 units, then falling to silence at 9.
+        let curve = DistanceAttenuationCurve::new(vec![(9.0, 0.0), (0.0, 1.0), (5.0, 1.0)]);
+
+        assert_eq!(curve.gain(3.0), Some(1.0));
+        assert_eq!(curve.gain(7.0), Some(0.5));
+        assert_eq!(curve.gain(20.0), Some(0.0));
+        assert_eq!(DistanceAttenuationCurve::default().gain(3.0), None);
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::{
+    Added, Changed, Component, Or, Query, Reflect, ReflectComponent, ReflectResource,
+    RemovedComponents, Resource,
+};
+use steam_audio::hrtf::HRTFInterpolation;
+
+use crate::{params::Snapshot, playback::SpatialAudioSource};
+
+/// Quality settings of the binaural effect.
+///
+/// As a resource it's the default for newly started voices, as a component it overrides the
+/// default for one source and can be changed while it plays.
+#[derive(Component, Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component, Resource)]
+pub struct BinauralConfig {
+    /// `Nearest` is cheaper, `Bilinear` avoids audible steps as the source moves.
+    #[reflect(ignore)]
+    pub interpolation: HRTFInterpolation,
+    /// How much of the HRTF filtering is applied, `0.0` leaves the signal unfiltered.
+    pub spatial_blend: f32,
+}
+
+impl Default for BinauralConfig {
+    fn default() -> Self {
+        Self {
+            interpolation: HRTFInterpolation::Bilinear,
+            spatial_blend: 1.0,
+        }
+    }
+}
+
+impl Snapshot for BinauralConfig {
+    const WORDS: usize = 2;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words[0] = match self.interpolation {
+            HRTFInterpolation::Nearest => 0.0,
+            HRTFInterpolation::Bilinear => 1.0,
+        };
+        words[1] = self.spatial_blend;
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        let interpolation = if words[0] == 0.0 {
+            HRTFInterpolation::Nearest
+        } else {
+            HRTFInterpolation::Bilinear
+        };
+
+        Self {
+            interpolation,
+            spatial_blend: words[1],
+        }
+    }
+}
+
+pub fn update_binaural_config(
+    query: Query<(&SpatialAudioSource, &BinauralConfig), Changed<BinauralConfig>>,
+) {
+    for (source, config) in query.iter() {
+        source.voice.binaural.store(*config);
+    }
+}
+
+/// Applies Steam Audio's near-field correction to sources closer to the listener than
+/// `threshold` meters, where the HRTF alone makes them sound too far away.
+///
+/// The correction is crossfaded in between `0.9` and `1.0` of the threshold so a source crossing
+/// it doesn't pop. It only affects the binaural stage, Ambisonics and panning are left as is.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct NearFieldCorrection {
+    pub enabled: bool,
+    pub threshold: f32,
+}
+
+impl Default for NearFieldCorrection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 1.0,
+        }
+    }
+}
+
+impl NearFieldCorrection {
+    /// How much of the corrected signal is heard at `distance`, from `0.0` to `1.0`.
+    pub(crate) fn weight(threshold: f32, distance: f32) -> f32 {
+        if threshold <= 0.0 {
+            return 0.0;
+        }
+        let band = threshold * 0.1;
+        ((threshold - distance) / band).clamp(0.0, 1.0)
+    }
+}
+
+/// Hands the voice its threshold, `0.0` while the correction is disabled or removed.
+pub fn update_near_field(
+    query: Query<
+        (&SpatialAudioSource, &NearFieldCorrection),
+        Or<(Changed<NearFieldCorrection>, Added<SpatialAudioSource>)>,
+    >,
+    sources: Query<&SpatialAudioSource>,
+    mut removed: RemovedComponents<NearFieldCorrection>,
+) {
+    for source in sources.iter_many(removed.read()) {
+        source.voice.near_field_threshold.store(0.0);
+    }
+
+    for (source, correction) in query.iter() {
+        let threshold = if correction.enabled {
+            correction.threshold.max(0.0)
+        } else {
+            0.0
+        };
+        source.voice.near_field_threshold.store(threshold);
+    }
+}
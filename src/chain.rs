@@ -0,0 +1,454 @@
+use bevy::{
+    log::warn,
+    math::Vec3,
+    prelude::{Component, Reflect, ReflectComponent},
+};
+use steam_audio::{
+    hrtf::{AudioSettings, HRTF},
+    prelude::{
+        AmbisonicsDecodeEffect, AmbisonicsDecodeParams, AmbisonicsEncodeEffect,
+        AmbisonicsEncodeParams, BinauralEffect, BinauralParams, Context, DeinterleavedFrame,
+        DirectEffect, DirectEffectParams, ReflectionEffectParams,
+    },
+};
+
+use crate::{
+    ambisonics::AmbisonicsOrder,
+    coords::bevy_to_phonon,
+    eq::{HeadphoneEqFilter, HeadphoneEqPreset},
+    output::OutputMode,
+    reflections::{ReflectionPipeline, ReflectionTarget},
+    source::SourceOrientation,
+};
+
+/// Replaces the fixed direct → binaural rendering of a voice with these stages, run in order.
+///
+/// Each stage takes the channels the one before it produced, stages that can't are skipped with
+/// a warning. The last stage's channels are mapped onto the [`OutputMode`], mono ending up on
+/// the front left and right. Read when the voice starts.
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct EffectChain(pub Vec<AudioEffect>);
+
+/// A stage of an [`EffectChain`].
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub enum AudioEffect {
+    /// Distance attenuation, air absorption, directivity and transmission, on any channels.
+    Direct,
+    /// Spatializes mono through the HRTF into stereo.
+    Binaural,
+    /// Mixes the simulated reflections of mono into the shared Ambisonics bed, passing it
+    /// through. Does nothing unless
+    /// [`ReflectionConfig::enabled`](crate::reflections::ReflectionConfig::enabled) was on as the
+    /// voice started.
+    Reverb,
+    /// Encodes mono into a sound field of this order.
+    AmbisonicsEncode(AmbisonicsOrder),
+    /// Decodes a sound field for the listener, through the HRTF or to the speakers.
+    AmbisonicsDecode,
+    /// The listener's [`HeadphoneEq`](crate::eq::HeadphoneEq), on stereo.
+    Eq,
+    /// Scales every channel.
+    Gain(f32),
+}
+
+/// What the stages need to render one block, gathered by the decoder.
+pub(crate) struct StageParams {
+    pub(crate) direct: DirectEffectParams,
+    pub(crate) binaural: BinauralParams,
+    /// World space direction from the listener to the source.
+    pub(crate) direction: Vec3,
+    pub(crate) listener: SourceOrientation,
+    pub(crate) reflections: Option<ReflectionEffectParams>,
+    pub(crate) reverb_gain: f32,
+    pub(crate) eq_gains: [f32; 3],
+}
+
+/// A DSP stage of an [`EffectChain`], built once when the voice starts.
+pub(crate) trait AudioStage: Send {
+    /// Picks up the parameters of the next block.
+    fn update(&mut self, _params: &StageParams) {}
+
+    /// Rebuilds whatever was built from the HRTF after it was swapped.
+    fn set_hrtf(&mut self, _context: &Context, _hrtf: &HRTF) {}
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame);
+}
+
+/// Where a voice's [`AudioEffect::Reverb`] stages mix to.
+pub(crate) type ReverbTarget = Option<ReflectionTarget>;
+
+/// The built stages of an [`EffectChain`], along with the channels each one outputs.
+pub(crate) struct StageChain {
+    audio_settings: AudioSettings,
+    stages: Vec<(Box<dyn AudioStage>, usize)>,
+}
+
+impl StageChain {
+    pub(crate) fn new(
+        chain: &EffectChain,
+        context: &Context,
+        audio_settings: &AudioSettings,
+        hrtf: &HRTF,
+        output_mode: OutputMode,
+        reverb: &ReverbTarget,
+    ) -> Self {
+        let mut stages = Vec::new();
+        // Voices decode mono.
+        let mut channels = 1;
+
+        for effect in &chain.0 {
+            let stage = build_stage(
+                *effect,
+                channels,
+                context,
+                audio_settings,
+                hrtf,
+                output_mode,
+                reverb,
+            );
+            match stage {
+                Ok((stage, output)) => {
+                    stages.push((stage, output));
+                    channels = output;
+                }
+                Err(err) => warn!("Skipping {effect:?} in the EffectChain: {err}"),
+            }
+        }
+
+        Self {
+            audio_settings: audio_settings.clone(),
+            stages,
+        }
+    }
+
+    pub(crate) fn set_hrtf(&mut self, context: &Context, hrtf: &HRTF) {
+        for (stage, _) in &mut self.stages {
+            stage.set_hrtf(context, hrtf);
+        }
+    }
+
+    /// Runs the mono `input` through every stage, returning one block per channel of the
+    /// `output_mode`.
+    pub(crate) fn process(
+        &mut self,
+        params: &StageParams,
+        input: &mut DeinterleavedFrame,
+        output_mode: OutputMode,
+    ) -> Vec<Vec<f32>> {
+        // The first stage reads the decoder's input buffer in place.
+        let mut buffer: Option<DeinterleavedFrame> = None;
+        for (stage, channels) in &mut self.stages {
+            let mut output = DeinterleavedFrame::new(
+                self.audio_settings.frame_size() as usize,
+                *channels,
+                self.audio_settings.sampling_rate(),
+            );
+            stage.update(params);
+            stage.process(buffer.as_mut().unwrap_or(&mut *input), &mut output);
+            buffer = Some(output);
+        }
+
+        let channels = output_mode.channels() as usize;
+        let mut blocks = match buffer {
+            Some(buffer) => buffer.current_frame,
+            None => input.current_frame.clone(),
+        };
+        if blocks.len() == 1 {
+            let mono = blocks.remove(0);
+            blocks = vec![mono.clone(), mono];
+        }
+        let frame_size = blocks.first().map_or(0, Vec::len);
+        blocks.resize(channels, vec![0.0; frame_size]);
+        blocks
+    }
+}
+
+/// Builds the stage for `effect` taking `channels`, along with the channels it outputs.
+fn build_stage(
+    effect: AudioEffect,
+    channels: usize,
+    context: &Context,
+    audio_settings: &AudioSettings,
+    hrtf: &HRTF,
+    output_mode: OutputMode,
+    reverb: &ReverbTarget,
+) -> Result<(Box<dyn AudioStage>, usize), String> {
+    let expect = |expected: usize| {
+        if channels == expected {
+            Ok(())
+        } else {
+            Err(format!("takes {expected} channels, got {channels}"))
+        }
+    };
+
+    match effect {
+        AudioEffect::Direct => {
+            let effect = DirectEffect::new(context, audio_settings, channels as u32)
+                .map_err(|err| format!("{err:?}"))?;
+            let stage = DirectStage {
+                audio_settings: audio_settings.clone(),
+                effect,
+                params: DirectEffectParams::default(),
+            };
+            Ok((Box::new(stage), channels))
+        }
+        AudioEffect::Binaural => {
+            expect(1)?;
+            let effect = BinauralEffect::new(context, audio_settings, hrtf)
+                .map_err(|err| format!("{err:?}"))?;
+            let stage = BinauralStage {
+                audio_settings: audio_settings.clone(),
+                effect,
+                params: BinauralParams::default(),
+            };
+            Ok((Box::new(stage), 2))
+        }
+        AudioEffect::Reverb => {
+            expect(1)?;
+            let pipeline = reverb
+                .clone()
+                .map(|target| ReflectionPipeline::new(context, audio_settings, target));
+            let stage = ReverbStage {
+                audio_settings: audio_settings.clone(),
+                pipeline,
+                params: None,
+                gain: 0.0,
+            };
+            Ok((Box::new(stage), 1))
+        }
+        AudioEffect::AmbisonicsEncode(order) => {
+            expect(1)?;
+            let effect = AmbisonicsEncodeEffect::new(context, audio_settings, order.order())
+                .map_err(|err| format!("{err:?}"))?;
+            let stage = AmbisonicsEncodeStage {
+                order,
+                effect,
+                direction: Vec3::ZERO,
+            };
+            Ok((Box::new(stage), order.channels()))
+        }
+        AudioEffect::AmbisonicsDecode => {
+            let order = (1..=3)
+                .map(AmbisonicsOrder)
+                .find(|order| order.channels() == channels)
+                .ok_or_else(|| format!("takes a sound field, got {channels} channels"))?;
+            let stage = AmbisonicsDecodeStage {
+                audio_settings: audio_settings.clone(),
+                order,
+                output_mode,
+                effect: ambisonics_decode_effect(
+                    context,
+                    audio_settings,
+                    hrtf,
+                    order,
+                    output_mode,
+                )?,
+                listener: SourceOrientation::default(),
+            };
+            Ok((Box::new(stage), output_mode.channels() as usize))
+        }
+        AudioEffect::Eq => {
+            expect(2)?;
+            let filter =
+                HeadphoneEqFilter::new(context, audio_settings, HeadphoneEqPreset::Flat.gains());
+            Ok((Box::new(EqStage(filter)), 2))
+        }
+        AudioEffect::Gain(gain) => Ok((Box::new(GainStage(gain)), channels)),
+    }
+}
+
+fn ambisonics_decode_effect(
+    context: &Context,
+    audio_settings: &AudioSettings,
+    hrtf: &HRTF,
+    order: AmbisonicsOrder,
+    output_mode: OutputMode,
+) -> Result<AmbisonicsDecodeEffect, String> {
+    AmbisonicsDecodeEffect::new(
+        context,
+        audio_settings,
+        hrtf,
+        output_mode.speaker_layout(),
+        order.order(),
+    )
+    .map_err(|err| format!("{err:?}"))
+}
+
+/// Moves the block out of `input`, for effects that take their input by value.
+fn take_frame(
+    input: &mut DeinterleavedFrame,
+    audio_settings: &AudioSettings,
+) -> DeinterleavedFrame {
+    let replacement = DeinterleavedFrame::new(
+        audio_settings.frame_size() as usize,
+        input.current_frame.len(),
+        audio_settings.sampling_rate(),
+    );
+    std::mem::replace(input, replacement)
+}
+
+fn copy_frame(input: &DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+    for (output, input) in output.current_frame.iter_mut().zip(&input.current_frame) {
+        output.clone_from(input);
+    }
+}
+
+struct DirectStage {
+    audio_settings: AudioSettings,
+    effect: DirectEffect,
+    params: DirectEffectParams,
+}
+
+impl AudioStage for DirectStage {
+    fn update(&mut self, params: &StageParams) {
+        self.params = params.direct.clone();
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        let input = take_frame(input, &self.audio_settings);
+        self.effect
+            .apply_to_buffer(&self.params, input, output)
+            .unwrap();
+    }
+}
+
+struct BinauralStage {
+    audio_settings: AudioSettings,
+    effect: BinauralEffect,
+    params: BinauralParams,
+}
+
+impl AudioStage for BinauralStage {
+    fn update(&mut self, params: &StageParams) {
+        self.params = params.binaural.clone();
+    }
+
+    fn set_hrtf(&mut self, context: &Context, hrtf: &HRTF) {
+        match BinauralEffect::new(context, &self.audio_settings, hrtf) {
+            Ok(effect) => self.effect = effect,
+            Err(err) => warn!("Could not rebuild steam audio binaural effect: {err:?}"),
+        }
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        self.effect
+            .apply_to_buffer(&self.params, input, output)
+            .unwrap();
+    }
+}
+
+struct ReverbStage {
+    audio_settings: AudioSettings,
+    pipeline: Option<ReflectionPipeline>,
+    /// `None` until the first reflection simulation finishes.
+    params: Option<ReflectionEffectParams>,
+    gain: f32,
+}
+
+impl AudioStage for ReverbStage {
+    fn update(&mut self, params: &StageParams) {
+        if let Some(reflections) = &params.reflections {
+            self.params = Some(reflections.clone());
+        }
+        self.gain = params.reverb_gain;
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        copy_frame(input, output);
+
+        let (Some(pipeline), Some(params)) = (&mut self.pipeline, &self.params) else {
+            return;
+        };
+        let mut reflected = DeinterleavedFrame::new(
+            self.audio_settings.frame_size() as usize,
+            1,
+            self.audio_settings.sampling_rate(),
+        );
+        copy_frame(input, &mut reflected);
+        pipeline.apply(params, &mut reflected, self.gain);
+    }
+}
+
+struct AmbisonicsEncodeStage {
+    order: AmbisonicsOrder,
+    effect: AmbisonicsEncodeEffect,
+    direction: Vec3,
+}
+
+impl AudioStage for AmbisonicsEncodeStage {
+    fn update(&mut self, params: &StageParams) {
+        self.direction = params.direction;
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        let params = AmbisonicsEncodeParams {
+            direction: bevy_to_phonon(self.direction),
+            order: self.order.order(),
+        };
+        self.effect.apply_to_buffer(&params, input, output).unwrap();
+    }
+}
+
+struct AmbisonicsDecodeStage {
+    audio_settings: AudioSettings,
+    order: AmbisonicsOrder,
+    output_mode: OutputMode,
+    effect: AmbisonicsDecodeEffect,
+    listener: SourceOrientation,
+}
+
+impl AudioStage for AmbisonicsDecodeStage {
+    fn update(&mut self, params: &StageParams) {
+        self.listener = params.listener;
+    }
+
+    fn set_hrtf(&mut self, context: &Context, hrtf: &HRTF) {
+        match ambisonics_decode_effect(
+            context,
+            &self.audio_settings,
+            hrtf,
+            self.order,
+            self.output_mode,
+        ) {
+            Ok(effect) => self.effect = effect,
+            Err(err) => warn!("Could not rebuild steam audio ambisonics decode effect: {err}"),
+        }
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        let params = AmbisonicsDecodeParams {
+            order: self.order.order(),
+            orientation: self.listener.into(),
+            binaural: self.output_mode == OutputMode::Binaural,
+        };
+        self.effect.apply_to_buffer(&params, input, output).unwrap();
+    }
+}
+
+struct EqStage(HeadphoneEqFilter);
+
+impl AudioStage for EqStage {
+    fn update(&mut self, params: &StageParams) {
+        self.0.set_gains(params.eq_gains);
+    }
+
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        copy_frame(input, output);
+        if let [left, right] = &mut output.current_frame[..] {
+            self.0.apply(left, right);
+        }
+    }
+}
+
+struct GainStage(f32);
+
+impl AudioStage for GainStage {
+    fn process(&mut self, input: &mut DeinterleavedFrame, output: &mut DeinterleavedFrame) {
+        for (output, input) in output.current_frame.iter_mut().zip(&input.current_frame) {
+            for (output, input) in output.iter_mut().zip(input) {
+                *output = input * self.0;
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    log::warn,
+    math::{primitives::Sphere, Vec3},
+    prelude::{Component, Meshable, Reflect, ReflectComponent, RemovedComponents, ResMut},
+};
+
+use crate::{geometry::SteamAudioScene, mesh::AudioMesh};
+
+/// Adds the entity's physics collider to the Steam Audio scene, so geometry that already exists
+/// for physics doesn't need a render mesh too.
+///
+/// Cuboids, balls, trimeshes, convex hulls and heightfields are supported, balls are
+/// approximated with an icosphere. Uses the [`AudioMaterial`](crate::material::AudioMaterial)
+/// of the entity like an [`AudioObstacle`](crate::scene::AudioObstacle).
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct AudioFromCollider;
+
+/// Subdivisions of the icosphere standing in for ball colliders.
+const BALL_SUBDIVISIONS: u32 = 1;
+
+fn ball(radius: f32) -> Option<AudioMesh> {
+    let mesh = Sphere::new(radius).mesh().ico(BALL_SUBDIVISIONS).ok()?;
+    AudioMesh::try_from(mesh).ok()
+}
+
+fn triangles(vertices: impl Iterator<Item = Vec3>, triangles: Vec<[u32; 3]>) -> AudioMesh {
+    AudioMesh {
+        vertices: vertices.collect(),
+        triangles,
+        ..Default::default()
+    }
+}
+
+/// Removes the static mesh of despawned entities and entities that lost [`AudioFromCollider`].
+pub fn remove_collider_obstacles(
+    mut scene: ResMut<SteamAudioScene>,
+    mut removed: RemovedComponents<AudioFromCollider>,
+) {
+    for entity in removed.read() {
+        scene.remove(entity);
+    }
+}
+
+/// Both physics engines wrap a parry shape, only where they keep it differs.
+macro_rules! collider_backend {
+    ($module:ident, $feature:literal, $physics:ident, $collider:ident => $shape:expr) => {
+        #[cfg(feature = $feature)]
+        pub mod $module {
+            use bevy::prelude::{
+                Added, Changed, Entity, GlobalTransform, Or, Query, Res, ResMut, With,
+            };
+            use $physics::{parry::shape::TypedShape, prelude::Collider};
+
+            use super::*;
+            use crate::material::{AudioMaterial, MaterialLibrary};
+
+            fn to_audio_mesh(collider: &Collider) -> Option<AudioMesh> {
+                let point = |point: &$physics::parry::math::Point<f32>| {
+                    Vec3::new(point.x, point.y, point.z)
+                };
+
+                let $collider = collider;
+                let shape = $shape;
+                match shape.as_typed_shape() {
+                    TypedShape::Cuboid(cuboid) => {
+                        let (vertices, indices) = cuboid.to_trimesh();
+                        Some(triangles(vertices.iter().map(point), indices))
+                    }
+                    TypedShape::Ball(shape) => ball(shape.radius),
+                    TypedShape::TriMesh(trimesh) => Some(triangles(
+                        trimesh.vertices().iter().map(point),
+                        trimesh.indices().to_vec(),
+                    )),
+                    TypedShape::ConvexPolyhedron(hull) => {
+                        let (vertices, indices) = hull.to_trimesh();
+                        Some(triangles(vertices.iter().map(point), indices))
+                    }
+                    TypedShape::HeightField(heightfield) => {
+                        let (vertices, indices) = heightfield.to_trimesh();
+                        Some(triangles(vertices.iter().map(point), indices))
+                    }
+                    _ => None,
+                }
+            }
+
+            /// Adds new and changed [`AudioFromCollider`] colliders to the scene.
+            pub fn register_collider_obstacles(
+                mut scene: ResMut<SteamAudioScene>,
+                library: Res<MaterialLibrary>,
+                colliders: Query<
+                    (Entity, &Collider, &GlobalTransform, Option<&AudioMaterial>),
+                    (
+                        With<AudioFromCollider>,
+                        Or<(Added<AudioFromCollider>, Changed<Collider>)>,
+                    ),
+                >,
+            ) {
+                for (entity, collider, transform, material) in colliders.iter() {
+                    let Some(audio_mesh) = to_audio_mesh(collider) else {
+                        warn!("Could not add collider {entity:?} to the scene, unsupported shape");
+                        continue;
+                    };
+
+                    let material = material.cloned().unwrap_or_default().resolve(&library);
+                    scene.insert(
+                        entity,
+                        &audio_mesh.transformed(transform).with_material(material),
+                    );
+                }
+            }
+        }
+    };
+}
+
+collider_backend!(rapier, "rapier", bevy_rapier3d, collider => &collider.raw);
+collider_backend!(avian, "avian", avian3d, collider => collider.shape_scaled());
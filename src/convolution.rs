@@ -0,0 +1,291 @@
+use bevy::{
+    asset::{io::Reader, Asset, AssetEvent, AssetId, AssetLoader, Assets, Handle, LoadContext},
+    ecs::entity::EntityHashMap,
+    prelude::{
+        Component, Entity, EventReader, Local, Query, Ref, Reflect, ReflectComponent,
+        RemovedComponents, Res, TypePath,
+    },
+};
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::sync::Arc;
+
+use crate::playback::SpatialAudioSource;
+
+/// A recorded room impulse response, loaded from a `.ir.wav` file and averaged down to mono.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct ConvolutionReverbIR {
+    pub samples: Arc<[f32]>,
+    pub sample_rate: u32,
+}
+
+impl ConvolutionReverbIR {
+    /// The samples linearly resampled to `sample_rate`.
+    fn resampled(&self, sample_rate: u32) -> Vec<f32> {
+        if self.sample_rate == sample_rate || self.samples.is_empty() {
+            return self.samples.to_vec();
+        }
+
+        let step = self.sample_rate as f64 / sample_rate as f64;
+        let len = (self.samples.len() as f64 / step).ceil() as usize;
+        (0..len)
+            .map(|index| {
+                let position = index as f64 * step;
+                let before = position as usize;
+                let t = (position - before as f64) as f32;
+                let from = self.samples[before.min(self.samples.len() - 1)];
+                let to = self.samples[(before + 1).min(self.samples.len() - 1)];
+                from + (to - from) * t
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct ConvolutionReverbIRLoader;
+
+impl AssetLoader for ConvolutionReverbIRLoader {
+    type Asset = ConvolutionReverbIR;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        let decoder = rodio::Decoder::new(std::io::Cursor::new(data))
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+        let channels = rodio::Source::channels(&decoder).max(1) as usize;
+        let sample_rate = rodio::Source::sample_rate(&decoder);
+        let interleaved: Vec<f32> = decoder
+            .map(|sample| rodio::cpal::Sample::to_f32(&sample))
+            .collect();
+        let samples = interleaved
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        Ok(ConvolutionReverbIR {
+            samples,
+            sample_rate,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ir.wav"]
+    }
+}
+
+/// Sends a source through a [`ConvolutionReverbIR`] at the given wet level, mixed into the front
+/// left and right channels on top of its spatialized signal.
+///
+/// The send is taken after the source's volume, so the reverb doesn't fall off with distance like
+/// the direct path does.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct ConvolutionReverbSend(pub Handle<ConvolutionReverbIR>, pub f32);
+
+/// Uniformly partitioned overlap-add convolution, one block in and out at a time.
+pub(crate) struct Convolver {
+    block: usize,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    /// Spectra of the impulse response, split into blocks.
+    partitions: Vec<Vec<Complex<f32>>>,
+    /// Spectra of the latest input blocks, `history[position]` being the newest.
+    history: Vec<Vec<Complex<f32>>>,
+    position: usize,
+    /// Second half of the last inverse transform, added to the next block.
+    overlap: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl Convolver {
+    pub(crate) fn new(ir: &[f32], block: usize) -> Self {
+        let size = block * 2;
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(size);
+        let inverse = planner.plan_fft_inverse(size);
+
+        let partitions: Vec<_> = ir
+            .chunks(block)
+            .map(|chunk| {
+                let mut spectrum = Self::padded(chunk, size);
+                forward.process(&mut spectrum);
+                spectrum
+            })
+            .collect();
+
+        Self {
+            block,
+            history: vec![vec![Complex::default(); size]; partitions.len().max(1)],
+            partitions,
+            forward,
+            inverse,
+            position: 0,
+            overlap: vec![0.0; block],
+            spectrum: vec![Complex::default(); size],
+        }
+    }
+
+    fn padded(samples: &[f32], size: usize) -> Vec<Complex<f32>> {
+        let mut spectrum = vec![Complex::default(); size];
+        for (bin, sample) in spectrum.iter_mut().zip(samples) {
+            bin.re = *sample;
+        }
+        spectrum
+    }
+
+    /// Convolves the next `input` block, adding `gain` times the result to `output`.
+    pub(crate) fn process(&mut self, input: &[f32], gain: f32, output: &mut [f32]) {
+        let size = self.block * 2;
+        self.position = (self.position + self.history.len() - 1) % self.history.len();
+        let newest = &mut self.history[self.position];
+        newest.fill(Complex::default());
+        for (bin, sample) in newest.iter_mut().zip(input.iter().take(self.block)) {
+            bin.re = *sample;
+        }
+        self.forward.process(newest);
+
+        // The n-th partition of the response meets the input from n blocks ago.
+        self.spectrum.fill(Complex::default());
+        for (index, partition) in self.partitions.iter().enumerate() {
+            let input = &self.history[(self.position + index) % self.history.len()];
+            for ((sum, input), partition) in self.spectrum.iter_mut().zip(input).zip(partition) {
+                *sum += input * partition;
+            }
+        }
+        self.inverse.process(&mut self.spectrum);
+
+        let scale = 1.0 / size as f32;
+        for (index, sample) in output.iter_mut().take(self.block).enumerate() {
+            let wet = self.spectrum[index].re * scale + self.overlap[index];
+            self.overlap[index] = self.spectrum[index + self.block].re * scale;
+            *sample += gain * wet;
+        }
+    }
+}
+
+/// Hands voices the convolver for their [`ConvolutionReverbSend`] once the impulse response has
+/// loaded, and its wet level whenever it changes.
+pub fn update_convolution_reverb(
+    irs: Res<Assets<ConvolutionReverbIR>>,
+    mut events: EventReader<AssetEvent<ConvolutionReverbIR>>,
+    sends: Query<(Entity, Ref<SpatialAudioSource>, Ref<ConvolutionReverbSend>)>,
+    sources: Query<&SpatialAudioSource>,
+    mut removed: RemovedComponents<ConvolutionReverbSend>,
+    // The response each voice's convolver was built from, building one is costly.
+    mut built: Local<EntityHashMap<AssetId<ConvolutionReverbIR>>>,
+) {
+    let loaded: Vec<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for entity in removed.read() {
+        built.remove(&entity);
+        if let Ok(source) = sources.get(entity) {
+            source.voice.convolution_wet.store(0.0);
+        }
+    }
+
+    for (entity, source, send) in sends.iter() {
+        if source.is_added() || send.is_changed() {
+            source.voice.convolution_wet.store(send.1.max(0.0));
+        }
+
+        let id = send.0.id();
+        let stale = source.is_added() || built.get(&entity) != Some(&id) || loaded.contains(&id);
+        if !stale {
+            continue;
+        }
+        let Some(ir) = irs.get(id) else {
+            continue;
+        };
+
+        let audio_settings = &source.voice.audio_settings;
+        let convolver = Convolver::new(
+            &ir.resampled(audio_settings.sampling_rate()),
+            audio_settings.frame_size() as usize,
+        );
+        *source.voice.convolution.lock().unwrap() = Some(Box::new(convolver));
+        built.insert(entity, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `signal` run through a convolver of `ir` block by block.
+    fn convolve(ir: &[f32], block: usize, signal: &[f32]) -> Vec<f32> {
+        let mut convolver = Convolver::new(ir, block);
+        signal
+            .chunks(block)
+            .flat_map(|input| {
+                let mut output = vec![0.0; block];
+                convolver.process(input, 1.0, &mut output);
+                output
+            })
+            .collect()
+    }
+
+    fn signal(len: usize) -> Vec<f32> {
+        (0..len).map(|index| (index as f32 * 0.37).sin()).collect()
+    }
+
+    fn assert_close(output: &[f32], expected: &[f32]) {
+        assert_eq!(output.len(), expected.len());
+        for (index, (output, expected)) in output.iter().zip(expected).enumerate() {
+            assert!(
+                (output - expected).abs() < 1e-4,
+                "sample {index} is {output}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn dirac_impulse_passes_the_signal_through() {
+        let signal = signal(256);
+        assert_close(&convolve(&[1.0], 64, &signal), &signal);
+    }
+
+    #[test]
+    fn taps_are_delayed_and_scaled_copies() {
+        // Taps further apart than a block, so they land in different partitions.
+        let mut ir = vec![0.0; 12];
+        ir[0] = 0.5;
+        ir[5] = 0.25;
+        ir[11] = -0.75;
+        let signal = signal(64);
+
+        let expected: Vec<f32> = (0..signal.len())
+            .map(|index| {
+                ir.iter()
+                    .enumerate()
+                    .filter(|(delay, _)| *delay <= index)
+                    .map(|(delay, tap)| tap * signal[index - delay])
+                    .sum()
+            })
+            .collect();
+        assert_close(&convolve(&ir, 8, &signal), &expected);
+    }
+
+    #[test]
+    fn wet_gain_scales_what_is_added() {
+        let signal = signal(64);
+        let mut convolver = Convolver::new(&[1.0], 64);
+        let mut output = vec![1.0; 64];
+        convolver.process(&signal, 0.5, &mut output);
+
+        let expected: Vec<f32> = signal.iter().map(|sample| 1.0 + sample * 0.5).collect();
+        assert_close(&output, &expected);
+    }
+}
@@ -0,0 +1,108 @@
+//! Conversions from Bevy's space into Steam Audio's.
+//!
+//! Both are right-handed with +X right, +Y up and -Z ahead, so directions carry over as they
+//! are. Positions and distances are scaled from Bevy units to the meters Steam Audio works in,
+//! see [`AudioUnitsPerMeter`](crate::settings::AudioUnitsPerMeter). Everything handed to Steam
+//! Audio goes through here so the mapping lives in one place.
+
+use bevy::{
+    math::{Quat, Vec3},
+    transform::components::GlobalTransform,
+};
+
+use crate::settings::AudioUnitsPerMeter;
+
+/// A Bevy direction in Steam Audio's space.
+pub fn bevy_to_phonon(vector: Vec3) -> [f32; 3] {
+    [vector.x, vector.y, vector.z]
+}
+
+/// A Bevy position in Steam Audio's space, in meters.
+pub fn bevy_position_to_phonon(position: Vec3, units: AudioUnitsPerMeter) -> [f32; 3] {
+    bevy_to_phonon(position / units.get())
+}
+
+/// A velocity in Bevy units per second, in meters per second.
+pub fn bevy_velocity_to_phonon(velocity: Vec3, units: AudioUnitsPerMeter) -> [f32; 3] {
+    bevy_to_phonon(velocity / units.get())
+}
+
+/// A distance in Bevy units, in meters.
+pub fn bevy_distance_to_phonon(distance: f32, units: AudioUnitsPerMeter) -> f32 {
+    distance / units.get()
+}
+
+/// The right, up and ahead axes of a Bevy rotation in Steam Audio's space.
+pub fn bevy_rotation_to_phonon(rotation: Quat) -> [[f32; 3]; 3] {
+    [
+        rotation * Vec3::X,
+        rotation * Vec3::Y,
+        rotation * Vec3::NEG_Z,
+    ]
+    .map(bevy_to_phonon)
+}
+
+/// Direction from `listener` to `position` in the listener's own space, which is what the
+/// binaural and panning effects expect.
+///
+/// Only the listener's rotation is undone, a scaled listener doesn't skew the direction.
+pub fn listener_direction(listener: &GlobalTransform, position: Vec3) -> Vec3 {
+    let (_, rotation, translation) = listener.to_scale_rotation_translation();
+    (rotation.inverse() * (position - translation)).normalize_or_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{EulerRot, Mat3};
+
+    use super::*;
+
+    const UNITS: AudioUnitsPerMeter = AudioUnitsPerMeter(2.5);
+
+    #[test]
+    fn positions_round_trip_through_meters() {
+        let position = Vec3::new(3.0, -1.5, 12.0);
+
+        let meters = Vec3::from(bevy_position_to_phonon(position, UNITS));
+        assert!(meters.abs_diff_eq(position / 2.5, 1e-6));
+        assert!((meters * UNITS.get()).abs_diff_eq(position, 1e-5));
+
+        let velocity = Vec3::from(bevy_velocity_to_phonon(position, UNITS));
+        assert!((velocity * UNITS.get()).abs_diff_eq(position, 1e-5));
+
+        let distance = bevy_distance_to_phonon(position.length(), UNITS);
+        assert!((distance * UNITS.get() - position.length()).abs() < 1e-5);
+        assert!((distance - meters.length()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn invalid_scales_leave_positions_in_units() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        for units in [0.0, -1.0, f32::NAN] {
+            let meters = bevy_position_to_phonon(position, AudioUnitsPerMeter(units));
+            assert_eq!(Vec3::from(meters), position);
+        }
+    }
+
+    #[test]
+    fn rotations_round_trip_through_axes() {
+        let rotation = Quat::from_euler(EulerRot::YXZ, 0.7, -0.3, 1.2);
+
+        let [right, up, ahead] = bevy_rotation_to_phonon(rotation).map(Vec3::from);
+        let back = Quat::from_mat3(&Mat3::from_cols(right, up, -ahead));
+        assert!(back.abs_diff_eq(rotation, 1e-5) || back.abs_diff_eq(-rotation, 1e-5));
+    }
+
+    #[test]
+    fn listener_direction_ignores_scale() {
+        let listener = GlobalTransform::from(
+            bevy::prelude::Transform::from_xyz(1.0, 0.0, 0.0)
+                .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2))
+                .with_scale(Vec3::new(1.0, 3.0, 0.5)),
+        );
+
+        // Turned left, so what's ahead in the world is on the listener's right.
+        let direction = listener_direction(&listener, Vec3::new(1.0, 0.0, -4.0));
+        assert!(direction.abs_diff_eq(Vec3::X, 1e-5), "{direction}");
+    }
+}
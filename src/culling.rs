@@ -0,0 +1,54 @@
+use bevy::prelude::{Component, GlobalTransform, Query, Reflect, ReflectComponent, With};
+use std::sync::atomic::Ordering;
+
+use crate::{playback::SpatialAudioSource, source::PrimaryListener};
+
+/// Past this distance from the listener a source outputs silence without running the effects.
+///
+/// The source fades out linearly over the last 10% of the distance, and back in the same way
+/// when it comes back into range.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct MaxAudibleDistance(pub f32);
+
+impl Default for MaxAudibleDistance {
+    fn default() -> Self {
+        Self(f32::INFINITY)
+    }
+}
+
+impl MaxAudibleDistance {
+    /// Gain for a listener `distance` away, `0.0` once the source is out of range.
+    pub fn gain(&self, distance: f32) -> f32 {
+        if !self.0.is_finite() {
+            return 1.0;
+        }
+
+        let fade = self.0 * 0.1;
+        if fade <= 0.0 {
+            return if distance <= self.0 { 1.0 } else { 0.0 };
+        }
+
+        ((self.0 - distance) / fade).clamp(0.0, 1.0)
+    }
+}
+
+pub fn update_audible(
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    sources: Query<(
+        &SpatialAudioSource,
+        &GlobalTransform,
+        Option<&MaxAudibleDistance>,
+    )>,
+) {
+    let Some(listener) = listener.iter().next().map(GlobalTransform::translation) else {
+        return;
+    };
+
+    for (source, transform, max_distance) in sources.iter() {
+        let max_distance = max_distance.copied().unwrap_or_default();
+        let gain = max_distance.gain(transform.translation().distance(listener));
+        source.voice.distance_gain.store(gain);
+        source.voice.audible.store(gain > 0.0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::{Query, Res, With},
+    time::{Real, Time},
+    utils::Duration,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    reflections::ReflectionState, simulation::SimulationSource, source::SpatialAudioSettings,
+    virtual_voice::VoiceCounts,
+};
+
+/// Timings collected on the audio thread and by the direct simulation, shared by every voice.
+#[derive(Default)]
+pub(crate) struct AudioStats {
+    blocks: AtomicU64,
+    block_nanos: AtomicU64,
+    /// Blocks that took longer to process than they last.
+    underruns: AtomicU64,
+    simulation_nanos: AtomicU64,
+}
+
+impl AudioStats {
+    /// Records a block that took `elapsed` to process and plays for `duration`.
+    pub(crate) fn record_block(&self, elapsed: Duration, duration: Duration) {
+        self.blocks.fetch_add(1, Ordering::Relaxed);
+        self.block_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        if elapsed > duration {
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_simulation(&self, elapsed: Duration) {
+        self.simulation_nanos
+            .store(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Reports Steam Audio timings through Bevy's diagnostics, add it alongside
+/// [`SpatialAudioPlugin`](crate::source::SpatialAudioPlugin).
+pub struct SteamAudioDiagnosticsPlugin;
+
+impl SteamAudioDiagnosticsPlugin {
+    /// Voices rendered through their effects, see [`VoiceCounts`].
+    pub const ACTIVE_VOICES: DiagnosticPath =
+        DiagnosticPath::const_new("steam_audio/active_voices");
+    /// Voices over [`MaxVoices`](crate::virtual_voice::MaxVoices) outputting silence.
+    pub const VIRTUAL_VOICES: DiagnosticPath =
+        DiagnosticPath::const_new("steam_audio/virtual_voices");
+    /// Sources registered with the simulator.
+    pub const ACTIVE_SOURCES: DiagnosticPath =
+        DiagnosticPath::const_new("steam_audio/active_sources");
+    /// Duration of the last direct simulation, in microseconds.
+    pub const SIM_TIME: DiagnosticPath = DiagnosticPath::const_new("steam_audio/sim_time_us");
+    /// Blocks processed by all voices together.
+    pub const BLOCKS_PER_SECOND: DiagnosticPath =
+        DiagnosticPath::const_new("steam_audio/blocks_per_second");
+    /// Average time a voice spends processing one block, in microseconds.
+    pub const BLOCK_TIME: DiagnosticPath = DiagnosticPath::const_new("steam_audio/block_time_us");
+    /// Blocks since the last frame that took longer to process than they play for, each one a
+    /// likely underrun.
+    pub const UNDERRUNS: DiagnosticPath = DiagnosticPath::const_new("steam_audio/underruns");
+    /// Frames since the reflection and pathing results voices are playing were captured.
+    pub const REFLECTION_LATENCY: DiagnosticPath =
+        DiagnosticPath::const_new("steam_audio/reflection_latency_frames");
+}
+
+impl Plugin for SteamAudioDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::ACTIVE_VOICES))
+            .register_diagnostic(Diagnostic::new(Self::VIRTUAL_VOICES))
+            .register_diagnostic(Diagnostic::new(Self::ACTIVE_SOURCES))
+            .register_diagnostic(Diagnostic::new(Self::SIM_TIME).with_suffix("us"))
+            .register_diagnostic(Diagnostic::new(Self::BLOCKS_PER_SECOND))
+            .register_diagnostic(Diagnostic::new(Self::BLOCK_TIME).with_suffix("us"))
+            .register_diagnostic(Diagnostic::new(Self::UNDERRUNS))
+            .register_diagnostic(Diagnostic::new(Self::REFLECTION_LATENCY))
+            .add_systems(Update, update_diagnostics);
+    }
+}
+
+fn update_diagnostics(
+    mut diagnostics: Diagnostics,
+    settings: Res<SpatialAudioSettings>,
+    time: Res<Time<Real>>,
+    voices: Res<VoiceCounts>,
+    reflections: Res<ReflectionState>,
+    sources: Query<(), With<SimulationSource>>,
+) {
+    let stats = &settings.stats;
+
+    diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::ACTIVE_VOICES, || {
+        voices.active as f64
+    });
+    diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::VIRTUAL_VOICES, || {
+        voices.virtualized as f64
+    });
+
+    diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::ACTIVE_SOURCES, || {
+        sources.iter().len() as f64
+    });
+
+    let simulation_nanos = stats.simulation_nanos.load(Ordering::Relaxed);
+    if simulation_nanos > 0 {
+        diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::SIM_TIME, || {
+            simulation_nanos as f64 / 1_000.0
+        });
+    }
+
+    if let Some(latency) = reflections.latency_frames() {
+        diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::REFLECTION_LATENCY, || {
+            latency as f64
+        });
+    }
+
+    let blocks = stats.blocks.swap(0, Ordering::Relaxed);
+    let block_nanos = stats.block_nanos.swap(0, Ordering::Relaxed);
+    let underruns = stats.underruns.swap(0, Ordering::Relaxed);
+    diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::UNDERRUNS, || underruns as f64);
+    let delta = time.delta_secs_f64();
+    if delta > 0.0 {
+        diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::BLOCKS_PER_SECOND, || {
+            blocks as f64 / delta
+        });
+    }
+    if blocks > 0 {
+        diagnostics.add_measurement(&SteamAudioDiagnosticsPlugin::BLOCK_TIME, || {
+            block_nanos as f64 / blocks as f64 / 1_000.0
+        });
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::{
+    ecs::entity::EntityHashMap,
+    math::Vec3,
+    prelude::{
+        Component, Entity, GlobalTransform, Has, Local, Query, Reflect, ReflectComponent,
+        ReflectResource, Res, Resource,
+    },
+    time::Time,
+};
+
+use crate::{
+    playback::SpatialAudioSource,
+    settings::{AudioUnitsPerMeter, GlobalAudioSettings},
+    source::{ActiveListener, PrimaryListener},
+};
+
+/// Global Doppler settings, the pitch of a moving source is shifted by
+/// `(c + factor * listener_speed) / (c - factor * source_speed)` where `c` is the
+/// [`GlobalAudioSettings::speed_of_sound`].
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct DopplerConfig {
+    /// Scales the effect, `0.0` disables Doppler entirely.
+    pub factor: f32,
+    /// Velocities are clamped to this speed so teleporting entities don't cause pitch spikes.
+    pub max_speed: f32,
+}
+
+impl Default for DopplerConfig {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            max_speed: 100.0,
+        }
+    }
+}
+
+/// Opts a source out of the Doppler effect.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct NoDoppler;
+
+/// Explicit velocity of a source or listener.
+///
+/// Entities without it have their velocity derived from the change in their `GlobalTransform`.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AudioVelocity(pub Vec3);
+
+/// Fraction of the previous listener velocity kept each frame, smoothing out the jitter of
+/// physics steps. `0.0` uses the latest frame alone.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct VelocitySmoothingFactor(pub f32);
+
+impl Default for VelocitySmoothingFactor {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+/// Smoothed velocity of the [`PrimaryListener`] in Bevy units per second, from its
+/// [`AudioVelocity`] or the change in its `GlobalTransform`.
+///
+/// Drives the Doppler shift of [`update_doppler`], and the simulator's shared inputs too when
+/// built with the `extended-inputs` feature.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct ListenerVelocity(pub Vec3);
+
+impl ListenerVelocity {
+    /// Moves towards `velocity`, keeping `smoothing` of the current one.
+    pub(crate) fn update(&mut self, velocity: Vec3, smoothing: VelocitySmoothingFactor) {
+        let keep = smoothing.0.clamp(0.0, 0.99);
+        self.0 = self.0 * keep + velocity * (1.0 - keep);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_doppler(
+    config: Res<DopplerConfig>,
+    global: Res<GlobalAudioSettings>,
+    units: Res<AudioUnitsPerMeter>,
+    time: Res<Time>,
+    listener_velocity: Res<ListenerVelocity>,
+    listener: Res<ActiveListener>,
+    sources: Query<(
+        Entity,
+        &SpatialAudioSource,
+        &GlobalTransform,
+        Option<&AudioVelocity>,
+        Has<NoDoppler>,
+    )>,
+    mut previous: Local<EntityHashMap<Vec3>>,
+) {
+    let delta = time.delta_secs();
+    let mut positions = EntityHashMap::default();
+    let mut velocity = |entity: Entity, position: Vec3, explicit: Option<&AudioVelocity>| {
+        positions.insert(entity, position);
+        let velocity = match (explicit, previous.get(&entity)) {
+            (Some(velocity), _) => velocity.0,
+            (None, Some(previous)) if delta > 0.0 => (position - *previous) / delta,
+            _ => Vec3::ZERO,
+        };
+        velocity.clamp_length_max(config.max_speed)
+    };
+
+    if listener.entity.is_none() {
+        return;
+    }
+    let listener_position = listener.orientation.origin;
+    let listener_velocity = listener_velocity.0.clamp_length_max(config.max_speed);
+
+    // Velocities are in Bevy units per second.
+    let speed_of_sound = global.speed_of_sound * units.get();
+    for (entity, source, transform, explicit, no_doppler) in sources.iter() {
+        let source_position = transform.translation();
+        let source_velocity = velocity(entity, source_position, explicit);
+
+        if no_doppler || config.factor == 0.0 {
+            source.voice.doppler_pitch.store(1.0);
+            continue;
+        }
+
+        let to_listener = (listener_position - source_position).normalize_or_zero();
+        let pitch = doppler_pitch(
+            speed_of_sound,
+            source_velocity.dot(to_listener) * config.factor,
+            -listener_velocity.dot(to_listener) * config.factor,
+        );
+        source.voice.doppler_pitch.store(pitch);
+    }
+
+    *previous = positions;
+}
+
+/// The pitch of a source moving towards the listener at `source_speed` heard by a listener
+/// moving towards it at `listener_speed`.
+fn doppler_pitch(speed_of_sound: f32, source_speed: f32, listener_speed: f32) -> f32 {
+    // Keep both speeds well below the speed of sound so the ratio stays finite.
+    let limit = speed_of_sound * 0.9;
+    let (source_speed, listener_speed) = (
+        source_speed.clamp(-limit, limit),
+        listener_speed.clamp(-limit, limit),
+    );
+    (speed_of_sound + listener_speed) / (speed_of_sound - source_speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::Handle,
+        ecs::system::RunSystemOnce,
+        prelude::{Transform, World},
+    };
+    use std::sync::Arc;
+
+    use crate::playback::VoiceState;
+
+    use super::*;
+
+    #[test]
+    fn stationary_listener_has_no_velocity() {
+        let mut velocity = ListenerVelocity::default();
+        for _ in 0..10 {
+            velocity.update(Vec3::ZERO, VelocitySmoothingFactor::default());
+        }
+        assert_eq!(velocity.0, Vec3::ZERO);
+    }
+
+    #[test]
+    fn velocity_settles_after_stopping() {
+        let mut velocity = ListenerVelocity::default();
+        velocity.update(Vec3::X * 10.0, VelocitySmoothingFactor(0.0));
+        assert_eq!(velocity.0, Vec3::X * 10.0);
+
+        for _ in 0..100 {
+            velocity.update(Vec3::ZERO, VelocitySmoothingFactor(0.5));
+        }
+        assert!(velocity.0.length() < 1e-6);
+    }
+
+    #[test]
+    fn halving_the_speed_of_sound_doubles_the_shift() {
+        let speed_of_sound = GlobalAudioSettings::default().speed_of_sound;
+
+        // A moving listener shifts by `speed / c`, exactly doubling.
+        let shift = |speed_of_sound| doppler_pitch(speed_of_sound, 0.0, 1.0) - 1.0;
+        let ratio = shift(speed_of_sound * 0.5) / shift(speed_of_sound);
+        assert!((ratio - 2.0).abs() < 1e-4, "shift ratio {ratio}");
+
+        // A moving source shifts by `speed / (c - speed)`, a little more than doubling.
+        let shift = |speed_of_sound| doppler_pitch(speed_of_sound, 1.0, 0.0) - 1.0;
+        let ratio = shift(speed_of_sound * 0.5) / shift(speed_of_sound);
+        let expected = (speed_of_sound - 1.0) / (speed_of_sound * 0.5 - 1.0);
+        assert!((ratio - expected).abs() < 1e-4, "shift ratio {ratio}");
+    }
+
+    #[test]
+    fn sources_follow_changes_to_the_speed_of_sound() {
+        let mut world = World::new();
+        world.init_resource::<DopplerConfig>();
+        world.init_resource::<GlobalAudioSettings>();
+        world.init_resource::<AudioUnitsPerMeter>();
+        world.init_resource::<ListenerVelocity>();
+        world.init_resource::<Time>();
+        world.insert_resource(ActiveListener {
+            entity: Some(Entity::PLACEHOLDER),
+            ..Default::default()
+        });
+
+        // Closing in on the listener at the origin.
+        let voice = Arc::new(VoiceState::default());
+        world.spawn((
+            SpatialAudioSource {
+                voice: voice.clone(),
+                asset: Handle::default(),
+            },
+            GlobalTransform::from(Transform::from_xyz(0.0, 0.0, -10.0)),
+            AudioVelocity(Vec3::Z * 5.0),
+        ));
+
+        let shift = |world: &mut World| {
+            world.run_system_once(update_doppler).unwrap();
+            voice.doppler_pitch.load() - 1.0
+        };
+        // In Bevy units per second, like the velocity.
+        let speed_of_sound = world.resource::<GlobalAudioSettings>().speed_of_sound
+            * world.resource::<AudioUnitsPerMeter>().get();
+        let before = shift(&mut world);
+        world.resource_mut::<GlobalAudioSettings>().speed_of_sound *= 0.5;
+        let after = shift(&mut world);
+
+        let expected = (speed_of_sound - 5.0) / (speed_of_sound * 0.5 - 5.0);
+        assert!(before > 0.0);
+        assert!(
+            (after / before - expected).abs() < 1e-3,
+            "{before} then {after}"
+        );
+    }
+}
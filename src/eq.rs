@@ -0,0 +1,175 @@
+use bevy::prelude::{Component, Query, Reflect, ReflectComponent, Res, With};
+use steam_audio::{
+    hrtf::AudioSettings,
+    prelude::{Context, DeinterleavedFrame, EqEffect, EqEffectParams},
+};
+
+use crate::source::{PrimaryListener, SpatialAudioSettings};
+
+/// Equalizes the output of every voice to compensate for the listener's headphones.
+///
+/// Read from the [`PrimaryListener`], changes are crossfaded over 256 frames.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct HeadphoneEq {
+    pub preset: HeadphoneEqPreset,
+}
+
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq)]
+pub enum HeadphoneEqPreset {
+    /// Leaves the output untouched.
+    #[default]
+    Flat,
+    /// Closed back over-ear headphones, which tend to be bass heavy.
+    OverEar,
+    /// Open back headphones, which tend to roll off the low end.
+    OpenBack,
+    /// In-ear monitors and earbuds, which tend to be bright.
+    InEar,
+    /// Gains of the low, mid and high bands.
+    Custom([f32; 3]),
+}
+
+impl HeadphoneEqPreset {
+    /// Gains of the low, mid and high bands.
+    pub fn gains(&self) -> [f32; 3] {
+        match *self {
+            Self::Flat => [1.0, 1.0, 1.0],
+            Self::OverEar => [0.8, 1.0, 1.1],
+            Self::OpenBack => [1.2, 1.0, 0.95],
+            Self::InEar => [1.1, 1.0, 0.8],
+            Self::Custom(gains) => gains.map(|gain| gain.max(0.0)),
+        }
+    }
+}
+
+pub fn update_headphone_eq(
+    settings: Res<SpatialAudioSettings>,
+    listener: Query<Option<&HeadphoneEq>, With<PrimaryListener>>,
+) {
+    let preset = listener
+        .iter()
+        .next()
+        .flatten()
+        .map_or(HeadphoneEqPreset::Flat, |eq| eq.preset);
+
+    let gains = preset.gains();
+    if settings.headphone_eq.load() != gains {
+        settings.headphone_eq.store(gains);
+    }
+}
+
+/// EQ for both output channels, crossfading from the previous gains when they change.
+pub(crate) struct HeadphoneEqFilter {
+    audio_settings: AudioSettings,
+    gains: [f32; 3],
+    effects: [EqEffect; 2],
+    /// The effects and gains that were replaced, faded out over the next block.
+    previous: Option<([EqEffect; 2], [f32; 3])>,
+    /// Spare effects so changing gains doesn't allocate on the audio thread.
+    spare: Option<[EqEffect; 2]>,
+}
+
+impl HeadphoneEqFilter {
+    const CROSSFADE: usize = 256;
+
+    pub(crate) fn new(context: &Context, audio_settings: &AudioSettings, gains: [f32; 3]) -> Self {
+        let effect = || {
+            EqEffect::new(context, audio_settings).expect("could not build steam audio eq effect")
+        };
+
+        Self {
+            audio_settings: audio_settings.clone(),
+            gains,
+            effects: [effect(), effect()],
+            previous: None,
+            spare: Some([effect(), effect()]),
+        }
+    }
+
+    pub(crate) fn gains(&self) -> [f32; 3] {
+        self.gains
+    }
+
+    pub(crate) fn set_gains(&mut self, gains: [f32; 3]) {
+        if gains == self.gains {
+            return;
+        }
+
+        // Mid crossfade, the oldest gains are dropped.
+        if let Some((effects, _)) = self.previous.take() {
+            self.spare = Some(effects);
+        }
+        if let Some(spare) = self.spare.take() {
+            let previous = std::mem::replace(&mut self.effects, spare);
+            self.previous = Some((previous, self.gains));
+        }
+        self.gains = gains;
+    }
+
+    /// Equalizes a stereo block in place.
+    pub(crate) fn apply(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let flat = HeadphoneEqPreset::Flat.gains();
+        if self.gains == flat && self.previous.is_none() {
+            return;
+        }
+
+        let [new_left, new_right] = equalize(
+            &self.audio_settings,
+            &mut self.effects,
+            self.gains,
+            left,
+            right,
+        );
+        let Some((mut previous, previous_gains)) = self.previous.take() else {
+            left.copy_from_slice(&new_left);
+            right.copy_from_slice(&new_right);
+            return;
+        };
+
+        let [old_left, old_right] = equalize(
+            &self.audio_settings,
+            &mut previous,
+            previous_gains,
+            left,
+            right,
+        );
+        let crossfade = Self::CROSSFADE.min(left.len()).max(1);
+        for index in 0..left.len() {
+            let t = ((index + 1) as f32 / crossfade as f32).min(1.0);
+            left[index] = t * new_left[index] + (1.0 - t) * old_left[index];
+            right[index] = t * new_right[index] + (1.0 - t) * old_right[index];
+        }
+        self.spare = Some(previous);
+    }
+}
+
+/// Runs `left` and `right` through `effects`, flat gains pass the block through untouched.
+fn equalize(
+    audio_settings: &AudioSettings,
+    effects: &mut [EqEffect; 2],
+    gains: [f32; 3],
+    left: &[f32],
+    right: &[f32],
+) -> [Vec<f32>; 2] {
+    if gains == HeadphoneEqPreset::Flat.gains() {
+        return [left.to_vec(), right.to_vec()];
+    }
+
+    let params = EqEffectParams { gains };
+    let frame_size = audio_settings.frame_size() as usize;
+    let sampling_rate = audio_settings.sampling_rate();
+
+    let mut channel = |effect: &mut EqEffect, samples: &[f32]| {
+        let mut input = DeinterleavedFrame::new(frame_size, 1, sampling_rate);
+        input.current_frame[0] = samples.to_vec();
+        let mut output = DeinterleavedFrame::new(frame_size, 1, sampling_rate);
+        effect
+            .apply_to_buffer(&params, &mut input, &mut output)
+            .unwrap();
+        output.current_frame[0].clone()
+    };
+
+    let [left_effect, right_effect] = effects;
+    [channel(left_effect, left), channel(right_effect, right)]
+}
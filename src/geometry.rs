@@ -0,0 +1,585 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{AssetEvent, Assets},
+    ecs::entity::EntityHashMap,
+    log::warn,
+    math::{Mat4, Vec3},
+    prelude::{
+        Added, Changed, Entity, Event, EventReader, EventWriter, GlobalTransform,
+        IntoSystemConfigs, Local, Mesh, Mesh3d, Query, Reflect, ReflectResource, RemovedComponents,
+        Res, ResMut, Resource, With,
+    },
+    state::state::{StateTransitionEvent, States},
+};
+use std::marker::PhantomData;
+use steam_audio::prelude::{
+    Context, InstancedMesh, InstancedMeshSettings, Scene, StaticMesh, StaticMeshSettings,
+};
+
+use crate::{
+    coords::bevy_position_to_phonon,
+    material::{AudioMaterial, MaterialLibrary},
+    mesh::{AudioMesh, AudioMeshError, MaterialPalette, WELD_TOLERANCE},
+    probe::BakeReflectionsTask,
+    ray_tracer::RayTracer,
+    reflections::ReflectionState,
+    scene::{AudioObstacle, DynamicAudioGeometry},
+    settings::AudioUnitsPerMeter,
+    simulation::DirectSimulationState,
+    source::SpatialAudioSettings,
+};
+
+/// The Steam Audio scene set on the simulator, holding one static mesh per [`AudioObstacle`]
+/// and one instanced mesh per [`DynamicAudioGeometry`].
+///
+/// Obstacles are baked with their transform when they're added, and re-added when their `Mesh`
+/// asset changes. Dynamic geometry only moves its instance.
+#[derive(Resource)]
+pub struct SteamAudioScene {
+    pub scene: Scene,
+    ray_tracer: RayTracer,
+    meshes: EntityHashMap<StaticMesh>,
+    instances: EntityHashMap<DynamicInstance>,
+    /// The scale meshes are converted with, from [`AudioUnitsPerMeter`].
+    units: AudioUnitsPerMeter,
+    dirty: bool,
+}
+
+/// The sub-scene of a [`DynamicAudioGeometry`], committed once, and its instance in the root
+/// scene.
+struct DynamicInstance {
+    _sub_scene: Scene,
+    _mesh: StaticMesh,
+    instance: InstancedMesh,
+}
+
+fn static_mesh_settings(audio_mesh: &AudioMesh, units: AudioUnitsPerMeter) -> StaticMeshSettings {
+    StaticMeshSettings {
+        vertices: audio_mesh
+            .vertices
+            .iter()
+            .map(|vertex| bevy_position_to_phonon(*vertex, units).into())
+            .collect(),
+        triangles: audio_mesh.triangles.clone(),
+        material_indices: audio_mesh.material_indices.clone(),
+        materials: audio_mesh.materials.clone(),
+    }
+}
+
+/// The transform of an instance whose sub-scene is in meters, in the root scene's meters.
+fn instance_transform(transform: &GlobalTransform, units: AudioUnitsPerMeter) -> Mat4 {
+    let units = Mat4::from_scale(Vec3::splat(units.get()));
+    units.inverse() * transform.compute_matrix() * units
+}
+
+impl SteamAudioScene {
+    pub(crate) fn new(context: &Context, ray_tracer: RayTracer) -> Self {
+        Self {
+            scene: Scene::new(context, &ray_tracer.scene_settings())
+                .expect("could not build steam audio scene"),
+            ray_tracer,
+            meshes: EntityHashMap::default(),
+            instances: EntityHashMap::default(),
+            units: AudioUnitsPerMeter::default(),
+            dirty: true,
+        }
+    }
+
+    /// Number of static meshes in the scene.
+    pub fn len(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+
+    /// Whether changes are waiting to be committed.
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Commits the scene and sets it on the simulator again at the next
+    /// [`commit_audio_scene`], like after the simulator was rebuilt.
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Converts meshes added from now on with `units`, meshes already in the scene keep their
+    /// scale until it's rebuilt.
+    pub(crate) fn set_units_per_meter(&mut self, units: AudioUnitsPerMeter) {
+        self.units = units;
+    }
+
+    pub(crate) fn insert(&mut self, entity: Entity, audio_mesh: &AudioMesh) {
+        self.remove(entity);
+
+        match StaticMesh::new(&self.scene, &static_mesh_settings(audio_mesh, self.units)) {
+            Ok(static_mesh) => {
+                self.scene.add_static_mesh(&static_mesh);
+                self.meshes.insert(entity, static_mesh);
+                self.dirty = true;
+            }
+            Err(err) => warn!("Could not add audio obstacle {entity:?} to the scene: {err:?}"),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, entity: Entity) {
+        if let Some(static_mesh) = self.meshes.remove(&entity) {
+            self.scene.remove_static_mesh(&static_mesh);
+            self.dirty = true;
+        }
+    }
+
+    /// Adds `audio_mesh`, in the entity's local space, as an instance placed at `transform`.
+    pub(crate) fn insert_dynamic(
+        &mut self,
+        context: &Context,
+        entity: Entity,
+        audio_mesh: &AudioMesh,
+        transform: &GlobalTransform,
+    ) {
+        self.remove_dynamic(entity);
+
+        let scene_settings = self.ray_tracer.scene_settings();
+        let units = self.units;
+        let instance = Scene::new(context, &scene_settings).and_then(|sub_scene| {
+            let mesh = StaticMesh::new(&sub_scene, &static_mesh_settings(audio_mesh, units))?;
+            sub_scene.add_static_mesh(&mesh);
+            sub_scene.commit();
+
+            let instance = InstancedMesh::new(
+                &self.scene,
+                &InstancedMeshSettings {
+                    sub_scene: &sub_scene,
+                    transform: instance_transform(transform, units).into(),
+                },
+            )?;
+            Ok(DynamicInstance {
+                _sub_scene: sub_scene,
+                _mesh: mesh,
+                instance,
+            })
+        });
+
+        match instance {
+            Ok(instance) => {
+                self.scene.add_instanced_mesh(&instance.instance);
+                self.instances.insert(entity, instance);
+                self.dirty = true;
+            }
+            Err(err) => warn!("Could not add dynamic audio geometry {entity:?}: {err:?}"),
+        }
+    }
+
+    /// Drops every mesh and instance, starting over from an empty scene.
+    pub(crate) fn clear(&mut self, context: &Context) {
+        match Scene::new(context, &self.ray_tracer.scene_settings()) {
+            Ok(scene) => {
+                self.meshes.clear();
+                self.instances.clear();
+                self.scene = scene;
+                self.dirty = true;
+            }
+            Err(err) => warn!("Could not rebuild steam audio scene, keeping the old one: {err:?}"),
+        }
+    }
+
+    /// Moves the instance of `entity`, the sub-scene stays as it is.
+    pub(crate) fn move_dynamic(&mut self, entity: Entity, transform: &GlobalTransform) {
+        if let Some(instance) = self.instances.get(&entity) {
+            instance.instance.update_transform(
+                &self.scene,
+                instance_transform(transform, self.units).into(),
+            );
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn remove_dynamic(&mut self, entity: Entity) {
+        if let Some(instance) = self.instances.remove(&entity) {
+            self.scene.remove_instanced_mesh(&instance.instance);
+            self.dirty = true;
+        }
+    }
+}
+
+/// The world space mesh of an obstacle with its materials resolved.
+fn world_audio_mesh(
+    entity: Entity,
+    mesh: &Mesh,
+    transform: &GlobalTransform,
+    material: Option<&AudioMaterial>,
+    palette: Option<&MaterialPalette>,
+    library: &MaterialLibrary,
+) -> Option<AudioMesh> {
+    AudioMesh::from_mesh_transformed(mesh, transform)
+        .and_then(|audio_mesh| audio_mesh.validate_and_weld(WELD_TOLERANCE))
+        .and_then(|audio_mesh| with_materials(audio_mesh, material, palette, library))
+        .map_err(|err| warn!("Could not add audio obstacle {entity:?} to the scene: {err}"))
+        .ok()
+}
+
+/// The local space mesh of an obstacle with its materials resolved.
+fn local_audio_mesh(
+    entity: Entity,
+    mesh: &Mesh,
+    material: Option<&AudioMaterial>,
+    palette: Option<&MaterialPalette>,
+    library: &MaterialLibrary,
+) -> Option<AudioMesh> {
+    AudioMesh::try_from(mesh)
+        .and_then(|audio_mesh| audio_mesh.validate_and_weld(WELD_TOLERANCE))
+        .and_then(|audio_mesh| with_materials(audio_mesh, material, palette, library))
+        .map_err(|err| warn!("Could not add dynamic audio geometry {entity:?}: {err}"))
+        .ok()
+}
+
+/// A palette assigns materials per triangle, otherwise the whole mesh shares one.
+fn with_materials(
+    audio_mesh: AudioMesh,
+    material: Option<&AudioMaterial>,
+    palette: Option<&MaterialPalette>,
+    library: &MaterialLibrary,
+) -> Result<AudioMesh, AudioMeshError> {
+    match palette {
+        Some(palette) => audio_mesh.with_palette(palette),
+        None => {
+            Ok(audio_mesh.with_material(material.cloned().unwrap_or_default().resolve(library)))
+        }
+    }
+}
+
+/// Adds new [`AudioObstacle`]s to the scene and re-adds obstacles whose mesh asset changed.
+///
+/// Obstacles whose mesh can't be converted are logged and left out.
+pub fn register_audio_obstacles(
+    mut scene: ResMut<SteamAudioScene>,
+    meshes: Res<Assets<Mesh>>,
+    library: Res<MaterialLibrary>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    geometry: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &GlobalTransform,
+            Option<&AudioMaterial>,
+            Option<&MaterialPalette>,
+        ),
+        With<AudioObstacle>,
+    >,
+    added: Query<(), Added<AudioObstacle>>,
+    // Obstacles whose mesh hadn't loaded yet.
+    mut pending: Local<Vec<Entity>>,
+) {
+    let modified: Vec<_> = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    let waiting = std::mem::take(&mut *pending);
+    for (entity, mesh, transform, material, palette) in geometry.iter() {
+        let update =
+            added.contains(entity) || waiting.contains(&entity) || modified.contains(&mesh.id());
+        if !update {
+            continue;
+        }
+
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            pending.push(entity);
+            continue;
+        };
+
+        if let Some(audio_mesh) =
+            world_audio_mesh(entity, mesh, transform, material, palette, &library)
+        {
+            scene.insert(entity, &audio_mesh);
+        }
+    }
+}
+
+/// Removes the static mesh of despawned entities and entities that lost [`AudioObstacle`].
+pub fn remove_audio_obstacles(
+    mut scene: ResMut<SteamAudioScene>,
+    mut removed: RemovedComponents<AudioObstacle>,
+) {
+    for entity in removed.read() {
+        scene.remove(entity);
+    }
+}
+
+/// Adds new [`DynamicAudioGeometry`] to the scene and rebuilds the sub-scene of geometry whose
+/// mesh asset changed.
+#[allow(clippy::too_many_arguments)]
+pub fn register_dynamic_geometry(
+    mut scene: ResMut<SteamAudioScene>,
+    settings: Res<SpatialAudioSettings>,
+    meshes: Res<Assets<Mesh>>,
+    library: Res<MaterialLibrary>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    geometry: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &GlobalTransform,
+            Option<&AudioMaterial>,
+            Option<&MaterialPalette>,
+        ),
+        With<DynamicAudioGeometry>,
+    >,
+    added: Query<(), Added<DynamicAudioGeometry>>,
+    // Geometry whose mesh hadn't loaded yet.
+    mut pending: Local<Vec<Entity>>,
+) {
+    let modified: Vec<_> = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    let waiting = std::mem::take(&mut *pending);
+    for (entity, mesh, transform, material, palette) in geometry.iter() {
+        let update =
+            added.contains(entity) || waiting.contains(&entity) || modified.contains(&mesh.id());
+        if !update {
+            continue;
+        }
+
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            pending.push(entity);
+            continue;
+        };
+
+        if let Some(audio_mesh) = local_audio_mesh(entity, mesh, material, palette, &library) {
+            scene.insert_dynamic(&settings.context, entity, &audio_mesh, transform);
+        }
+    }
+}
+
+/// Moves the instances of [`DynamicAudioGeometry`] whose transform changed, geometry that
+/// stays put costs nothing.
+pub fn move_dynamic_geometry(
+    mut scene: ResMut<SteamAudioScene>,
+    moved: Query<
+        (Entity, &GlobalTransform),
+        (With<DynamicAudioGeometry>, Changed<GlobalTransform>),
+    >,
+) {
+    for (entity, transform) in moved.iter() {
+        scene.move_dynamic(entity, transform);
+    }
+}
+
+/// Removes the instance of despawned entities and entities that lost [`DynamicAudioGeometry`].
+pub fn remove_dynamic_geometry(
+    mut scene: ResMut<SteamAudioScene>,
+    mut removed: RemovedComponents<DynamicAudioGeometry>,
+) {
+    for entity in removed.read() {
+        scene.remove_dynamic(entity);
+    }
+}
+
+/// Commits the scene and hands it to the simulator, at most once per frame.
+pub fn commit_audio_scene(
+    mut scene: ResMut<SteamAudioScene>,
+    settings: Res<SpatialAudioSettings>,
+    reflections: Res<ReflectionState>,
+    direct: Res<DirectSimulationState>,
+    bake: Res<BakeReflectionsTask>,
+) {
+    // Committing while the simulation or bake tasks trace the scene isn't allowed, try next
+    // frame.
+    if !scene.dirty || reflections.is_running() || direct.is_running() || bake.is_running() {
+        return;
+    }
+
+    scene.scene.commit();
+    if let Some(simulator) = &settings.simulator {
+        simulator.set_scene(&scene.scene);
+        simulator.commit();
+    }
+    scene.dirty = false;
+}
+
+/// Whether the [`SteamAudioScene`] matches the geometry of the world.
+///
+/// Set it to `Dirty` to rebuild the scene from scratch from the current [`AudioObstacle`]s and
+/// [`DynamicAudioGeometry`], [`AudioSceneReloadPlugin`] does so on state transitions.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum AudioSceneState {
+    #[default]
+    Clean,
+    Dirty,
+}
+
+/// Sent once a `Dirty` [`AudioSceneState`] has been rebuilt.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSceneRebuilt {
+    /// Static meshes in the rebuilt scene.
+    pub obstacles: usize,
+}
+
+/// Empties the scene and adds every obstacle and dynamic geometry back, so nothing of a level
+/// that was unloaded stays in the simulation.
+#[allow(clippy::too_many_arguments)]
+pub fn rebuild_audio_scene(
+    mut state: ResMut<AudioSceneState>,
+    mut scene: ResMut<SteamAudioScene>,
+    settings: Res<SpatialAudioSettings>,
+    meshes: Res<Assets<Mesh>>,
+    library: Res<MaterialLibrary>,
+    obstacles: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &GlobalTransform,
+            Option<&AudioMaterial>,
+            Option<&MaterialPalette>,
+        ),
+        With<AudioObstacle>,
+    >,
+    dynamic: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &GlobalTransform,
+            Option<&AudioMaterial>,
+            Option<&MaterialPalette>,
+        ),
+        With<DynamicAudioGeometry>,
+    >,
+    mut rebuilt: EventWriter<AudioSceneRebuilt>,
+) {
+    if *state == AudioSceneState::Clean {
+        return;
+    }
+
+    // Geometry whose mesh hasn't loaded yet is added once it has, like new geometry.
+    scene.clear(&settings.context);
+    for (entity, mesh, transform, material, palette) in obstacles.iter() {
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        if let Some(audio_mesh) =
+            world_audio_mesh(entity, mesh, transform, material, palette, &library)
+        {
+            scene.insert(entity, &audio_mesh);
+        }
+    }
+    for (entity, mesh, transform, material, palette) in dynamic.iter() {
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        if let Some(audio_mesh) = local_audio_mesh(entity, mesh, material, palette, &library) {
+            scene.insert_dynamic(&settings.context, entity, &audio_mesh, transform);
+        }
+    }
+
+    *state = AudioSceneState::Clean;
+    rebuilt.send(AudioSceneRebuilt {
+        obstacles: scene.len(),
+    });
+}
+
+/// Marks the scene `Dirty` whenever the state `S` changes.
+pub fn mark_audio_scene_dirty<S: States>(
+    mut transitions: EventReader<StateTransitionEvent<S>>,
+    mut state: ResMut<AudioSceneState>,
+) {
+    if transitions
+        .read()
+        .any(|transition| transition.exited != transition.entered)
+    {
+        *state = AudioSceneState::Dirty;
+    }
+}
+
+/// Rebuilds the [`SteamAudioScene`] whenever the state `S` changes, like when a level is loaded.
+///
+/// Despawning the geometry of the old level is left to the game, e.g. with `StateScoped`.
+pub struct AudioSceneReloadPlugin<S: States>(PhantomData<fn() -> S>);
+
+impl<S: States> Default for AudioSceneReloadPlugin<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S: States> Plugin for AudioSceneReloadPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            mark_audio_scene_dirty::<S>.before(rebuild_audio_scene),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{math::Quat, prelude::Transform};
+
+    use super::*;
+
+    const UNITS: AudioUnitsPerMeter = AudioUnitsPerMeter(4.0);
+
+    /// Where the sub-scene point `meters` ends up in the root scene's meters, going through
+    /// Bevy units.
+    fn expected(transform: &GlobalTransform, meters: Vec3) -> Vec3 {
+        transform.transform_point(meters * UNITS.get()) / UNITS.get()
+    }
+
+    fn assert_maps_points(transform: Transform) {
+        let transform = GlobalTransform::from(transform);
+        let matrix = instance_transform(&transform, UNITS);
+
+        for point in [Vec3::ZERO, Vec3::X, Vec3::new(0.5, -2.0, 3.0)] {
+            let mapped = matrix.transform_point3(point);
+            assert!(
+                mapped.abs_diff_eq(expected(&transform, point), 1e-5),
+                "{point} went to {mapped}"
+            );
+        }
+    }
+
+    #[test]
+    fn instance_translation_is_in_meters() {
+        let transform = GlobalTransform::from_xyz(8.0, -4.0, 2.0);
+        let matrix = instance_transform(&transform, UNITS);
+
+        assert!(matrix
+            .transform_point3(Vec3::ZERO)
+            .abs_diff_eq(Vec3::new(2.0, -1.0, 0.5), 1e-6));
+        assert_maps_points(Transform::from_xyz(8.0, -4.0, 2.0));
+    }
+
+    #[test]
+    fn instance_keeps_non_uniform_scale() {
+        let transform = Transform::from_xyz(4.0, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_y(0.6))
+            .with_scale(Vec3::new(1.0, 2.0, 3.0));
+        assert_maps_points(transform);
+
+        let matrix = instance_transform(&transform.into(), UNITS);
+        let (scale, _, _) = matrix.to_scale_rotation_translation();
+        assert!(scale.abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-5), "{scale}");
+    }
+
+    #[test]
+    fn instance_can_be_mirrored() {
+        let transform = Transform::from_xyz(0.0, 0.0, -4.0).with_scale(Vec3::new(-1.0, 1.0, 1.0));
+        assert_maps_points(transform);
+
+        let matrix = instance_transform(&transform.into(), UNITS);
+        assert!(matrix.determinant() < 0.0);
+        assert!(matrix
+            .transform_point3(Vec3::X)
+            .abs_diff_eq(Vec3::new(-1.0, 0.0, -1.0), 1e-6));
+    }
+}
@@ -0,0 +1,400 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{Assets, Handle, RenderAssetUsages},
+    color::{palettes::css, Color, LinearRgba, Mix},
+    math::{Isometry3d, Vec3},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    prelude::{
+        AlphaMode, BuildChildren, Changed, Commands, Component, DespawnRecursiveExt, Entity,
+        Gizmos, GlobalTransform, IntoSystemConfigs, Local, Mesh, Mesh3d, Or, Query,
+        RemovedComponents, Res, ResMut, Resource, Transform, With,
+    },
+    render::mesh::{Indices, PrimitiveTopology},
+    transform::TransformSystem,
+};
+
+use crate::{
+    attenuation::AudioDirectivity,
+    culling::MaxAudibleDistance,
+    playback::SpatialAudioSource,
+    scene::AudioSceneMesh,
+    source::{Listener, PrimaryListener},
+};
+
+/// Size of the box drawn around a listener, roughly a human head.
+const HEAD_SIZE: Vec3 = Vec3::new(0.16, 0.24, 0.2);
+/// Points along each directivity lobe outline.
+const LOBE_SEGMENTS: usize = 32;
+
+/// Draws the sources, listeners and geometry of
+/// [`SpatialAudioPlugin`](crate::source::SpatialAudioPlugin) with gizmos, colored by
+/// [`AudioGizmoColors`] and toggled by [`SteamAudioDebugConfig`].
+///
+/// Every source gets a marker, an arrow along the direction it faces, its directivity lobe and a
+/// sphere at its [`MaxAudibleDistance`]. The marker fades with the source's gain and turns to
+/// the occluded color as it's occluded. Listeners get a head-sized box and their axes, and the
+/// [`AudioSceneMesh`] is drawn as a wireframe. Sources with [`ShowDirectivityLobe`] also get a
+/// solid lobe mesh.
+pub struct SteamAudioGizmosPlugin;
+
+impl Plugin for SteamAudioGizmosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioGizmoColors>()
+            .init_resource::<SteamAudioDebugConfig>()
+            .add_systems(
+                PostUpdate,
+                (
+                    (
+                        draw_source_gizmos,
+                        draw_listener_gizmos,
+                        draw_geometry_gizmos,
+                        draw_occlusion_rays,
+                    )
+                        .after(TransformSystem::TransformPropagate),
+                    (update_directivity_lobes, remove_directivity_lobes),
+                ),
+            );
+    }
+}
+
+/// What [`SteamAudioGizmosPlugin`] draws.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SteamAudioDebugConfig {
+    /// Wireframe of the [`AudioSceneMesh`].
+    pub geometry: bool,
+    pub sources: bool,
+    pub listener: bool,
+    /// A line from the [`PrimaryListener`] to every source, in the occluded color while occluded.
+    pub occlusion_rays: bool,
+}
+
+impl Default for SteamAudioDebugConfig {
+    fn default() -> Self {
+        Self {
+            geometry: true,
+            sources: true,
+            listener: true,
+            occlusion_rays: false,
+        }
+    }
+}
+
+/// Colors used by [`SteamAudioGizmosPlugin`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct AudioGizmoColors {
+    pub source: Color,
+    pub direction: Color,
+    pub directivity: Color,
+    pub max_distance: Color,
+    pub listener: Color,
+    pub geometry: Color,
+    /// Sources and rays blend towards this as they're occluded.
+    pub occluded: Color,
+}
+
+impl Default for AudioGizmoColors {
+    fn default() -> Self {
+        Self {
+            source: css::ORANGE.into(),
+            direction: css::YELLOW.into(),
+            directivity: css::AQUA.into(),
+            max_distance: css::DIM_GRAY.into(),
+            listener: css::LIME.into(),
+            geometry: css::SLATE_GRAY.into(),
+            occluded: css::RED.into(),
+        }
+    }
+}
+
+pub fn draw_source_gizmos(
+    mut gizmos: Gizmos,
+    config: Res<SteamAudioDebugConfig>,
+    colors: Res<AudioGizmoColors>,
+    sources: Query<(
+        &SpatialAudioSource,
+        &GlobalTransform,
+        Option<&MaxAudibleDistance>,
+        Option<&AudioDirectivity>,
+    )>,
+) {
+    if !config.sources {
+        return;
+    }
+
+    for (source, transform, max_distance, directivity) in &sources {
+        let directivity = directivity.copied().unwrap_or_default();
+        let position = transform.translation();
+        let forward = transform.forward().as_vec3();
+
+        let occlusion = source.voice.direct.load().occlusion;
+        let gain = source.voice.gain().clamp(0.0, 1.0);
+        let color = colors
+            .occluded
+            .mix(&colors.source, occlusion)
+            .with_alpha(0.25 + 0.75 * gain);
+        gizmos.sphere(Isometry3d::from_translation(position), 0.1, color);
+        gizmos.arrow(position, position + forward * 0.5, colors.direction);
+
+        // The lobe is symmetric around the forward axis, two outlines through it are enough.
+        for side in [transform.up().as_vec3(), transform.right().as_vec3()] {
+            gizmos.linestrip(
+                (0..=LOBE_SEGMENTS).map(|segment| {
+                    let angle = segment as f32 / LOBE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    let (sin, cos) = angle.sin_cos();
+                    position + (forward * cos + side * sin) * directivity.gain(cos) * 0.5
+                }),
+                colors.directivity,
+            );
+        }
+
+        if let Some(MaxAudibleDistance(distance)) = max_distance.copied() {
+            if distance.is_finite() {
+                gizmos.sphere(
+                    Isometry3d::from_translation(position),
+                    distance,
+                    colors.max_distance,
+                );
+            }
+        }
+    }
+}
+
+pub fn draw_listener_gizmos(
+    mut gizmos: Gizmos,
+    config: Res<SteamAudioDebugConfig>,
+    colors: Res<AudioGizmoColors>,
+    listeners: Query<&GlobalTransform, With<Listener>>,
+) {
+    if !config.listener {
+        return;
+    }
+
+    for transform in &listeners {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        gizmos.cuboid(
+            Transform::from_translation(translation)
+                .with_rotation(rotation)
+                .with_scale(HEAD_SIZE),
+            colors.listener,
+        );
+        gizmos.arrow(
+            translation,
+            translation + transform.forward().as_vec3() * 0.5,
+            colors.listener,
+        );
+        gizmos.axes(*transform, 0.3);
+    }
+}
+
+pub fn draw_geometry_gizmos(
+    mut gizmos: Gizmos,
+    config: Res<SteamAudioDebugConfig>,
+    colors: Res<AudioGizmoColors>,
+    scene_mesh: Res<AudioSceneMesh>,
+) {
+    let Some(mesh) = scene_mesh.0.as_ref().filter(|_| config.geometry) else {
+        return;
+    };
+
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle.map(|index| mesh.vertices[index as usize]);
+        gizmos.linestrip([a, b, c, a], colors.geometry);
+    }
+}
+
+pub fn draw_occlusion_rays(
+    mut gizmos: Gizmos,
+    config: Res<SteamAudioDebugConfig>,
+    colors: Res<AudioGizmoColors>,
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    sources: Query<(&SpatialAudioSource, &GlobalTransform)>,
+) {
+    if !config.occlusion_rays {
+        return;
+    }
+    let Some(listener) = listener.iter().next().map(GlobalTransform::translation) else {
+        return;
+    };
+
+    for (source, transform) in &sources {
+        let occlusion = source.voice.direct.load().occlusion;
+        let color = colors.occluded.mix(&colors.direction, occlusion);
+        gizmos.line(listener, transform.translation(), color);
+    }
+}
+
+/// Shows the [`AudioDirectivity`] of a source as a solid lobe around the direction it faces,
+/// colored from green at full gain to red at silence.
+///
+/// The lobe is rebuilt whenever the directivity or this component changes.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ShowDirectivityLobe {
+    /// Segments around the forward axis.
+    pub azimuth_segments: u32,
+    /// Segments from the forward axis to the backward one.
+    pub elevation_segments: u32,
+    /// Length of the lobe where the gain is 1.0, in Bevy units.
+    pub size: f32,
+}
+
+impl Default for ShowDirectivityLobe {
+    fn default() -> Self {
+        Self {
+            azimuth_segments: 32,
+            elevation_segments: 16,
+            size: 0.5,
+        }
+    }
+}
+
+impl ShowDirectivityLobe {
+    /// The lobe of `directivity` in the source's local space, with
+    /// `(azimuth_segments + 1) * (elevation_segments + 1)` vertices.
+    pub fn mesh(&self, directivity: &AudioDirectivity) -> Mesh {
+        let azimuth_segments = self.azimuth_segments.max(3);
+        let elevation_segments = self.elevation_segments.max(2);
+
+        let mut positions = Vec::new();
+        let mut colors = Vec::new();
+        for elevation in 0..=elevation_segments {
+            // Angle from the forward axis.
+            let theta = elevation as f32 / elevation_segments as f32 * std::f32::consts::PI;
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            let gain = directivity.gain(cos_theta);
+
+            for azimuth in 0..=azimuth_segments {
+                let phi = azimuth as f32 / azimuth_segments as f32 * std::f32::consts::TAU;
+                let (sin_phi, cos_phi) = phi.sin_cos();
+                let direction = Vec3::new(sin_theta * cos_phi, sin_theta * sin_phi, -cos_theta);
+
+                positions.push((direction * gain * self.size).to_array());
+                let gain = gain.clamp(0.0, 1.0);
+                colors.push(LinearRgba::rgb(1.0 - gain, gain, 0.0).to_f32_array());
+            }
+        }
+
+        let row = azimuth_segments + 1;
+        let mut indices = Vec::new();
+        for elevation in 0..elevation_segments {
+            for azimuth in 0..azimuth_segments {
+                let corner = elevation * row + azimuth;
+                indices.extend([corner, corner + row, corner + 1]);
+                indices.extend([corner + 1, corner + row, corner + row + 1]);
+            }
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+        .with_computed_smooth_normals()
+    }
+}
+
+/// The child entity showing the lobe of a [`ShowDirectivityLobe`] source.
+#[derive(Component)]
+pub struct DirectivityLobe {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+}
+
+pub fn update_directivity_lobes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    sources: Query<
+        (
+            Entity,
+            &ShowDirectivityLobe,
+            Option<&AudioDirectivity>,
+            Option<&DirectivityLobe>,
+        ),
+        Or<(Changed<ShowDirectivityLobe>, Changed<AudioDirectivity>)>,
+    >,
+    // Shared by every lobe, the colors come from the vertices.
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    for (entity, show, directivity, lobe) in &sources {
+        let mesh = show.mesh(&directivity.copied().unwrap_or_default());
+        if let Some(lobe) = lobe {
+            meshes.insert(&lobe.mesh, mesh);
+            continue;
+        }
+
+        let material = material
+            .get_or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: Color::WHITE.with_alpha(0.5),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..Default::default()
+                })
+            })
+            .clone();
+        let mesh = meshes.add(mesh);
+        let child = commands
+            .spawn((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material),
+                Transform::default(),
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .add_child(child)
+            .insert(DirectivityLobe {
+                entity: child,
+                mesh,
+            });
+    }
+}
+
+/// Despawns the lobe of sources that lost [`ShowDirectivityLobe`].
+pub fn remove_directivity_lobes(
+    mut commands: Commands,
+    mut removed: RemovedComponents<ShowDirectivityLobe>,
+    lobes: Query<&DirectivityLobe>,
+) {
+    for entity in removed.read() {
+        let Ok(lobe) = lobes.get(entity) else {
+            continue;
+        };
+
+        commands.entity(lobe.entity).despawn_recursive();
+        commands.entity(entity).remove::<DirectivityLobe>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lobe_has_a_vertex_per_segment_corner() {
+        let cardioid = AudioDirectivity {
+            dipole_weight: 0.5,
+            dipole_power: 1.0,
+        };
+        for (azimuth_segments, elevation_segments, vertices) in
+            [(32, 16, 33 * 17), (8, 4, 9 * 5), (1, 1, 4 * 3)]
+        {
+            let mesh = ShowDirectivityLobe {
+                azimuth_segments,
+                elevation_segments,
+                ..Default::default()
+            }
+            .mesh(&cardioid);
+            assert_eq!(mesh.count_vertices(), vertices);
+            assert_eq!(
+                mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+                    .map(|colors| colors.len()),
+                Some(vertices)
+            );
+        }
+    }
+}
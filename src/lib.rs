@@ -1,6 +1,113 @@
+pub mod ambisonics;
+pub mod area;
+pub mod attenuation;
+pub mod binaural;
+pub mod chain;
+#[cfg(any(feature = "rapier", feature = "avian"))]
+pub mod collider;
+pub mod convolution;
+pub mod coords;
+pub mod culling;
+pub mod diagnostics;
+pub mod doppler;
+pub mod eq;
+pub mod geometry;
+#[cfg(feature = "audio-debug")]
+pub mod gizmos;
+pub mod material;
+pub mod mesh;
+pub mod mix;
+pub mod occlusion;
+pub mod opencl;
+pub mod output;
+pub mod params;
+pub mod pathing;
+pub mod pitch;
+pub mod playback;
+pub mod pool;
+pub mod portal;
+pub mod probe;
+pub mod ray_tracer;
+pub mod reflections;
+pub mod samples;
+pub mod scene;
+pub mod settings;
+pub mod simulation;
+pub mod sofa;
 pub mod source;
+pub mod terrain;
+pub mod transmission;
+pub mod virtual_voice;
+pub mod volume;
 
 pub mod prelude {
-    pub use crate::source::{listener_update, Listener, SpatialAudioPlugin};
+    pub use crate::ambisonics::{AmbisonicsBed, AmbisonicsConfig, AmbisonicsHrtf, AmbisonicsOrder};
+    pub use crate::area::{AudioArea, ListenerReverbState};
+    pub use crate::attenuation::{
+        AirAbsorption, AirAbsorptionOverride, AttenuationCurveAsset, AudioDirectivity,
+        DistanceAttenuation, DistanceAttenuationCurve, SourceRadius,
+    };
+    pub use crate::binaural::{BinauralConfig, NearFieldCorrection};
+    pub use crate::chain::{AudioEffect, EffectChain};
+    #[cfg(any(feature = "rapier", feature = "avian"))]
+    pub use crate::collider::AudioFromCollider;
+    pub use crate::convolution::{ConvolutionReverbIR, ConvolutionReverbSend};
+    pub use crate::culling::MaxAudibleDistance;
+    pub use crate::diagnostics::SteamAudioDiagnosticsPlugin;
+    pub use crate::doppler::{
+        AudioVelocity, DopplerConfig, ListenerVelocity, NoDoppler, VelocitySmoothingFactor,
+    };
+    pub use crate::eq::{HeadphoneEq, HeadphoneEqPreset};
+    pub use crate::geometry::{
+        AudioSceneRebuilt, AudioSceneReloadPlugin, AudioSceneState, SteamAudioScene,
+    };
+    #[cfg(feature = "audio-debug")]
+    pub use crate::gizmos::{
+        AudioGizmoColors, ShowDirectivityLobe, SteamAudioDebugConfig, SteamAudioGizmosPlugin,
+    };
+    pub use crate::material::{AudioMaterial, MaterialLibrary};
+    pub use crate::mesh::{MaterialPalette, ATTRIBUTE_AUDIO_MATERIAL};
+    pub use crate::mix::{SourceMix, SpatialBlend, SteamAudioMixer};
+    pub use crate::occlusion::{Occlusion, OcclusionMode};
+    pub use crate::opencl::OpenClConfig;
+    pub use crate::output::OutputMode;
+    pub use crate::params::{SharedParams, SourceParams};
+    pub use crate::pathing::{PathingConfig, PathingSource};
+    pub use crate::pitch::{PitchShift, PitchVariance};
+    pub use crate::playback::{
+        AudioErrorPolicy, AudioFinished, KeepOnFinish, PauseAudio, PauseFadeFrames,
+        PlaybackPosition, SeekAudio, SeekError, SpatialAudioBundle, SpatialAudioCommands,
+        SpatialAudioError, SpatialAudioSource, SpatialPlaybackControl, SpatialPlaybackFinished,
+        SpatialPlaybackStarted, TailBlocks, WarmupBlocks,
+    };
+    pub use crate::pool::{AudioSourcePool, PlayOneShotAudio, PooledAudio};
+    pub use crate::portal::{AudioPortal, DoorOpen};
+    pub use crate::probe::{
+        BakeFinished, BakeProgress, BakeReflections, BakeReflectionsTask, BakeVariation,
+        BakedDataAsset, BakedDataSaver, BakedProbeVolume, BakedReflections, ProbeVolume,
+    };
+    pub use crate::ray_tracer::{
+        GpuSimulationAvailable, RayTracerBackend, RayTracerFallback, SteamAudioInfo,
+    };
+    pub use crate::reflections::ReflectionConfig;
+    pub use crate::samples::{AudioData, SourceFactory, SpatialAudioErrorKind};
+    pub use crate::scene::{AudioObstacle, AudioSceneMesh, DynamicAudioGeometry};
+    pub use crate::settings::{
+        AudioConfig, AudioUnitsPerMeter, ContextConfig, EnvironmentPreset, FrameSize,
+        FrameSizeError, GlobalAudioSettings, HrtfConfig, SimulationConfig,
+    };
+    pub use crate::simulation::{
+        AudioSystemSet, DirectSimulationState, SimulationSource, SimulationSources,
+        SimulationTickRate,
+    };
+    pub use crate::sofa::{HrtfAsset, SofaHrtf};
+    pub use crate::source::{
+        listener_update, ActiveListener, HrtfFallback, HrtfSource, Listener, PrimaryListener,
+        SourceOrientation, SpatialAudioPlugin,
+    };
+    pub use crate::terrain::{AudioTerrain, TerrainHeights};
+    pub use crate::transmission::{Transmission, TransmissionConfig};
+    pub use crate::virtual_voice::{MaxVoices, SourcePriority, VirtualVoiceThreshold, VoiceCounts};
+    pub use crate::volume::{AudioBus, BusVolumes, FadeIn, VolumeScale};
     pub use steam_audio::prelude::*;
 }
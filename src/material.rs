@@ -0,0 +1,99 @@
+use bevy::{
+    prelude::{Component, Reflect, ReflectComponent, Resource},
+    utils::HashMap,
+};
+use steam_audio::{materials, prelude::Material};
+
+/// The acoustic material of a geometry entity, used when its mesh is added to the scene.
+///
+/// Geometry without this component uses [`AudioMaterial::Generic`]. The presets can be picked
+/// from an inspector, custom materials aren't reflected.
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub enum AudioMaterial {
+    #[default]
+    Generic,
+    Brick,
+    Carpet,
+    Ceramic,
+    Concrete,
+    Glass,
+    Gravel,
+    Metal,
+    Plaster,
+    Rock,
+    Wood,
+    /// Looked up in the [`MaterialLibrary`], falling back to generic if it isn't registered.
+    Named(String),
+    Custom(
+        #[reflect(ignore)]
+        #[reflect(default = "generic_material")]
+        Material,
+    ),
+}
+
+fn generic_material() -> Material {
+    materials::GENERIC
+}
+
+impl AudioMaterial {
+    pub fn resolve(&self, library: &MaterialLibrary) -> Material {
+        match self {
+            Self::Generic => materials::GENERIC,
+            Self::Brick => materials::BRICK,
+            Self::Carpet => materials::CARPET,
+            Self::Ceramic => materials::CERAMIC,
+            Self::Concrete => materials::CONCRETE,
+            Self::Glass => materials::GLASS,
+            Self::Gravel => materials::GRAVEL,
+            Self::Metal => materials::METAL,
+            Self::Plaster => materials::PLASTER,
+            Self::Rock => materials::ROCK,
+            Self::Wood => materials::WOOD,
+            Self::Named(name) => library.get(name).unwrap_or(materials::GENERIC),
+            Self::Custom(material) => *material,
+        }
+    }
+}
+
+/// Materials registered by name, so they can be referenced from [`AudioMaterial::Named`].
+///
+/// Starts out with the built in presets under their lowercase names, e.g. `"concrete"`.
+#[derive(Resource, Debug, Clone)]
+pub struct MaterialLibrary {
+    materials: HashMap<String, Material>,
+}
+
+impl MaterialLibrary {
+    pub fn register(&mut self, name: impl Into<String>, material: Material) -> &mut Self {
+        self.materials.insert(name.into(), material);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Material> {
+        self.materials.get(name).copied()
+    }
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        let mut library = Self {
+            materials: HashMap::default(),
+        };
+
+        library
+            .register("generic", materials::GENERIC)
+            .register("brick", materials::BRICK)
+            .register("carpet", materials::CARPET)
+            .register("ceramic", materials::CERAMIC)
+            .register("concrete", materials::CONCRETE)
+            .register("glass", materials::GLASS)
+            .register("gravel", materials::GRAVEL)
+            .register("metal", materials::METAL)
+            .register("plaster", materials::PLASTER)
+            .register("rock", materials::ROCK)
+            .register("wood", materials::WOOD);
+
+        library
+    }
+}
@@ -0,0 +1,852 @@
+use bevy::{
+    log::{debug, warn},
+    math::{IVec3, Vec3},
+    prelude::{Component, GlobalTransform, Mesh, Transform},
+    render::{
+        mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+        render_resource::{PrimitiveTopology, VertexFormat},
+    },
+    utils::HashMap,
+};
+
+/// Per-vertex index into the [`MaterialPalette`] of the mesh entity.
+///
+/// The material of a triangle is read from its first vertex.
+pub const ATTRIBUTE_AUDIO_MATERIAL: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Vertex_AudioMaterial",
+    0x5354_4541_4d41_5544,
+    VertexFormat::Uint32,
+);
+
+/// Vertices closer than this are merged when obstacles are added to the scene, see
+/// [`AudioMesh::validate_and_weld`].
+pub const WELD_TOLERANCE: f32 = 1e-4;
+
+/// The materials indexed by [`ATTRIBUTE_AUDIO_MATERIAL`] on the entity's mesh.
+#[derive(Component, Debug, Clone, Default)]
+pub struct MaterialPalette(pub Vec<steam_audio::prelude::Material>);
+
+#[derive(Default)]
+pub struct AudioMesh {
+    pub vertices: Vec<Vec3>,
+    pub triangles: Vec<[u32; 3]>,
+    pub materials: Vec<steam_audio::prelude::Material>,
+    pub material_indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AudioMeshError {
+    #[error("mesh has no vertex positions")]
+    MissingPositions,
+    #[error("mesh positions are {0:?}, expected Float32x3")]
+    WrongPositionFormat(VertexFormat),
+    #[error("mesh topology is {0:?}, expected a triangle list or strip")]
+    NonTrianglePrimitiveTopology(PrimitiveTopology),
+    #[error("mesh has no vertices")]
+    EmptyGeometry,
+    /// Every triangle has zero area, or there are none at all.
+    #[error("mesh has no triangles with any area")]
+    DegenerateMesh,
+    /// Returned instead of dropping the broken triangles by [`AudioMesh::from_mesh`] in strict
+    /// mode.
+    #[error("mesh has {} invalid triangles", .0.len())]
+    InvalidGeometry(Vec<AudioMeshWarning>),
+    /// A triangle of the mesh uses a material past the end of its [`MaterialPalette`].
+    #[error("mesh uses material {index}, but the palette only has {len}")]
+    MissingPaletteMaterial { index: u32, len: usize },
+    #[error("heightfield has {len} samples, expected {rows}x{cols}")]
+    HeightfieldSize {
+        len: usize,
+        rows: usize,
+        cols: usize,
+    },
+}
+
+/// A problem found by [`AudioMesh::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AudioMeshWarning {
+    #[error("triangle {0} has zero area")]
+    DegenerateTriangle(usize),
+    /// Split normals and UVs duplicate positions in most meshes, so these are only reported.
+    #[error("vertex {1} has the same position as vertex {0}")]
+    DuplicateVertex(usize, usize),
+    /// Steam Audio would read the vertex straight from out of bounds memory.
+    #[error("triangle {triangle} uses vertex {index}, which doesn't exist")]
+    OutOfRangeIndex { triangle: usize, index: u32 },
+}
+
+impl AudioMeshWarning {
+    /// Whether the triangle is dropped, or the conversion fails in strict mode.
+    fn is_invalid(&self) -> bool {
+        !matches!(self, Self::DuplicateVertex(..))
+    }
+}
+
+impl AudioMesh {
+    /// Converts `mesh`, dropping degenerate triangles and triangles with out of range indices.
+    ///
+    /// Problems are logged, in `strict` mode any broken triangle fails the conversion with
+    /// [`AudioMeshError::InvalidGeometry`] instead.
+    pub fn from_mesh(mesh: &Mesh, strict: bool) -> Result<Self, AudioMeshError> {
+        let mut audio_mesh = Self::from_mesh_unchecked(mesh)?;
+
+        let warnings = audio_mesh.validate();
+        if strict && warnings.iter().any(AudioMeshWarning::is_invalid) {
+            return Err(AudioMeshError::InvalidGeometry(
+                warnings
+                    .into_iter()
+                    .filter(AudioMeshWarning::is_invalid)
+                    .collect(),
+            ));
+        }
+        for warning in &warnings {
+            if warning.is_invalid() {
+                warn!("Audio mesh {warning}, dropping it");
+            } else {
+                debug!("Audio mesh {warning}");
+            }
+        }
+
+        // Converted meshes have one material index per triangle.
+        let kept: Vec<_> = audio_mesh
+            .triangles
+            .iter()
+            .copied()
+            .zip(audio_mesh.material_indices.iter().copied())
+            .filter(|(triangle, _)| audio_mesh.is_valid(triangle))
+            .collect();
+        (audio_mesh.triangles, audio_mesh.material_indices) = kept.into_iter().unzip();
+
+        if audio_mesh.triangles.is_empty() {
+            return Err(AudioMeshError::DegenerateMesh);
+        }
+        Ok(audio_mesh)
+    }
+
+    /// Lists the degenerate triangles, out of range indices and duplicate vertices.
+    pub fn validate(&self) -> Vec<AudioMeshWarning> {
+        let mut warnings = Vec::new();
+
+        for (triangle, indices) in self.triangles.iter().enumerate() {
+            let out_of_range: Vec<_> = indices
+                .iter()
+                .filter(|index| **index as usize >= self.vertices.len())
+                .map(|&index| AudioMeshWarning::OutOfRangeIndex { triangle, index })
+                .collect();
+            if !out_of_range.is_empty() {
+                warnings.extend(out_of_range);
+            } else if !self.is_valid(indices) {
+                warnings.push(AudioMeshWarning::DegenerateTriangle(triangle));
+            }
+        }
+
+        let mut positions = HashMap::new();
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            let bits = vertex.to_array().map(f32::to_bits);
+            if let Some(&first) = positions.get(&bits) {
+                warnings.push(AudioMeshWarning::DuplicateVertex(first, index));
+            } else {
+                positions.insert(bits, index);
+            }
+        }
+
+        warnings
+    }
+
+    /// Merges vertices closer than `tolerance` to each other and drops the triangles left
+    /// without area or with out of range indices, logging the counts before and after.
+    ///
+    /// Steam Audio's ray tracer misses and double hits around degenerate triangles and cracks
+    /// between duplicated vertices, which shows as flickering occlusion.
+    pub fn validate_and_weld(mut self, tolerance: f32) -> Result<Self, AudioMeshError> {
+        let (vertices_before, triangles_before) = (self.vertices.len(), self.triangles.len());
+
+        // Vertices within the tolerance are at most one grid cell apart.
+        let cell_size = tolerance.max(f32::EPSILON);
+        let cell = |vertex: Vec3| (vertex / cell_size).floor().as_ivec3();
+        let mut grid: HashMap<IVec3, Vec<u32>> = HashMap::new();
+        let mut welded: Vec<Vec3> = Vec::new();
+        let remap: Vec<u32> = self
+            .vertices
+            .iter()
+            .map(|&vertex| {
+                let center = cell(vertex);
+                let neighbours = (-1..=1).flat_map(|x| {
+                    (-1..=1).flat_map(move |y| (-1..=1).map(move |z| center + IVec3::new(x, y, z)))
+                });
+                let existing = neighbours
+                    .filter_map(|neighbour| grid.get(&neighbour))
+                    .flatten()
+                    .copied()
+                    .find(|&index| welded[index as usize].distance(vertex) <= tolerance);
+
+                existing.unwrap_or_else(|| {
+                    let index = welded.len() as u32;
+                    welded.push(vertex);
+                    grid.entry(center).or_default().push(index);
+                    index
+                })
+            })
+            .collect();
+
+        let remapped: Vec<_> = self
+            .triangles
+            .iter()
+            .map(|triangle| triangle.map(|index| remap.get(index as usize).copied()))
+            .collect();
+        self.vertices = welded;
+
+        // Materials stay per triangle when they are.
+        let per_triangle = self.material_indices.len() == self.triangles.len();
+        let mut material_indices = Vec::new();
+        let mut triangles = Vec::new();
+        for (index, triangle) in remapped.into_iter().enumerate() {
+            let [Some(a), Some(b), Some(c)] = triangle else {
+                continue;
+            };
+            if !self.is_valid(&[a, b, c]) {
+                continue;
+            }
+
+            triangles.push([a, b, c]);
+            if per_triangle {
+                material_indices.push(self.material_indices[index]);
+            }
+        }
+        self.triangles = triangles;
+        if per_triangle {
+            self.material_indices = material_indices;
+        }
+
+        debug!(
+            "Welded audio mesh from {vertices_before} vertices and {triangles_before} triangles to \
+             {} vertices and {} triangles",
+            self.vertices.len(),
+            self.triangles.len(),
+        );
+
+        if self.triangles.is_empty() {
+            return Err(AudioMeshError::DegenerateMesh);
+        }
+        Ok(self)
+    }
+
+    /// In range and with some area.
+    fn is_valid(&self, triangle: &[u32; 3]) -> bool {
+        let vertex = |index: u32| self.vertices.get(index as usize).copied();
+        let (Some(a), Some(b), Some(c)) = (
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2]),
+        ) else {
+            return false;
+        };
+        (b - a).cross(c - a).length_squared() > 0.0
+    }
+
+    /// Assigns `material` to every triangle of the mesh.
+    pub fn with_material(mut self, material: steam_audio::prelude::Material) -> Self {
+        self.materials = vec![material];
+        self.material_indices = self.triangles.iter().map(|_| 0).collect();
+        self
+    }
+
+    /// Uses `palette` for the per-triangle indices read from [`ATTRIBUTE_AUDIO_MATERIAL`].
+    ///
+    /// Fails when an index is past the end of the palette, Steam Audio would read the material
+    /// from out of bounds memory.
+    pub fn with_palette(mut self, palette: &MaterialPalette) -> Result<Self, AudioMeshError> {
+        if let Some(&index) = self
+            .material_indices
+            .iter()
+            .max()
+            .filter(|&&index| index as usize >= palette.0.len())
+        {
+            return Err(AudioMeshError::MissingPaletteMaterial {
+                index,
+                len: palette.0.len(),
+            });
+        }
+
+        self.materials = palette.0.clone();
+        Ok(self)
+    }
+
+    /// Converts `mesh` with `transform` baked into its vertices, so the geometry lands in the
+    /// acoustic scene where the entity is rendered.
+    pub fn from_mesh_transformed(
+        mesh: &Mesh,
+        transform: &GlobalTransform,
+    ) -> Result<Self, AudioMeshError> {
+        Ok(Self::try_from(mesh)?.transformed(transform))
+    }
+
+    /// Bakes `transform` into the vertices.
+    pub fn transformed(mut self, transform: &GlobalTransform) -> Self {
+        for vertex in self.vertices.iter_mut() {
+            *vertex = transform.transform_point(*vertex);
+        }
+
+        // Mirroring transforms turn the triangles inside out.
+        if transform.affine().matrix3.determinant() < 0.0 {
+            for triangle in self.triangles.iter_mut() {
+                triangle.swap(1, 2);
+            }
+        }
+
+        self
+    }
+
+    /// A triangle grid over `heights`, `rows` by `cols` samples in row-major order, spaced
+    /// `cell_size` apart along X for columns and Z for rows and centered on the entity.
+    ///
+    /// Cells with a NaN corner are left out, so holes like cave mouths stay open.
+    pub fn from_heightfield(
+        heights: &[f32],
+        rows: usize,
+        cols: usize,
+        cell_size: f32,
+        transform: &GlobalTransform,
+    ) -> Result<Self, AudioMeshError> {
+        Ok(
+            Self::from_heightfield_strided(heights, rows, cols, cell_size, 1)?
+                .transformed(transform),
+        )
+    }
+
+    /// [`Self::from_heightfield`] in local space, keeping every `stride`th sample. The last row
+    /// and column are always kept so the grid still covers the whole heightfield.
+    ///
+    /// A decimated cell is left out when any sample it covers is a hole, not just its corners.
+    pub(crate) fn from_heightfield_strided(
+        heights: &[f32],
+        rows: usize,
+        cols: usize,
+        cell_size: f32,
+        stride: usize,
+    ) -> Result<Self, AudioMeshError> {
+        if heights.len() != rows * cols {
+            return Err(AudioMeshError::HeightfieldSize {
+                len: heights.len(),
+                rows,
+                cols,
+            });
+        }
+        if rows < 2 || cols < 2 {
+            return Err(AudioMeshError::EmptyGeometry);
+        }
+
+        let kept = |len: usize| {
+            let mut kept: Vec<usize> = (0..len).step_by(stride.max(1)).collect();
+            if kept.last() != Some(&(len - 1)) {
+                kept.push(len - 1);
+            }
+            kept
+        };
+        let (kept_rows, kept_cols) = (kept(rows), kept(cols));
+        let center = Vec3::new((cols - 1) as f32, 0.0, (rows - 1) as f32) * cell_size * 0.5;
+
+        // The vertex of each kept sample, `None` for holes.
+        let mut vertices = Vec::new();
+        let mut indices = Vec::with_capacity(kept_rows.len() * kept_cols.len());
+        for &row in &kept_rows {
+            for &col in &kept_cols {
+                let height = heights[row * cols + col];
+                indices.push(height.is_finite().then(|| {
+                    let position = Vec3::new(col as f32, 0.0, row as f32) * cell_size;
+                    vertices.push(position + Vec3::Y * height - center);
+                    vertices.len() as u32 - 1
+                }));
+            }
+        }
+
+        let width = kept_cols.len();
+        let mut triangles = Vec::new();
+        for row in 0..kept_rows.len() - 1 {
+            for col in 0..width - 1 {
+                let hole = (kept_rows[row]..=kept_rows[row + 1]).any(|sample_row| {
+                    (kept_cols[col]..=kept_cols[col + 1])
+                        .any(|sample_col| !heights[sample_row * cols + sample_col].is_finite())
+                });
+                if hole {
+                    continue;
+                }
+
+                let corner = |row: usize, col: usize| indices[row * width + col];
+                let (Some(a), Some(b), Some(c), Some(d)) = (
+                    corner(row, col),
+                    corner(row, col + 1),
+                    corner(row + 1, col),
+                    corner(row + 1, col + 1),
+                ) else {
+                    continue;
+                };
+                // Counter-clockwise seen from above, facing up.
+                triangles.push([a, c, b]);
+                triangles.push([b, c, d]);
+            }
+        }
+        if triangles.is_empty() {
+            return Err(AudioMeshError::DegenerateMesh);
+        }
+
+        Ok(Self {
+            vertices,
+            triangles,
+            ..Default::default()
+        })
+    }
+
+    /// The material of the first triangle crossed walking from `from` to `to`, `None` when the
+    /// segment is unobstructed.
+    pub fn first_hit(&self, from: Vec3, to: Vec3) -> Option<steam_audio::prelude::Material> {
+        let segment = to - from;
+        let mut closest: Option<(f32, usize)> = None;
+
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            let [a, b, c] = triangle.map(|vertex| self.vertices[vertex as usize]);
+            let Some(t) = intersect_segment(from, segment, a, b, c) else {
+                continue;
+            };
+
+            if closest.map_or(true, |(closest, _)| t < closest) {
+                closest = Some((t, index));
+            }
+        }
+
+        let (_, triangle) = closest?;
+        let material = self.material_indices.get(triangle).copied().unwrap_or(0);
+        self.materials.get(material as usize).copied()
+    }
+}
+
+/// Möller–Trumbore intersection of `from + t * segment` with a triangle, for `t` in `(0, 1)`.
+fn intersect_segment(from: Vec3, segment: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = segment.cross(edge2);
+    let determinant = edge1.dot(p);
+    // Both faces count, walls aren't always closed meshes.
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inverse = 1.0 / determinant;
+    let offset = from - a;
+    let u = offset.dot(p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = offset.cross(edge1);
+    let v = segment.dot(q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse;
+    (t > 0.0 && t < 1.0).then_some(t)
+}
+
+/// Merges several meshes into one world space [`AudioMesh`].
+#[derive(Default)]
+pub struct AudioSceneBuilder {
+    mesh: AudioMesh,
+}
+
+impl AudioSceneBuilder {
+    /// Appends `mesh` transformed into world space, with `material` on all of its triangles.
+    pub fn add_mesh(
+        &mut self,
+        mesh: &Mesh,
+        transform: Transform,
+        material: steam_audio::prelude::Material,
+    ) -> Result<&mut Self, AudioMeshError> {
+        let audio_mesh =
+            AudioMesh::from_mesh_transformed(mesh, &transform.into())?.with_material(material);
+        Ok(self.add_audio_mesh(audio_mesh))
+    }
+
+    /// Appends an already world space `audio_mesh`, keeping its per-triangle materials.
+    pub fn add_audio_mesh(&mut self, audio_mesh: AudioMesh) -> &mut Self {
+        let vertex_offset = self.mesh.vertices.len() as u32;
+        let material_offset = self.mesh.materials.len() as u32;
+
+        self.mesh.vertices.extend(audio_mesh.vertices);
+        self.mesh.triangles.extend(
+            audio_mesh
+                .triangles
+                .iter()
+                .map(|triangle| triangle.map(|index| index + vertex_offset)),
+        );
+        self.mesh.materials.extend(audio_mesh.materials);
+        self.mesh.material_indices.extend(
+            audio_mesh
+                .material_indices
+                .iter()
+                .map(|index| index + material_offset),
+        );
+
+        self
+    }
+
+    pub fn build(self) -> AudioMesh {
+        self.mesh
+    }
+}
+
+impl TryFrom<Mesh> for AudioMesh {
+    type Error = AudioMeshError;
+    fn try_from(mesh: Mesh) -> Result<Self, Self::Error> {
+        Self::try_from(&mesh)
+    }
+}
+
+impl TryFrom<&Mesh> for AudioMesh {
+    type Error = AudioMeshError;
+    fn try_from(mesh: &Mesh) -> Result<Self, Self::Error> {
+        Self::from_mesh(mesh, false)
+    }
+}
+
+impl AudioMesh {
+    /// Reads the triangles of `mesh` as they are, see [`AudioMesh::from_mesh`].
+    fn from_mesh_unchecked(mesh: &Mesh) -> Result<Self, AudioMeshError> {
+        let vertices: Vec<Vec3> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(vertices)) => {
+                vertices.iter().map(|a| (*a).into()).collect()
+            }
+            Some(positions) => {
+                return Err(AudioMeshError::WrongPositionFormat(VertexFormat::from(
+                    positions,
+                )))
+            }
+            None => return Err(AudioMeshError::MissingPositions),
+        };
+        if vertices.is_empty() {
+            return Err(AudioMeshError::EmptyGeometry);
+        }
+
+        let indices: Vec<u32> = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.iter().map(|indices| *indices as u32).collect(),
+            Some(Indices::U32(indices)) => indices.iter().map(|indices| *indices).collect(),
+            // Non-indexed meshes use the vertices in order, every three of them forming a
+            // triangle in a list and every window of three in a strip.
+            None => (0..vertices.len() as u32).collect(),
+        };
+
+        let triangles: Vec<[u32; 3]> = match mesh.primitive_topology() {
+            PrimitiveTopology::TriangleList => indices
+                .chunks_exact(3)
+                .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+                .collect(),
+            PrimitiveTopology::TriangleStrip => {
+                let mut indices: Vec<_> = indices
+                    .windows(3)
+                    .map(|indices| [indices[0], indices[1], indices[2]])
+                    .collect();
+
+                for (index, indices) in indices.iter_mut().enumerate() {
+                    if (index + 1) % 2 == 0 {
+                        *indices = [indices[1], indices[0], indices[2]];
+                    }
+                }
+
+                indices
+            }
+            topology => return Err(AudioMeshError::NonTrianglePrimitiveTopology(topology)),
+        };
+
+        let (materials, material_indices) = match mesh.attribute(ATTRIBUTE_AUDIO_MATERIAL) {
+            Some(VertexAttributeValues::Uint32(vertex_materials)) => {
+                let material_indices: Vec<u32> = triangles
+                    .iter()
+                    .map(|triangle: &[u32; 3]| {
+                        vertex_materials
+                            .get(triangle[0] as usize)
+                            .copied()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+
+                // Generic until a palette is applied.
+                let count = material_indices
+                    .iter()
+                    .max()
+                    .map_or(1, |max| *max as usize + 1);
+                (
+                    vec![steam_audio::materials::GENERIC; count],
+                    material_indices,
+                )
+            }
+            _ => (
+                vec![steam_audio::materials::GENERIC],
+                triangles.iter().map(|_| 0 /* GENERIC index */).collect(),
+            ),
+        };
+
+        Ok(Self {
+            vertices: vertices,
+            triangles: triangles,
+            materials: materials,
+            material_indices: material_indices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::RenderAssetUsages,
+        math::primitives::{Cuboid, Plane3d},
+    };
+
+    use super::*;
+
+    fn mesh(topology: PrimitiveTopology, positions: Vec<[f32; 3]>) -> Mesh {
+        Mesh::new(topology, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    }
+
+    /// Two triangles of a unit quad on the XZ plane.
+    fn quad() -> AudioMesh {
+        AudioMesh {
+            vertices: vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 0.0, 1.0), Vec3::Z],
+            triangles: vec![[0, 2, 1], [0, 3, 2]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn heightfield_grids_need_not_be_square() {
+        let heights: Vec<f32> = (0..15).map(|index| index as f32).collect();
+        let mesh = AudioMesh::from_heightfield_strided(&heights, 3, 5, 2.0, 1).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 15);
+        assert_eq!(mesh.triangles.len(), 2 * 4 * 2);
+        // Columns along X, rows along Z, centered.
+        assert_eq!(mesh.vertices[0], Vec3::new(-4.0, 0.0, -2.0));
+        assert_eq!(mesh.vertices[14], Vec3::new(4.0, 14.0, 2.0));
+
+        assert_eq!(
+            AudioMesh::from_heightfield_strided(&heights, 5, 5, 1.0, 1).err(),
+            Some(AudioMeshError::HeightfieldSize {
+                len: 15,
+                rows: 5,
+                cols: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn heightfield_holes_drop_their_cells() {
+        let mut heights = vec![0.0; 25];
+        heights[2 * 5 + 2] = f32::NAN;
+
+        // The four cells around the hole.
+        let mesh = AudioMesh::from_heightfield_strided(&heights, 5, 5, 1.0, 1).unwrap();
+        assert_eq!(mesh.triangles.len(), (16 - 4) * 2);
+
+        // A hole inside a decimated cell drops it even though its corners are fine.
+        let mut heights = vec![0.0; 25];
+        heights[5 + 1] = f32::NAN;
+        let mesh = AudioMesh::from_heightfield_strided(&heights, 5, 5, 1.0, 2).unwrap();
+        assert_eq!(mesh.triangles.len(), 3 * 2);
+
+        assert_eq!(
+            AudioMesh::from_heightfield_strided(&[f32::NAN; 4], 2, 2, 1.0, 1).err(),
+            Some(AudioMeshError::DegenerateMesh)
+        );
+    }
+
+    #[test]
+    fn non_indexed_cuboids_keep_their_triangles() {
+        let cuboid = Mesh::from(Cuboid::default()).with_duplicated_vertices();
+        assert!(cuboid.indices().is_none());
+
+        let audio_mesh = AudioMesh::try_from(&cuboid).unwrap();
+        assert_eq!(audio_mesh.vertices.len(), 36);
+        assert_eq!(audio_mesh.triangles.len(), 12);
+        assert_eq!(audio_mesh.triangles[11], [33, 34, 35]);
+        // Still closed, so it blocks a segment through it.
+        assert!(audio_mesh
+            .first_hit(Vec3::new(0.1, 0.2, -2.0), Vec3::new(0.1, 0.2, 2.0))
+            .is_some());
+    }
+
+    #[test]
+    fn non_indexed_planes_keep_their_triangles() {
+        let plane = Mesh::from(Plane3d::default()).with_duplicated_vertices();
+        assert!(plane.indices().is_none());
+
+        let audio_mesh = AudioMesh::try_from(&plane).unwrap();
+        assert_eq!(audio_mesh.vertices.len(), 6);
+        assert_eq!(audio_mesh.triangles, [[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(audio_mesh.material_indices.len(), 2);
+
+        // Every window of three vertices in a strip.
+        let strip = mesh(
+            PrimitiveTopology::TriangleStrip,
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+            ],
+        );
+        let audio_mesh = AudioMesh::try_from(strip).unwrap();
+        assert_eq!(audio_mesh.triangles, [[0, 1, 2], [2, 1, 3]]);
+
+        assert_eq!(
+            AudioMesh::try_from(mesh(PrimitiveTopology::TriangleList, Vec::new())).err(),
+            Some(AudioMeshError::EmptyGeometry)
+        );
+    }
+
+    #[test]
+    fn degenerate_triangles_are_reported() {
+        // A quad and a sliver whose corners are on a line.
+        let list = mesh(
+            PrimitiveTopology::TriangleList,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0],
+                [2.0, 0.0, 0.0],
+            ],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 2, 1, 0, 3, 2, 0, 1, 4]));
+
+        let audio_mesh = AudioMesh::from_mesh_unchecked(&list).unwrap();
+        assert_eq!(
+            audio_mesh.validate(),
+            [AudioMeshWarning::DegenerateTriangle(2)]
+        );
+
+        assert_eq!(
+            AudioMesh::from_mesh(&list, true).err(),
+            Some(AudioMeshError::InvalidGeometry(vec![
+                AudioMeshWarning::DegenerateTriangle(2)
+            ]))
+        );
+        let lenient = AudioMesh::from_mesh(&list, false).unwrap();
+        assert_eq!(lenient.triangles, [[0, 2, 1], [0, 3, 2]]);
+        assert_eq!(lenient.material_indices, [0, 0]);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_reported() {
+        let mesh = AudioMesh {
+            triangles: vec![[0, 2, 1], [0, 3, 7]],
+            ..quad()
+        };
+
+        assert_eq!(
+            mesh.validate(),
+            [AudioMeshWarning::OutOfRangeIndex {
+                triangle: 1,
+                index: 7
+            }]
+        );
+    }
+
+    #[test]
+    fn welding_drops_strip_restarts_and_merges_close_vertices() {
+        // Two quads side by side as separate strips joined by repeated indices, the second
+        // starting on copies of the first one's last edge that are slightly off.
+        let offset = 1e-5;
+        let strip = mesh(
+            PrimitiveTopology::TriangleStrip,
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+                [1.0 + offset, 0.0, 0.0],
+                [1.0 + offset, 0.0, 1.0],
+                [2.0, 0.0, 0.0],
+                [2.0, 0.0, 1.0],
+            ],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 3, 3, 4, 4, 5, 6, 7]));
+
+        let mut audio_mesh = AudioMesh::from_mesh_unchecked(&strip).unwrap();
+        assert_eq!(audio_mesh.triangles.len(), 8);
+        audio_mesh.material_indices = (0..8).collect();
+
+        let welded = audio_mesh.validate_and_weld(WELD_TOLERANCE).unwrap();
+        assert_eq!(welded.vertices.len(), 6);
+        assert_eq!(welded.triangles.len(), 4);
+        assert!(welded.validate().is_empty());
+        // The restart triangles are gone, their neighbours keep their materials.
+        assert_eq!(welded.material_indices, [0, 1, 6, 7]);
+
+        // Without a tolerance only the repeated indices go.
+        let unwelded = AudioMesh::from_mesh_unchecked(&strip)
+            .unwrap()
+            .validate_and_weld(0.0)
+            .unwrap();
+        assert_eq!(unwelded.vertices.len(), 8);
+        assert_eq!(unwelded.triangles.len(), 4);
+    }
+
+    #[test]
+    fn scene_builder_keeps_each_meshes_material() {
+        let mut builder = AudioSceneBuilder::default();
+        builder
+            .add_mesh(
+                &Mesh::from(Plane3d::default()),
+                Transform::default(),
+                steam_audio::materials::CARPET,
+            )
+            .unwrap()
+            .add_mesh(
+                &Mesh::from(Cuboid::default()),
+                Transform::from_xyz(0.0, 2.0, 0.0),
+                steam_audio::materials::CONCRETE,
+            )
+            .unwrap();
+        let scene = builder.build();
+
+        assert_eq!(scene.vertices.len(), 4 + 24);
+        assert_eq!(scene.materials.len(), 2);
+        assert_eq!(scene.material_indices.len(), scene.triangles.len());
+        assert_eq!(scene.material_indices[..2], [0; 2]);
+        assert_eq!(scene.material_indices[2..], [1; 12]);
+
+        // The cuboid's triangles point at its own vertices, moved into place.
+        assert!(scene.triangles[2..]
+            .iter()
+            .flatten()
+            .all(|&index| index >= 4));
+        assert!(scene.vertices[4..].iter().all(|vertex| vertex.y >= 1.5));
+    }
+
+    #[test]
+    fn palette_must_cover_every_material_index() {
+        let mesh = AudioMesh {
+            material_indices: vec![0, 2],
+            ..quad()
+        };
+        let palette = MaterialPalette(vec![steam_audio::materials::GENERIC; 2]);
+
+        assert_eq!(
+            mesh.with_palette(&palette).err(),
+            Some(AudioMeshError::MissingPaletteMaterial { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn palette_replaces_materials() {
+        let mesh = AudioMesh {
+            material_indices: vec![0, 2],
+            ..quad()
+        };
+        let palette = MaterialPalette(vec![steam_audio::materials::GENERIC; 3]);
+
+        let mesh = mesh.with_palette(&palette).unwrap();
+        assert_eq!(mesh.materials.len(), 3);
+        assert_eq!(mesh.material_indices, [0, 2]);
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::prelude::{
+    Added, Changed, Component, DetectChanges, Or, Query, Reflect, ReflectComponent,
+    ReflectResource, Res, Resource,
+};
+
+use crate::{params::Snapshot, playback::SpatialAudioSource, source::SpatialAudioSettings};
+
+/// How much of a source goes through the spatial pipeline, clamped to `[0.0, 1.0]`.
+///
+/// At `0.0` the direct and binaural effects are bypassed and the source plays equally in both
+/// ears regardless of distance, which suits music and UI sounds. At `1.0` (the default) the full
+/// Steam Audio pipeline runs. Values in between scale the binaural blend and the distance
+/// attenuation, and changes are eased in over a few blocks.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct SpatialBlend(pub f32);
+
+impl Default for SpatialBlend {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+pub fn update_spatial_blend(
+    query: Query<
+        (&SpatialAudioSource, &SpatialBlend),
+        Or<(Changed<SpatialBlend>, Added<SpatialAudioSource>)>,
+    >,
+) {
+    for (source, blend) in query.iter() {
+        source.voice.spatial_blend.store(blend.0.clamp(0.0, 1.0));
+    }
+}
+
+/// Output gains of a source, for mixing.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct SourceMix {
+    /// Gain of the direct path.
+    pub direct_gain: f32,
+    /// Send of the source into the listener's reverb, the simulated reflections mixed into the
+    /// shared bed. Footsteps usually want a low send, gunshots a high one, `0.0` leaves the
+    /// source out of the reverb entirely. Changes are ramped over a block.
+    pub wet_gain: f32,
+    /// How much of the unprocessed signal replaces the output, `1.0` plays the decoded file
+    /// untouched.
+    pub dry_bypass: f32,
+}
+
+impl Default for SourceMix {
+    fn default() -> Self {
+        Self {
+            direct_gain: 1.0,
+            wet_gain: 1.0,
+            dry_bypass: 0.0,
+        }
+    }
+}
+
+impl Snapshot for SourceMix {
+    const WORDS: usize = 3;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words.copy_from_slice(&[self.direct_gain, self.wet_gain, self.dry_bypass]);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        Self {
+            direct_gain: words[0],
+            wet_gain: words[1],
+            dry_bypass: words[2],
+        }
+    }
+}
+
+pub fn update_source_mix(
+    query: Query<
+        (&SpatialAudioSource, &SourceMix),
+        Or<(Changed<SourceMix>, Added<SpatialAudioSource>)>,
+    >,
+) {
+    for (source, mix) in query.iter() {
+        source.voice.mix.store(SourceMix {
+            dry_bypass: mix.dry_bypass.clamp(0.0, 1.0),
+            ..*mix
+        });
+    }
+}
+
+/// Frames every voice takes to ease to new [`SteamAudioMixer`] levels.
+pub const MIXER_RAMP_FRAMES: u32 = 512;
+
+/// Levels of the simulation effects shared by every voice, like a dry switch for debugging or
+/// reverb that grows as the player progresses.
+///
+/// Voices pick changes up at their next block and ease to them over [`MIXER_RAMP_FRAMES`].
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct SteamAudioMixer {
+    /// Gain of the direct path.
+    pub direct_level: f32,
+    /// Gain of the [`ConvolutionReverbSend`](crate::convolution::ConvolutionReverbSend)s.
+    pub reverb_level: f32,
+    /// Wet level of the reverb bed, scaling the send of every source.
+    pub reflection_level: f32,
+    /// Gain of the paths found around geometry.
+    pub pathing_level: f32,
+    /// Gain of every voice after all of its effects.
+    pub master_gain: f32,
+}
+
+impl Default for SteamAudioMixer {
+    fn default() -> Self {
+        Self {
+            direct_level: 1.0,
+            reverb_level: 1.0,
+            reflection_level: 1.0,
+            pathing_level: 1.0,
+            master_gain: 1.0,
+        }
+    }
+}
+
+impl Snapshot for SteamAudioMixer {
+    const WORDS: usize = 5;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words.copy_from_slice(&[
+            self.direct_level,
+            self.reverb_level,
+            self.reflection_level,
+            self.pathing_level,
+            self.master_gain,
+        ]);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        Self {
+            direct_level: words[0],
+            reverb_level: words[1],
+            reflection_level: words[2],
+            pathing_level: words[3],
+            master_gain: words[4],
+        }
+    }
+}
+
+pub fn update_mixer(mixer: Res<SteamAudioMixer>, settings: Res<SpatialAudioSettings>) {
+    if !mixer.is_changed() {
+        return;
+    }
+
+    settings.mixer.store(SteamAudioMixer {
+        direct_level: mixer.direct_level.max(0.0),
+        reverb_level: mixer.reverb_level.max(0.0),
+        reflection_level: mixer.reflection_level.max(0.0),
+        pathing_level: mixer.pathing_level.max(0.0),
+        master_gain: mixer.master_gain.max(0.0),
+    });
+}
+
+/// A voice's [`SteamAudioMixer`] levels, easing linearly from the previous levels to the latest.
+pub(crate) struct MixerRamp {
+    from: SteamAudioMixer,
+    to: SteamAudioMixer,
+    /// Frames of the ease already played.
+    done: u32,
+}
+
+impl MixerRamp {
+    pub(crate) fn new(levels: SteamAudioMixer) -> Self {
+        Self {
+            from: levels,
+            to: levels,
+            done: MIXER_RAMP_FRAMES,
+        }
+    }
+
+    fn at(&self, level: fn(&SteamAudioMixer) -> f32, frame: u32) -> f32 {
+        let t = (frame as f32 / MIXER_RAMP_FRAMES as f32).min(1.0);
+        let (from, to) = (level(&self.from), level(&self.to));
+        from + (to - from) * t
+    }
+
+    /// Starts easing towards `levels` from wherever the current ease is.
+    pub(crate) fn set_target(&mut self, levels: SteamAudioMixer) {
+        if levels == self.to {
+            return;
+        }
+
+        let current = |level: fn(&SteamAudioMixer) -> f32| self.at(level, self.done);
+        let from = SteamAudioMixer {
+            direct_level: current(|mixer| mixer.direct_level),
+            reverb_level: current(|mixer| mixer.reverb_level),
+            reflection_level: current(|mixer| mixer.reflection_level),
+            pathing_level: current(|mixer| mixer.pathing_level),
+            master_gain: current(|mixer| mixer.master_gain),
+        };
+        self.from = from;
+        self.to = levels;
+        self.done = 0;
+    }
+
+    /// Gains of `level` for each of the next `len` frames.
+    pub(crate) fn gains(&self, level: fn(&SteamAudioMixer) -> f32, len: usize) -> Vec<f32> {
+        (0..len as u32)
+            .map(|index| self.at(level, self.done + index + 1))
+            .collect()
+    }
+
+    /// The mean gain of `level` over the next `len` frames, for effects taking one gain a block.
+    pub(crate) fn mean(&self, level: fn(&SteamAudioMixer) -> f32, len: usize) -> f32 {
+        self.gains(level, len).iter().sum::<f32>() / len.max(1) as f32
+    }
+
+    /// Moves past a block of `len` frames.
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.done = (self.done + len as u32).min(MIXER_RAMP_FRAMES);
+    }
+}
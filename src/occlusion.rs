@@ -0,0 +1,81 @@
+use bevy::prelude::{Component, Reflect, ReflectComponent};
+use steam_audio::prelude::{OcclusionType, SimulationInputs};
+
+use crate::{coords::bevy_distance_to_phonon, settings::AudioUnitsPerMeter};
+
+/// How the simulator tests the line between a source and the listener for obstacles.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub enum OcclusionMode {
+    /// A single ray from the listener to the source, either occluded or not.
+    Raycast,
+    /// Rays to `samples` points of a sphere of `radius` around the source, so sources partially
+    /// hidden behind thin obstacles are partially occluded.
+    Volumetric { radius: f32, samples: u32 },
+}
+
+impl Default for OcclusionMode {
+    fn default() -> Self {
+        Self::Volumetric {
+            radius: 0.5,
+            samples: 16,
+        }
+    }
+}
+
+/// Occlusion settings of a source, sources without it use the default.
+///
+/// Occluded sources are filtered by their [`Transmission`](crate::transmission::Transmission),
+/// or only attenuated when transmission is disabled.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Occlusion {
+    pub mode: OcclusionMode,
+    /// Seconds the occlusion takes to move most of the way to a new simulated value, `0.0` to
+    /// follow the simulation as is.
+    pub smoothing: f32,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Self {
+            mode: OcclusionMode::default(),
+            smoothing: 0.1,
+        }
+    }
+}
+
+impl Occlusion {
+    /// Sets the occlusion inputs of a source with a [`SourceRadius`] of `radius` Bevy units,
+    /// volumetric occlusion tests at least that sphere.
+    ///
+    /// [`SourceRadius`]: crate::attenuation::SourceRadius
+    pub(crate) fn apply(
+        &self,
+        inputs: &mut SimulationInputs,
+        radius: f32,
+        units: AudioUnitsPerMeter,
+    ) {
+        match self.mode {
+            OcclusionMode::Raycast => inputs.occlusion_type = OcclusionType::Raycast,
+            OcclusionMode::Volumetric {
+                radius: occlusion_radius,
+                samples,
+            } => {
+                inputs.occlusion_type = OcclusionType::Volumetric;
+                inputs.occlusion_radius =
+                    bevy_distance_to_phonon(occlusion_radius.max(radius), units);
+                inputs.num_occlusion_samples = samples.max(1);
+            }
+        }
+    }
+
+    /// Moves `previous` towards the `simulated` occlusion over a frame of `delta` seconds.
+    pub(crate) fn smooth(&self, previous: f32, simulated: f32, delta: f32) -> f32 {
+        if self.smoothing <= 0.0 {
+            return simulated;
+        }
+
+        let blend = 1.0 - (-delta / self.smoothing).exp();
+        previous + (simulated - previous) * blend
+    }
+}
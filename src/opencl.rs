@@ -0,0 +1,166 @@
+use bevy::{log::warn, prelude::Reflect};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use steam_audio::{
+    hrtf::AudioSettings,
+    prelude::{
+        Context, OpenCLDevice, OpenCLDeviceList, OpenCLDeviceSettings, OpenCLDeviceType,
+        ReflectionEffectType, SimulationSettings, TrueAudioNextDevice, TrueAudioNextDeviceSettings,
+    },
+};
+
+use crate::reflections::ReflectionConfig;
+
+/// The OpenCL device Radeon Rays and TrueAudio Next run on.
+///
+/// TrueAudio Next convolves reflections on the GPU, it's behind the `trueaudio-next` cargo
+/// feature and falls back to the CPU when the device can't be created.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenClConfig {
+    /// Index of the device among the GPUs OpenCL lists.
+    pub device_index: u32,
+    /// Compute units reserved for TrueAudio Next, the rest are left to rendering.
+    pub reserved_compute_units: u32,
+    /// Voices convolved with TrueAudio Next at once, voices past it convolve on the CPU.
+    pub max_sources: u32,
+    /// Probes Radeon Rays bakes at once.
+    pub bake_batch_size: u32,
+}
+
+impl Default for OpenClConfig {
+    fn default() -> Self {
+        Self {
+            device_index: 0,
+            reserved_compute_units: 0,
+            max_sources: 32,
+            bake_batch_size: 8,
+        }
+    }
+}
+
+impl OpenClConfig {
+    pub(crate) fn device(&self, context: &Context) -> Result<OpenCLDevice, String> {
+        let devices = OpenCLDeviceList::new(
+            context,
+            &OpenCLDeviceSettings {
+                type_: OpenCLDeviceType::Gpu,
+                num_cus_to_reserve: self.reserved_compute_units,
+                // Half of the reserved units update impulse responses, half convolve.
+                fraction_cus_for_ir_update: 0.5,
+                requires_tan: cfg!(feature = "trueaudio-next"),
+            },
+        )
+        .map_err(|err| format!("could not list opencl devices: {err:?}"))?;
+
+        if self.device_index as usize >= devices.len() {
+            return Err(format!(
+                "no opencl device {}, {} found",
+                self.device_index,
+                devices.len()
+            ));
+        }
+        OpenCLDevice::new(context, &devices, self.device_index)
+            .map_err(|err| format!("could not create opencl device: {err:?}"))
+    }
+}
+
+/// The TrueAudio Next device reflections are convolved on, with one slot per voice using it.
+pub(crate) struct TrueAudioNext {
+    opencl: OpenCLDevice,
+    device: TrueAudioNextDevice,
+    slots: Box<[AtomicBool]>,
+}
+
+impl TrueAudioNext {
+    /// Creates the device when the feature is on and reflections are enabled, reusing the ray
+    /// tracer's OpenCL device if it has one. `None` convolves on the CPU.
+    pub(crate) fn new(
+        context: &Context,
+        opencl: Option<&OpenCLDevice>,
+        config: &OpenClConfig,
+        audio_settings: &AudioSettings,
+        reflections: &ReflectionConfig,
+    ) -> Option<Arc<Self>> {
+        if !cfg!(feature = "trueaudio-next") || !reflections.enabled {
+            return None;
+        }
+
+        match Self::try_new(context, opencl, config, audio_settings, reflections) {
+            Ok(tan) => Some(Arc::new(tan)),
+            Err(reason) => {
+                warn!("{reason}, convolving reflections on the CPU.");
+                None
+            }
+        }
+    }
+
+    fn try_new(
+        context: &Context,
+        opencl: Option<&OpenCLDevice>,
+        config: &OpenClConfig,
+        audio_settings: &AudioSettings,
+        reflections: &ReflectionConfig,
+    ) -> Result<Self, String> {
+        let opencl = match opencl {
+            Some(opencl) => opencl.clone(),
+            None => config.device(context)?,
+        };
+        let device = TrueAudioNextDevice::new(
+            &opencl,
+            &TrueAudioNextDeviceSettings {
+                frame_size: audio_settings.frame_size(),
+                ir_size: reflections.ir_size(audio_settings),
+                order: reflections.order,
+                max_sources: config.max_sources,
+            },
+        )
+        .map_err(|err| format!("could not create trueaudio next device: {err:?}"))?;
+
+        Ok(Self {
+            opencl,
+            device,
+            slots: (0..config.max_sources)
+                .map(|_| AtomicBool::new(false))
+                .collect(),
+        })
+    }
+
+    /// Makes the simulator produce impulse responses for the device.
+    pub(crate) fn apply(&self, settings: &mut SimulationSettings) {
+        settings.reflection_type = ReflectionEffectType::TrueAudioNext;
+        settings.opencl_device = Some(self.opencl.clone());
+        settings.tan_device = Some(self.device.clone());
+    }
+}
+
+/// A slot of the [`TrueAudioNext`] device, freed when dropped.
+pub(crate) struct TanSlot {
+    tan: Arc<TrueAudioNext>,
+    pub(crate) index: u32,
+}
+
+impl TanSlot {
+    /// A free slot, `None` once every slot is in use.
+    pub(crate) fn take(tan: Arc<TrueAudioNext>) -> Option<Self> {
+        let index = tan.slots.iter().position(|slot| {
+            slot.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        })?;
+        Some(Self {
+            tan,
+            index: index as u32,
+        })
+    }
+
+    pub(crate) fn device(&self) -> &TrueAudioNextDevice {
+        &self.tan.device
+    }
+}
+
+impl Drop for TanSlot {
+    fn drop(&mut self) {
+        self.tan.slots[self.index as usize].store(false, Ordering::Release);
+    }
+}
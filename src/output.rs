@@ -0,0 +1,70 @@
+use bevy::prelude::{DetectChanges, Res, Resource};
+use steam_audio::prelude::SpeakerLayout;
+
+use crate::{params::Snapshot, source::SpatialAudioSettings};
+
+/// How voices are rendered to the output device.
+///
+/// Changing it at runtime rebuilds the effects of playing voices at their next block.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    /// Through the HRTF, for headphones.
+    #[default]
+    Binaural,
+    /// Panned between the speakers of the layout, for desktop speakers and surround setups.
+    Panning(SpeakerLayout),
+}
+
+impl OutputMode {
+    /// Channels voices output in this mode.
+    pub fn channels(&self) -> u16 {
+        match self {
+            Self::Binaural => 2,
+            Self::Panning(SpeakerLayout::Mono) => 1,
+            Self::Panning(SpeakerLayout::Stereo) => 2,
+            Self::Panning(SpeakerLayout::Quadraphonic) => 4,
+            Self::Panning(SpeakerLayout::Surround5_1) => 6,
+            Self::Panning(SpeakerLayout::Surround7_1) => 8,
+        }
+    }
+
+    /// The layout the Ambisonics bed is decoded to.
+    pub(crate) fn speaker_layout(&self) -> SpeakerLayout {
+        match self {
+            Self::Binaural => SpeakerLayout::Stereo,
+            Self::Panning(layout) => *layout,
+        }
+    }
+}
+
+impl Snapshot for OutputMode {
+    const WORDS: usize = 1;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words[0] = match self {
+            Self::Binaural => 0.0,
+            Self::Panning(SpeakerLayout::Mono) => 1.0,
+            Self::Panning(SpeakerLayout::Stereo) => 2.0,
+            Self::Panning(SpeakerLayout::Quadraphonic) => 3.0,
+            Self::Panning(SpeakerLayout::Surround5_1) => 4.0,
+            Self::Panning(SpeakerLayout::Surround7_1) => 5.0,
+        };
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        match words[0] as u32 {
+            1 => Self::Panning(SpeakerLayout::Mono),
+            2 => Self::Panning(SpeakerLayout::Stereo),
+            3 => Self::Panning(SpeakerLayout::Quadraphonic),
+            4 => Self::Panning(SpeakerLayout::Surround5_1),
+            5 => Self::Panning(SpeakerLayout::Surround7_1),
+            _ => Self::Binaural,
+        }
+    }
+}
+
+pub fn update_output_mode(mode: Res<OutputMode>, settings: Res<SpatialAudioSettings>) {
+    if mode.is_changed() {
+        settings.output_mode.store(*mode);
+    }
+}
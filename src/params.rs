@@ -0,0 +1,365 @@
+use bevy::{math::Vec3, transform::components::GlobalTransform};
+use std::sync::{
+    atomic::{fence, AtomicU32, Ordering},
+    Arc,
+};
+
+use crate::source::SourceOrientation;
+
+/// The per-block inputs of the spatial pipeline, written by the game and read by the decoder.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SourceParams {
+    /// Direction from the listener to the source, in listener space.
+    pub direction: Vec3,
+    pub source_position: Vec3,
+    pub listener_position: Vec3,
+    pub orientation: SourceOrientation,
+}
+
+impl SourceParams {
+    /// The parameters of a source at `source` heard by a listener at `listener`.
+    pub fn new(source: &GlobalTransform, listener: &GlobalTransform) -> Self {
+        Self::heard_by(source, &SourceOrientation::from(listener))
+    }
+
+    /// The parameters of a source at `source` heard by a listener with `listener`'s orientation,
+    /// like the crossfaded one of [`ActiveListener`](crate::source::ActiveListener).
+    pub fn heard_by(source: &GlobalTransform, listener: &SourceOrientation) -> Self {
+        Self {
+            direction: listener.local_direction(source.translation()),
+            source_position: source.translation(),
+            listener_position: listener.origin,
+            orientation: SourceOrientation::from(source),
+        }
+    }
+}
+
+/// Words a [`Snapshot`] may take up, so [`SharedParams`] can copy them on the stack.
+pub const MAX_WORDS: usize = 32;
+
+/// A value that can be stored in [`SharedParams`] as a flat list of floats.
+pub trait Snapshot: Copy + Default {
+    /// Length of the slices handed to `write_words` and `read_words`, at most [`MAX_WORDS`].
+    const WORDS: usize;
+
+    fn write_words(&self, words: &mut [f32]);
+
+    fn read_words(words: &[f32]) -> Self;
+}
+
+fn write_vec3s(words: &mut [f32], vectors: impl IntoIterator<Item = Vec3>) {
+    for (words, vector) in words.chunks_exact_mut(3).zip(vectors) {
+        words.copy_from_slice(&vector.to_array());
+    }
+}
+
+/// The `index`th vector of `words`.
+fn read_vec3(words: &[f32], index: usize) -> Vec3 {
+    Vec3::from_slice(&words[index * 3..])
+}
+
+impl Snapshot for f32 {
+    const WORDS: usize = 1;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words[0] = *self;
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        words[0]
+    }
+}
+
+impl Snapshot for [f32; 3] {
+    const WORDS: usize = 3;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words.copy_from_slice(self);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        [words[0], words[1], words[2]]
+    }
+}
+
+/// A leading flag word, `0.0` for `None`.
+impl<T: Snapshot> Snapshot for Option<T> {
+    const WORDS: usize = 1 + T::WORDS;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words[0] = if self.is_some() { 1.0 } else { 0.0 };
+        self.unwrap_or_default().write_words(&mut words[1..]);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        (words[0] != 0.0).then(|| T::read_words(&words[1..]))
+    }
+}
+
+impl Snapshot for SourceOrientation {
+    const WORDS: usize = 12;
+
+    fn write_words(&self, words: &mut [f32]) {
+        write_vec3s(words, [self.origin, self.right, self.up, self.ahead]);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        Self {
+            origin: read_vec3(words, 0),
+            right: read_vec3(words, 1),
+            up: read_vec3(words, 2),
+            ahead: read_vec3(words, 3),
+        }
+    }
+}
+
+impl Snapshot for SourceParams {
+    const WORDS: usize = 9 + SourceOrientation::WORDS;
+
+    fn write_words(&self, words: &mut [f32]) {
+        let (vectors, orientation) = words.split_at_mut(9);
+        write_vec3s(
+            vectors,
+            [self.direction, self.source_position, self.listener_position],
+        );
+        self.orientation.write_words(orientation);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        Self {
+            direction: read_vec3(words, 0),
+            source_position: read_vec3(words, 1),
+            listener_position: read_vec3(words, 2),
+            orientation: SourceOrientation::read_words(&words[9..]),
+        }
+    }
+}
+
+/// A seqlock around a [`Snapshot`], so the audio thread can read it without ever waiting on the
+/// game thread.
+///
+/// Writers bump the sequence to odd while they write, readers retry when the sequence was odd or
+/// changed underneath them.
+///
+/// Source inputs go through this instead of a triple buffer promoted by an exchange system,
+/// which wouldn't block any less and would add a frame of latency.
+pub struct SharedParams<T> {
+    inner: Arc<SeqLock>,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+struct SeqLock {
+    sequence: AtomicU32,
+    words: Box<[AtomicU32]>,
+}
+
+impl<T> Clone for SharedParams<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: Snapshot> Default for SharedParams<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Snapshot> SharedParams<T> {
+    /// Reads are retried this many times before the reader gives up on a snapshot.
+    const RETRIES: usize = 16;
+
+    pub fn new(value: T) -> Self {
+        assert!(
+            T::WORDS <= MAX_WORDS,
+            "snapshots can't be larger than {MAX_WORDS} words"
+        );
+
+        let mut words = [0.0; MAX_WORDS];
+        value.write_words(&mut words[..T::WORDS]);
+        let words = words[..T::WORDS]
+            .iter()
+            .map(|word| AtomicU32::new(word.to_bits()))
+            .collect();
+
+        Self {
+            inner: Arc::new(SeqLock {
+                sequence: AtomicU32::new(0),
+                words,
+            }),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn store(&self, value: T) {
+        let lock = &self.inner;
+        let mut words = [0.0; MAX_WORDS];
+        value.write_words(&mut words[..T::WORDS]);
+
+        // Claim the write by moving the sequence from even to odd.
+        let mut sequence = lock.sequence.load(Ordering::Relaxed);
+        loop {
+            if sequence % 2 == 1 {
+                std::hint::spin_loop();
+                sequence = lock.sequence.load(Ordering::Relaxed);
+                continue;
+            }
+
+            match lock.sequence.compare_exchange_weak(
+                sequence,
+                sequence.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => sequence = current,
+            }
+        }
+        fence(Ordering::Release);
+
+        for (word, value) in lock.words.iter().zip(words) {
+            word.store(value.to_bits(), Ordering::Relaxed);
+        }
+
+        lock.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    /// The latest complete snapshot, `None` if every attempt raced with a write.
+    pub fn try_load(&self) -> Option<T> {
+        let lock = &self.inner;
+
+        for _ in 0..Self::RETRIES {
+            let before = lock.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let mut words = [0.0; MAX_WORDS];
+            for (value, word) in words.iter_mut().zip(lock.words.iter()) {
+                *value = f32::from_bits(word.load(Ordering::Relaxed));
+            }
+
+            // Only decoded once it's known not to be torn.
+            fence(Ordering::Acquire);
+            if lock.sequence.load(Ordering::Relaxed) == before {
+                return Some(T::read_words(&words[..T::WORDS]));
+            }
+        }
+
+        None
+    }
+
+    /// Like [`Self::try_load`], but keeps retrying. Meant for the game thread, the audio thread
+    /// should fall back to its last snapshot instead.
+    pub fn load(&self) -> T {
+        loop {
+            if let Some(value) = self.try_load() {
+                return value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every word set to `value`, so a torn read shows up as a mix of values.
+    fn uniform(value: f32) -> SourceParams {
+        let vector = Vec3::splat(value);
+        SourceParams {
+            direction: vector,
+            source_position: vector,
+            listener_position: vector,
+            orientation: SourceOrientation {
+                origin: vector,
+                right: vector,
+                up: vector,
+                ahead: vector,
+            },
+        }
+    }
+
+    fn assert_untorn(params: &SourceParams) {
+        let mut words = [0.0; SourceParams::WORDS];
+        params.write_words(&mut words);
+        assert!(
+            words.iter().all(|word| *word == words[0]),
+            "torn read: {words:?}"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let params = SourceParams {
+            direction: Vec3::X,
+            source_position: Vec3::new(1.0, 2.0, 3.0),
+            listener_position: Vec3::new(-4.0, 5.0, -6.0),
+            orientation: SourceOrientation {
+                origin: Vec3::ONE,
+                ..Default::default()
+            },
+        };
+        assert_eq!(SharedParams::new(params).load(), params);
+
+        let shared = SharedParams::<Option<[f32; 3]>>::default();
+        assert_eq!(shared.load(), None);
+        shared.store(Some([0.25, 0.5, 1.0]));
+        assert_eq!(shared.load(), Some([0.25, 0.5, 1.0]));
+    }
+
+    #[test]
+    fn sixty_four_writers_never_tear_or_block_reads() {
+        use std::sync::atomic::AtomicUsize;
+
+        const SOURCES: usize = 64;
+        const WRITES: u32 = 2_000;
+
+        let sources: Arc<Vec<SharedParams<SourceParams>>> = Arc::new(
+            (0..SOURCES)
+                .map(|_| SharedParams::new(uniform(0.0)))
+                .collect(),
+        );
+        let running = Arc::new(AtomicUsize::new(SOURCES));
+
+        // One game-side writer per source, all of them racing the audio thread at once.
+        let writers: Vec<_> = (0..SOURCES)
+            .map(|index| {
+                let (sources, running) = (sources.clone(), running.clone());
+                std::thread::spawn(move || {
+                    for value in 1..=WRITES {
+                        sources[index].store(uniform(value as f32));
+                    }
+                    running.fetch_sub(1, Ordering::Release);
+                })
+            })
+            .collect();
+
+        // The audio thread reads every source once per block and keeps its last snapshot when
+        // a read races with a write, like the decoders do.
+        let mut current = vec![uniform(0.0); SOURCES];
+        while running.load(Ordering::Acquire) > 0 {
+            for (source, current) in sources.iter().zip(&mut current) {
+                if let Some(params) = source.try_load() {
+                    assert_untorn(&params);
+                    // Snapshots never go back in time either.
+                    assert!(params.direction.x >= current.direction.x);
+                    *current = params;
+                }
+            }
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        // Nothing is left waiting once the writes stop.
+        for source in sources.iter() {
+            assert_eq!(source.try_load(), Some(uniform(WRITES as f32)));
+        }
+    }
+}
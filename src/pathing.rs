@@ -0,0 +1,147 @@
+use bevy::{
+    log::warn,
+    prelude::{Component, Reflect, ReflectComponent, ReflectResource, Resource},
+};
+use steam_audio::{
+    hrtf::{AudioSettings, HRTF},
+    prelude::{
+        BakedDataIdentifier, Context, DeinterleavedFrame, PathBakeSettings, PathEffect,
+        PathEffectParams, PathEffectSettings, SimulationFlags, SimulationSettings, SpeakerLayout,
+    },
+};
+
+use crate::source::SourceOrientation;
+
+pub(crate) const PATHING_IDENTIFIER: BakedDataIdentifier = BakedDataIdentifier::Pathing;
+/// Fraction of rays that must reach a probe for it to count as visible.
+pub(crate) const PATHING_VISIBILITY_THRESHOLD: f32 = 0.1;
+
+/// Routes sound around geometry, through open [`AudioPortal`](crate::portal::AudioPortal)s and
+/// other gaps, using the pathing data baked into [`ProbeVolume`](crate::probe::ProbeVolume)s.
+///
+/// Only [`PathingSource`]s inside a baked volume are pathed, volumes baked while this was
+/// disabled have no pathing data. The defaults suit rooms joined by doors of the default
+/// [`AudioPortal`](crate::portal::AudioPortal) size.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct PathingConfig {
+    pub enabled: bool,
+    /// Points sampled around each probe when testing visibility between probes.
+    pub samples: u32,
+    /// Probes further apart than this are never considered visible to each other.
+    pub visibility_range: f32,
+    /// Radius of the sphere around the source and listener tested for visibility to probes.
+    ///
+    /// Keep it under half the width of the narrowest portal, or the door frame hides the probes
+    /// on the other side.
+    pub visibility_radius: f32,
+    /// Paths longer than this are dropped.
+    pub path_range: f32,
+    /// Ambisonics order the paths are rendered with.
+    pub order: u32,
+}
+
+impl Default for PathingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: 16,
+            visibility_range: 50.0,
+            visibility_radius: 0.5,
+            path_range: 100.0,
+            order: 1,
+        }
+    }
+}
+
+impl PathingConfig {
+    pub(crate) fn apply(&self, settings: &mut SimulationSettings) {
+        if !self.enabled {
+            return;
+        }
+
+        settings.flags |= SimulationFlags::PATHING;
+        settings.max_order = settings.max_order.max(self.order);
+    }
+
+    pub(crate) fn bake_settings(&self) -> PathBakeSettings {
+        PathBakeSettings {
+            identifier: PATHING_IDENTIFIER,
+            num_samples: self.samples,
+            visibility_radius: self.visibility_radius,
+            visibility_range: self.visibility_range,
+            path_range: self.path_range,
+            ..Default::default()
+        }
+    }
+}
+
+/// Marks a source whose sound is routed around geometry when [`PathingConfig`] is enabled.
+///
+/// Finding paths costs a search through the probe graph for every source, so it's opt-in.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct PathingSource;
+
+/// Renders the paths found by the simulator for a voice to stereo.
+pub(crate) struct PathPipeline {
+    config: PathingConfig,
+    audio_settings: AudioSettings,
+    effect: PathEffect,
+}
+
+impl PathPipeline {
+    pub(crate) fn new(
+        context: &Context,
+        audio_settings: &AudioSettings,
+        hrtf: &HRTF,
+        config: PathingConfig,
+    ) -> Self {
+        let effect = PathEffect::new(context, audio_settings, &Self::settings(hrtf, &config))
+            .expect("could not build steam audio path effect");
+
+        Self {
+            config,
+            audio_settings: audio_settings.clone(),
+            effect,
+        }
+    }
+
+    fn settings<'a>(hrtf: &'a HRTF, config: &PathingConfig) -> PathEffectSettings<'a> {
+        PathEffectSettings {
+            max_order: config.order,
+            spatialize: true,
+            speaker_layout: SpeakerLayout::Stereo,
+            hrtf: Some(hrtf),
+        }
+    }
+
+    /// Rebuilds the effect after the HRTF was swapped.
+    pub(crate) fn set_hrtf(&mut self, context: &Context, hrtf: &HRTF) {
+        match PathEffect::new(
+            context,
+            &self.audio_settings,
+            &Self::settings(hrtf, &self.config),
+        ) {
+            Ok(effect) => self.effect = effect,
+            Err(err) => warn!("Could not rebuild steam audio path effect: {err:?}"),
+        }
+    }
+
+    /// Renders the paths of the mono `input` into the stereo `output`.
+    pub(crate) fn apply(
+        &mut self,
+        params: &PathEffectParams,
+        input: &mut DeinterleavedFrame,
+        listener: SourceOrientation,
+        output: &mut DeinterleavedFrame,
+    ) {
+        let params = PathEffectParams {
+            order: self.config.order,
+            binaural: true,
+            listener: listener.into(),
+            ..params.clone()
+        };
+        self.effect.apply_to_buffer(&params, input, output).unwrap();
+    }
+}
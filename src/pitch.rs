@@ -0,0 +1,45 @@
+use bevy::prelude::{Added, Changed, Component, Or, Query, Reflect, ReflectComponent};
+
+use crate::playback::SpatialAudioSource;
+
+/// Pitch ratio of a source, `2.0` plays an octave up at twice the speed and `0.5` an octave
+/// down at half the speed.
+///
+/// Combined with the Doppler shift and applied by resampling the source before the effects.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PitchShift(pub f32);
+
+impl Default for PitchShift {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Randomly offsets the [`PitchShift`] by up to `±variance` every block, for natural sounding
+/// foliage, crowds and the like.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PitchVariance(pub f32);
+
+pub fn update_pitch(
+    query: Query<
+        (
+            &SpatialAudioSource,
+            Option<&PitchShift>,
+            Option<&PitchVariance>,
+        ),
+        Or<(
+            Changed<PitchShift>,
+            Changed<PitchVariance>,
+            Added<SpatialAudioSource>,
+        )>,
+    >,
+) {
+    for (source, pitch, variance) in query.iter() {
+        let pitch = pitch.copied().unwrap_or_default().0;
+        let variance = variance.copied().unwrap_or_default().0;
+        source.voice.pitch.store(pitch.max(0.0));
+        source.voice.pitch_variance.store(variance.abs());
+    }
+}
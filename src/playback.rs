@@ -0,0 +1,813 @@
+use bevy::{
+    asset::{AssetEvent, AssetId, Assets, Handle},
+    audio::{AudioPlayer, AudioSink, GlobalVolume, PlaybackMode, PlaybackSettings},
+    ecs::system::EntityCommands,
+    hierarchy::DespawnRecursiveExt,
+    log::warn,
+    math::Vec3,
+    prelude::{
+        Added, Bundle, Changed, Commands, Component, Entity, Event, EventReader, EventWriter,
+        GlobalTransform, Has, Or, Query, Reflect, ReflectComponent, ReflectResource,
+        RemovedComponents, Res, ResMut, Resource, Transform, With, Without, World,
+    },
+    utils::{Duration, HashSet},
+};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use steam_audio::{
+    hrtf::AudioSettings,
+    prelude::{PathEffectParams, ReflectionEffectParams},
+};
+
+use crate::{
+    ambisonics::{AmbisonicsBus, AmbisonicsHrtf, AmbisonicsOrder},
+    binaural::BinauralConfig,
+    chain::EffectChain,
+    convolution::Convolver,
+    diagnostics::AudioStats,
+    mix::{SourceMix, SteamAudioMixer},
+    opencl::TrueAudioNext,
+    output::OutputMode,
+    params::{SharedParams, SourceParams},
+    pathing::{PathingConfig, PathingSource},
+    pool::{DecoderPool, PlayOneShotAudio, PooledAudio},
+    reflections::{ReflectionConfig, ReflectionTarget},
+    samples::SpatialAudioErrorKind,
+    settings::SharedHrtf,
+    simulation::DirectOutputs,
+    source::{
+        ActiveListener, PrimaryListener, SourceOrientation, SpatialAudioSettings, SteamAudio,
+    },
+};
+
+/// State shared between a playing [`SteamDecoder`](crate::source::SteamDecoder) on the
+/// audio thread and the [`SpatialAudioSource`] of the entity that spawned it.
+pub struct VoiceState {
+    /// Set by the decoder once it has processed its first block.
+    pub(crate) started: AtomicBool,
+    /// Set by the decoder once its source is exhausted.
+    pub(crate) finished: AtomicBool,
+    /// Set by the decoder when its source couldn't be read, taken by [`playback_events`].
+    pub(crate) error: Mutex<Option<SpatialAudioErrorKind>>,
+    /// Silence played in place of a source that couldn't be read, see [`AudioErrorPolicy`].
+    pub(crate) error_silence: Duration,
+    /// Requested seek position in nanoseconds, [`NO_SEEK`] when there is none.
+    pub(crate) seek: AtomicU64,
+    /// Set by the decoder once a requested seek has been performed.
+    pub(crate) seeked: AtomicBool,
+    /// Position of the decoder in its source in nanoseconds, updated every block.
+    pub(crate) position: AtomicU64,
+    /// Frames of the source played, updated alongside `position`.
+    pub(crate) frames: AtomicU64,
+    pub(crate) paused: AtomicBool,
+    /// Set by [`SpatialPlaybackControl::stop`], the decoder ends at its next block.
+    pub(crate) stopped: AtomicBool,
+    pub(crate) pause_fade_frames: AtomicU32,
+    pub(crate) spatial_blend: AtomicF32,
+    /// The [`NearFieldCorrection`](crate::binaural::NearFieldCorrection) threshold in meters,
+    /// `0.0` when the source has none.
+    pub(crate) near_field_threshold: AtomicF32,
+    /// Positions of the voice's own entity and the listener, see [`update_source_params`].
+    pub(crate) params: SharedParams<SourceParams>,
+    pub(crate) mix: SharedParams<SourceMix>,
+    /// The shared [`SteamAudioMixer`](crate::mix::SteamAudioMixer) levels.
+    pub(crate) mixer: SharedParams<SteamAudioMixer>,
+    pub(crate) doppler_pitch: AtomicF32,
+    pub(crate) pitch: AtomicF32,
+    pub(crate) pitch_variance: AtomicF32,
+    pub(crate) volume: AtomicF32,
+    /// Length of the fade in, in nanoseconds.
+    pub(crate) fade_in: AtomicU64,
+    /// Transmission through the obstacle between source and listener, `None` when unoccluded.
+    pub(crate) transmission: SharedParams<Option<[f32; 3]>>,
+    /// Frame size and sampling rate of the voice's effects, those of
+    /// [`SpatialAudioSettings`] when it started.
+    pub(crate) audio_settings: AudioSettings,
+    /// HRTF settings the decoder follows, swapped in at the next block when they change.
+    pub(crate) hrtf: Arc<SharedHrtf>,
+    pub(crate) binaural: SharedParams<BinauralConfig>,
+    /// Orientation of the primary listener, used to decode Ambisonics.
+    pub(crate) listener_orientation: SharedParams<SourceOrientation>,
+    /// Wet mix of the listener's [`AudioArea`](crate::area::AudioArea) reverb, scales the
+    /// reflections.
+    pub(crate) reverb_wet: SharedParams<f32>,
+    /// See [`OutputMode`], switched to at the next block.
+    pub(crate) output_mode: SharedParams<OutputMode>,
+    /// The simulator's direct outputs, written every tick by
+    /// [`simulate_direct`](crate::simulation::simulate_direct).
+    pub(crate) direct: SharedParams<DirectOutputs>,
+    /// The [`SimulationTickRate`](crate::simulation::SimulationTickRate), the decoder eases to
+    /// new direct outputs over one tick.
+    pub(crate) simulation_tick_rate: SharedParams<f32>,
+    /// Band gains of the listener's [`HeadphoneEq`](crate::eq::HeadphoneEq).
+    pub(crate) headphone_eq: SharedParams<[f32; 3]>,
+    pub(crate) stats: Arc<AudioStats>,
+    /// Set when the source is rendered through Ambisonics, along with whether the decode is
+    /// binaural.
+    pub(crate) ambisonics: Option<(AmbisonicsOrder, bool)>,
+    /// Gain of the fade out towards [`MaxAudibleDistance`](crate::culling::MaxAudibleDistance).
+    pub(crate) distance_gain: AtomicF32,
+    /// Cleared once the source is past its max audible distance.
+    pub(crate) audible: AtomicBool,
+    /// Replaces the direct and binaural rendering when set.
+    pub(crate) effect_chain: Option<EffectChain>,
+    /// The reflection settings when reflections were enabled as the voice started.
+    pub(crate) reflection_config: Option<ReflectionConfig>,
+    /// The latest reflection simulation results, taken by the decoder.
+    pub(crate) reflections: Mutex<Option<ReflectionEffectParams>>,
+    /// The bed reflections are mixed into, set alongside `reflection_config`.
+    pub(crate) ambisonics_bus: Option<Arc<AmbisonicsBus>>,
+    /// The device reflections are convolved on when TrueAudio Next is active.
+    pub(crate) true_audio_next: Option<Arc<TrueAudioNext>>,
+    /// The pathing settings of a [`PathingSource`] when pathing was enabled as the voice started.
+    pub(crate) pathing_config: Option<PathingConfig>,
+    /// The latest pathing simulation results, taken by the decoder.
+    pub(crate) pathing: Mutex<Option<PathEffectParams>>,
+    /// A new convolver for the [`ConvolutionReverbSend`](crate::convolution::ConvolutionReverbSend),
+    /// taken by the decoder.
+    pub(crate) convolution: Mutex<Option<Box<Convolver>>>,
+    pub(crate) convolution_wet: AtomicF32,
+    /// Set while the voice is outputting silence instead of running the effects.
+    pub(crate) virtualized: AtomicBool,
+    /// See [`WarmupBlocks`].
+    pub(crate) warmup_blocks: u32,
+    /// See [`TailBlocks`], `0` for looping players.
+    pub(crate) tail_blocks: u32,
+    /// Where the decoder takes its effects from and returns them to, set for [`PooledAudio`].
+    pub(crate) pool: Option<DecoderPool>,
+    /// The [`BusVolumes`](crate::volume::BusVolumes) gain, along with changes of the
+    /// `GlobalVolume` since the voice started.
+    pub(crate) bus_volume: AtomicF32,
+    /// Bevy's `GlobalVolume` as the voice started, which bevy already applies to its sink.
+    pub(crate) start_global_volume: f32,
+}
+
+const NO_SEEK: u64 = u64::MAX;
+
+impl Default for VoiceState {
+    fn default() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            error: Mutex::new(None),
+            error_silence: Duration::ZERO,
+            seek: AtomicU64::new(NO_SEEK),
+            seeked: AtomicBool::new(false),
+            position: AtomicU64::new(0),
+            frames: AtomicU64::new(0),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            pause_fade_frames: AtomicU32::new(0),
+            spatial_blend: AtomicF32::new(1.0),
+            near_field_threshold: AtomicF32::new(0.0),
+            params: SharedParams::default(),
+            mix: SharedParams::default(),
+            mixer: SharedParams::default(),
+            doppler_pitch: AtomicF32::new(1.0),
+            pitch: AtomicF32::new(1.0),
+            pitch_variance: AtomicF32::new(0.0),
+            volume: AtomicF32::new(1.0),
+            fade_in: AtomicU64::new(0),
+            transmission: SharedParams::default(),
+            audio_settings: AudioSettings::default(),
+            hrtf: Arc::default(),
+            binaural: SharedParams::default(),
+            listener_orientation: SharedParams::default(),
+            reverb_wet: SharedParams::new(1.0),
+            output_mode: SharedParams::default(),
+            direct: SharedParams::default(),
+            simulation_tick_rate: SharedParams::new(60.0),
+            headphone_eq: SharedParams::new([1.0; 3]),
+            stats: Arc::default(),
+            ambisonics: None,
+            effect_chain: None,
+            distance_gain: AtomicF32::new(1.0),
+            audible: AtomicBool::new(true),
+            reflection_config: None,
+            reflections: Mutex::new(None),
+            ambisonics_bus: None,
+            true_audio_next: None,
+            pathing_config: None,
+            pathing: Mutex::new(None),
+            convolution: Mutex::new(None),
+            convolution_wet: AtomicF32::new(0.0),
+            virtualized: AtomicBool::new(false),
+            warmup_blocks: 0,
+            tail_blocks: 0,
+            pool: None,
+            bus_volume: AtomicF32::new(1.0),
+            start_global_volume: 1.0,
+        }
+    }
+}
+
+impl VoiceState {
+    /// Paused voices are skipped by the simulation.
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Gain of the direct path as of the last simulation, see
+    /// [`VirtualVoiceThreshold`](crate::virtual_voice::VirtualVoiceThreshold).
+    pub(crate) fn gain(&self) -> f32 {
+        let direct = self.direct.load();
+        // Only the spatialized part of the voice is attenuated.
+        let attenuation = 1.0
+            + (direct.distance_attenuation * direct.directivity - 1.0) * self.spatial_blend.load();
+        self.volume.load() * self.distance_gain.load() * attenuation
+    }
+
+    /// Where the reflections are rendered, `None` unless reflections were enabled as the voice
+    /// started.
+    pub(crate) fn reflection_target(&self) -> Option<ReflectionTarget> {
+        let (config, bus) = self.reflection_config.zip(self.ambisonics_bus.clone())?;
+        Some(ReflectionTarget {
+            config,
+            bus,
+            true_audio_next: self.true_audio_next.clone(),
+        })
+    }
+
+    pub(crate) fn request_seek(&self, position: Duration) {
+        let nanos = (position.as_nanos() as u64).min(NO_SEEK - 1);
+        self.seek.store(nanos, Ordering::Release);
+    }
+
+    pub(crate) fn take_seek(&self) -> Option<Duration> {
+        match self.seek.swap(NO_SEEK, Ordering::AcqRel) {
+            NO_SEEK => None,
+            nanos => {
+                self.seeked.store(true, Ordering::Release);
+                Some(Duration::from_nanos(nanos))
+            }
+        }
+    }
+}
+
+/// An `f32` stored as its bit pattern, for parameters read on the audio thread.
+#[derive(Default)]
+pub(crate) struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub(crate) fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    pub(crate) fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Added to every `AudioPlayer<SteamAudio>` entity once its voice has been queued.
+#[derive(Component, Clone)]
+pub struct SpatialAudioSource {
+    pub(crate) voice: Arc<VoiceState>,
+    /// The asset the entity was spawned with, its `AudioPlayer` now points at a copy carrying
+    /// the voice.
+    pub(crate) asset: Handle<SteamAudio>,
+}
+
+/// Controls a playing `AudioPlayer<SteamAudio>`, like bevy's `AudioSink`.
+///
+/// Added alongside [`SpatialAudioSource`], the decoder picks changes up at its next block.
+#[derive(Component, Clone)]
+pub struct SpatialPlaybackControl {
+    voice: Arc<VoiceState>,
+}
+
+impl SpatialPlaybackControl {
+    /// Outputs silence without advancing the source, see also [`PauseAudio`].
+    pub fn pause(&self) {
+        self.voice.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes from where [`Self::pause`] left off.
+    pub fn play(&self) {
+        self.voice.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn toggle(&self) {
+        self.voice.paused.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.voice.is_paused()
+    }
+
+    /// Ends playback for good, [`SpatialPlaybackFinished`] is sent once the decoder has stopped.
+    pub fn stop(&self) {
+        self.voice.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.voice.is_stopped()
+    }
+
+    /// Jumps to `position` at the next block, like [`SeekAudio`].
+    pub fn seek(&self, position: Duration) -> Result<(), SeekError> {
+        if self.voice.is_stopped() || self.voice.finished.load(Ordering::Acquire) {
+            return Err(SeekError::Ended);
+        }
+
+        self.voice.request_seek(position);
+        Ok(())
+    }
+
+    /// How far into its source the voice is, as of its last block.
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.voice.position.load(Ordering::Relaxed))
+    }
+
+    /// Overwrites the gain set by [`VolumeScale`](crate::volume::VolumeScale) until it changes.
+    pub fn set_volume(&self, volume: f32) {
+        self.voice.volume.store(volume.max(0.0));
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.voice.volume.load()
+    }
+}
+
+/// Sent when a [`SteamDecoder`](crate::source::SteamDecoder) has processed its first block.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpatialPlaybackStarted {
+    /// The entity holding the `AudioPlayer<SteamAudio>`.
+    pub entity: Entity,
+}
+
+/// Sent when a non-looping [`SteamDecoder`](crate::source::SteamDecoder) exhausts its source.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpatialPlaybackFinished {
+    /// The entity holding the `AudioPlayer<SteamAudio>`.
+    pub entity: Entity,
+}
+
+/// Sent when the audio of an `AudioPlayer<SteamAudio>` couldn't be opened or decoded, the voice
+/// plays [`AudioErrorPolicy::silence`] in its place.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SpatialAudioError {
+    /// The entity holding the `AudioPlayer<SteamAudio>`.
+    pub entity: Entity,
+    pub kind: SpatialAudioErrorKind,
+}
+
+/// What an `AudioPlayer<SteamAudio>` whose audio can't be read does, since bevy gives the
+/// decoder no way to fail.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct AudioErrorPolicy {
+    /// Silence played in place of the audio, the voice then finishes like any other.
+    pub silence: Duration,
+    /// Despawns the player once its [`SpatialAudioError`] is sent.
+    pub despawn: bool,
+}
+
+impl Default for AudioErrorPolicy {
+    fn default() -> Self {
+        Self {
+            silence: Duration::from_millis(100),
+            despawn: false,
+        }
+    }
+}
+
+/// The original name of [`SpatialPlaybackFinished`].
+pub type AudioFinished = SpatialPlaybackFinished;
+
+/// Seeks a playing `AudioPlayer<SteamAudio>` to the given position.
+///
+/// The decoder picks the request up at its next block and the component is removed once the
+/// seek has been performed.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct SeekAudio(pub Duration);
+
+/// How far into its source a playing `AudioPlayer<SteamAudio>` is, added with the voice and
+/// updated every frame.
+///
+/// The decoder advances it by the source frames each block reads, [`AudioSettings::frame_size`]
+/// of them unless the voice is pitched or resampled, so it's accurate to one block. What the speakers emit lags behind
+/// it by the block being played out plus the output device's buffer, typically a few tens of
+/// milliseconds.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct PlaybackPosition {
+    pub elapsed: Duration,
+    /// Frames at the sample rate of the source.
+    pub frames: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SeekError {
+    #[error("the voice has already finished or been stopped")]
+    Ended,
+}
+
+/// Suspends a playing `AudioPlayer<SteamAudio>` without losing its position.
+///
+/// The decoder outputs silence instead of advancing its source until the component is removed.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct PauseAudio;
+
+/// Fades a voice out over this many frames before [`PauseAudio`] silences it, and back in
+/// when it resumes.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct PauseFadeFrames(pub u32);
+
+/// Silent blocks a new voice runs through its effects before playing, so the first audible
+/// block doesn't start from empty convolution state.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct WarmupBlocks(pub u32);
+
+impl Default for WarmupBlocks {
+    fn default() -> Self {
+        Self(2)
+    }
+}
+
+/// Silent blocks at most a voice keeps running through its effects after its source ended, so
+/// the HRTF and reverb tails ring out instead of being cut off.
+///
+/// The voice ends early once a block of its tail is silent. Looping players have no tail.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct TailBlocks(pub u32);
+
+impl Default for TailBlocks {
+    fn default() -> Self {
+        Self(16)
+    }
+}
+
+/// Keeps the audio entity alive after [`SpatialPlaybackFinished`] is sent instead of despawning it.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct KeepOnFinish;
+
+/// A [`SteamAudio`] player at `transform`, the voice itself is set up once the asset is loaded.
+#[derive(Bundle, Clone)]
+pub struct SpatialAudioBundle {
+    pub player: AudioPlayer<SteamAudio>,
+    pub playback: PlaybackSettings,
+    pub transform: Transform,
+}
+
+/// A player of no sound at the origin, for struct update syntax with the `player` set.
+impl Default for SpatialAudioBundle {
+    fn default() -> Self {
+        Self::from_handle(Handle::default(), Transform::default())
+    }
+}
+
+impl SpatialAudioBundle {
+    /// Adds the sound at the Bevy asset path `path` to `assets` and plays it once at `transform`.
+    ///
+    /// The sound is streamed from the file as it plays instead of going through the
+    /// `AssetServer`, so there's no handle to it until it's added to `assets`. Sounds loaded with
+    /// the `AssetServer` play with [`Self::from_handle`] instead.
+    pub fn new(assets: &mut Assets<SteamAudio>, path: &str, transform: Transform) -> Self {
+        Self::from_handle(assets.add(SteamAudio::from_asset_path(path)), transform)
+    }
+
+    /// Plays an already added or loading sound once at `transform`.
+    pub fn from_handle(audio: Handle<SteamAudio>, transform: Transform) -> Self {
+        Self {
+            player: AudioPlayer(audio),
+            playback: PlaybackSettings::ONCE,
+            transform,
+        }
+    }
+}
+
+pub trait SpatialAudioCommands {
+    /// Spawns an entity at `position` that plays `audio` once and despawns when it finishes.
+    fn play_spatial(&mut self, audio: Handle<SteamAudio>, position: Vec3) -> EntityCommands<'_>;
+
+    /// Like [`Self::play_spatial`] with a decoder from the
+    /// [`AudioSourcePool`](crate::pool::AudioSourcePool), see [`PlayOneShotAudio`].
+    fn play_one_shot(&mut self, audio: Handle<SteamAudio>, position: Vec3);
+
+    /// Moves [`PrimaryListener`] to `listener`, crossfading from the current one.
+    fn set_primary_listener(&mut self, listener: Entity);
+}
+
+impl SpatialAudioCommands for Commands<'_, '_> {
+    fn play_spatial(&mut self, audio: Handle<SteamAudio>, position: Vec3) -> EntityCommands<'_> {
+        self.spawn(SpatialAudioBundle::from_handle(
+            audio,
+            Transform::from_translation(position),
+        ))
+    }
+
+    fn play_one_shot(&mut self, audio: Handle<SteamAudio>, position: Vec3) {
+        self.queue(PlayOneShotAudio { audio, position });
+    }
+
+    fn set_primary_listener(&mut self, listener: Entity) {
+        self.queue(move |world: &mut World| {
+            let primaries: Vec<Entity> = world
+                .query_filtered::<Entity, With<PrimaryListener>>()
+                .iter(world)
+                .collect();
+            for entity in primaries {
+                world.entity_mut(entity).remove::<PrimaryListener>();
+            }
+
+            match world.get_entity_mut(listener) {
+                Ok(mut entity) => {
+                    entity.insert(PrimaryListener);
+                }
+                Err(_) => {
+                    warn!("Could not make {listener:?} the PrimaryListener, it doesn't exist")
+                }
+            }
+        });
+    }
+}
+
+/// Gives every new `AudioPlayer<SteamAudio>` its own voice.
+///
+/// `Decodable::decoder` only sees the asset, so the player is pointed at a copy of its asset
+/// carrying the voice of that entity. Two entities playing the same handle never swap voices,
+/// whatever order bevy creates their decoders in.
+#[allow(clippy::too_many_arguments)]
+pub fn queue_voices(
+    mut commands: Commands,
+    global_volume: Res<GlobalVolume>,
+    mut assets: ResMut<Assets<SteamAudio>>,
+    settings: Res<SpatialAudioSettings>,
+    reflections: Res<ReflectionConfig>,
+    pathing: Res<PathingConfig>,
+    binaural: Res<BinauralConfig>,
+    audio_errors: Res<AudioErrorPolicy>,
+    warmup: Res<WarmupBlocks>,
+    tail: Res<TailBlocks>,
+    mut query: Query<
+        (
+            Entity,
+            &mut AudioPlayer<SteamAudio>,
+            Option<&PlaybackSettings>,
+            Option<&AmbisonicsOrder>,
+            Option<&AmbisonicsHrtf>,
+            Option<&BinauralConfig>,
+            Option<&EffectChain>,
+            Has<PooledAudio>,
+            Has<PathingSource>,
+        ),
+        Without<SpatialAudioSource>,
+    >,
+) {
+    for (
+        entity,
+        player,
+        playback,
+        ambisonics,
+        ambisonics_hrtf,
+        binaural_override,
+        chain,
+        pooled,
+        pathed,
+    ) in query.iter_mut()
+    {
+        // Bevy won't create the decoder until the asset is loaded either.
+        let Some(data) = assets.get(&player.0).map(|audio| audio.data.clone()) else {
+            continue;
+        };
+
+        let voice = Arc::new(VoiceState {
+            audio_settings: settings.audio_settings.clone(),
+            hrtf: settings.shared_hrtf.clone(),
+            warmup_blocks: warmup.0,
+            error_silence: audio_errors.silence,
+            start_global_volume: global_volume.volume.get(),
+            // A tail would leave a gap between repetitions.
+            tail_blocks: match playback.map(|playback| playback.mode) {
+                Some(PlaybackMode::Loop) => 0,
+                _ => tail.0,
+            },
+            // A per-source config wins over the global default.
+            binaural: SharedParams::new(binaural_override.copied().unwrap_or(*binaural)),
+            listener_orientation: settings.listener_orientation.clone(),
+            reverb_wet: settings.reverb_wet.clone(),
+            output_mode: settings.output_mode.clone(),
+            direct: SharedParams::default(),
+            simulation_tick_rate: settings.simulation_tick_rate.clone(),
+            headphone_eq: settings.headphone_eq.clone(),
+            mixer: settings.mixer.clone(),
+            stats: settings.stats.clone(),
+            reflection_config: reflections.enabled.then_some(*reflections),
+            ambisonics_bus: reflections.enabled.then(|| settings.ambisonics_bus.clone()),
+            true_audio_next: settings.true_audio_next.clone(),
+            pathing_config: (pathing.enabled && pathed).then_some(*pathing),
+            ambisonics: ambisonics.map(|order| {
+                let binaural = ambisonics_hrtf.copied().unwrap_or_default().0;
+                (*order, binaural)
+            }),
+            effect_chain: chain.cloned(),
+            pool: pooled.then(|| settings.decoder_pool.clone()),
+            ..Default::default()
+        });
+        let asset = std::mem::replace(
+            &mut player.0,
+            assets.add(SteamAudio {
+                data,
+                voice: Some(voice.clone()),
+            }),
+        );
+        commands.entity(entity).insert((
+            SpatialPlaybackControl {
+                voice: voice.clone(),
+            },
+            SpatialAudioSource { voice, asset },
+            PlaybackPosition::default(),
+        ));
+    }
+}
+
+/// Copies the position the decoder published into [`PlaybackPosition`], leaving it unchanged
+/// between blocks.
+pub fn update_playback_position(mut query: Query<(&SpatialAudioSource, &mut PlaybackPosition)>) {
+    for (source, mut position) in query.iter_mut() {
+        let current = PlaybackPosition {
+            elapsed: Duration::from_nanos(source.voice.position.load(Ordering::Relaxed)),
+            frames: source.voice.frames.load(Ordering::Relaxed),
+        };
+        if *position != current {
+            *position = current;
+        }
+    }
+}
+
+/// Hands every voice the position of its own entity relative to the [`PrimaryListener`], so one
+/// [`SteamAudio`] can play at many places at once.
+pub fn update_source_params(
+    listener: Res<ActiveListener>,
+    sources: Query<(&SpatialAudioSource, &GlobalTransform)>,
+) {
+    if listener.entity.is_none() {
+        return;
+    }
+
+    for (source, transform) in sources.iter() {
+        source
+            .voice
+            .params
+            .store(SourceParams::heard_by(transform, &listener.orientation));
+    }
+}
+
+/// Restarts voices whose [`SteamAudio`] was modified, like by a hot reload, so they play the new
+/// asset from its start.
+///
+/// The entity is kept, [`queue_voices`] picks it up again once the sink and source are removed.
+pub fn reload_voices(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<SteamAudio>>,
+    query: Query<(Entity, &SpatialAudioSource)>,
+) {
+    let modified: HashSet<AssetId<SteamAudio>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if modified.is_empty() {
+        return;
+    }
+
+    for (entity, source) in query.iter() {
+        if !modified.contains(&source.asset.id()) {
+            continue;
+        }
+
+        // The old decoder ends at its next block, nobody is left to report it finishing.
+        source.voice.stopped.store(true, Ordering::Relaxed);
+        commands
+            .entity(entity)
+            .remove::<(AudioSink, SpatialAudioSource, SpatialPlaybackControl)>()
+            .insert(AudioPlayer(source.asset.clone()));
+    }
+}
+
+pub fn playback_events(
+    mut commands: Commands,
+    mut started: EventWriter<SpatialPlaybackStarted>,
+    mut finished: EventWriter<SpatialPlaybackFinished>,
+    mut errors: EventWriter<SpatialAudioError>,
+    audio_errors: Res<AudioErrorPolicy>,
+    query: Query<(
+        Entity,
+        &SpatialAudioSource,
+        &PlaybackSettings,
+        Has<KeepOnFinish>,
+    )>,
+) {
+    for (entity, source, settings, keep) in query.iter() {
+        let error = source.voice.error.lock().unwrap().take();
+        if let Some(kind) = error {
+            errors.send(SpatialAudioError { entity, kind });
+            if audio_errors.despawn {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+
+        if source.voice.started.swap(false, Ordering::AcqRel) {
+            started.send(SpatialPlaybackStarted { entity });
+        }
+
+        let stopped = source.voice.is_stopped();
+
+        // Looping players replay a buffered copy after the decoder ends.
+        if matches!(settings.mode, PlaybackMode::Loop) && !stopped {
+            continue;
+        }
+
+        if !source.voice.finished.swap(false, Ordering::AcqRel) {
+            continue;
+        }
+
+        finished.send(SpatialPlaybackFinished { entity });
+
+        if stopped {
+            // Dropping the sink also ends the buffered copy of looping players.
+            if keep {
+                commands.entity(entity).remove::<AudioSink>();
+            } else {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        // `Despawn` and `Remove` are already handled by bevy.
+        if matches!(settings.mode, PlaybackMode::Once) && !keep {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn seek_voices(
+    mut commands: Commands,
+    requested: Query<
+        (&SpatialAudioSource, &SeekAudio),
+        Or<(Changed<SeekAudio>, Added<SpatialAudioSource>)>,
+    >,
+    pending: Query<(Entity, &SpatialAudioSource), With<SeekAudio>>,
+) {
+    for (entity, source) in pending.iter() {
+        if source.voice.seeked.swap(false, Ordering::AcqRel) {
+            commands.entity(entity).remove::<SeekAudio>();
+        }
+    }
+
+    for (source, seek) in requested.iter() {
+        source.voice.request_seek(seek.0);
+    }
+}
+
+/// Only writes when [`PauseAudio`] is added or removed, so it doesn't undo
+/// [`SpatialPlaybackControl`].
+pub fn pause_voices(
+    mut resumed: RemovedComponents<PauseAudio>,
+    paused: Query<
+        &SpatialAudioSource,
+        (
+            With<PauseAudio>,
+            Or<(Added<PauseAudio>, Added<SpatialAudioSource>)>,
+        ),
+    >,
+    sources: Query<&SpatialAudioSource>,
+    fades: Query<(&SpatialAudioSource, Option<&PauseFadeFrames>)>,
+) {
+    for (source, fade) in fades.iter() {
+        let fade = fade.map(|fade| fade.0).unwrap_or_default();
+        source
+            .voice
+            .pause_fade_frames
+            .store(fade, Ordering::Relaxed);
+    }
+
+    for entity in resumed.read() {
+        if let Ok(source) = sources.get(entity) {
+            source.voice.paused.store(false, Ordering::Relaxed);
+        }
+    }
+    for source in paused.iter() {
+        source.voice.paused.store(true, Ordering::Relaxed);
+    }
+}
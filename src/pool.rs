@@ -0,0 +1,225 @@
+use bevy::{
+    asset::Handle,
+    ecs::world::Command,
+    log::warn,
+    math::Vec3,
+    prelude::{
+        Component, Reflect, ReflectComponent, ReflectResource, Res, Resource, Transform, World,
+    },
+};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use steam_audio::hrtf::AudioSettings;
+
+use crate::{
+    playback::SpatialAudioBundle,
+    settings::SharedHrtf,
+    source::{DecoderSettings, SpatialAudioSettings, SteamAudio},
+};
+
+/// Decoders kept ready for [`PooledAudio`] voices, so frequent one-shot sounds like gunshots and
+/// footsteps don't build their context, HRTF and effects as they start.
+///
+/// Voices started while the pool is empty build their own with a warning.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct AudioSourcePool {
+    pub pool_size: u32,
+}
+
+impl Default for AudioSourcePool {
+    fn default() -> Self {
+        Self { pool_size: 8 }
+    }
+}
+
+/// Marks an `AudioPlayer<SteamAudio>` whose decoder comes from the [`AudioSourcePool`] and goes
+/// back to it once the voice ends.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct PooledAudio;
+
+/// Plays `audio` once at `position` with a [`PooledAudio`] voice, despawning when it finishes.
+#[derive(Debug, Clone)]
+pub struct PlayOneShotAudio {
+    pub audio: Handle<SteamAudio>,
+    pub position: Vec3,
+}
+
+impl Command for PlayOneShotAudio {
+    fn apply(self, world: &mut World) {
+        world.spawn((
+            SpatialAudioBundle::from_handle(self.audio, Transform::from_translation(self.position)),
+            PooledAudio,
+        ));
+    }
+}
+
+/// The idle decoder settings of the [`AudioSourcePool`], shared with every pooled voice.
+#[derive(Clone)]
+pub(crate) struct DecoderPool {
+    state: Arc<Mutex<PoolState>>,
+    /// Where the audio thread returns settings, sorted back into the pool on the game thread.
+    returned: Sender<DecoderSettings>,
+    /// Settings handed out and not returned yet.
+    lent: Arc<AtomicUsize>,
+}
+
+struct PoolState {
+    idle: Vec<DecoderSettings>,
+    size: usize,
+    /// Frame size and sampling rate the idle settings were built for.
+    built_for: Option<(u32, u32)>,
+    returned: Receiver<DecoderSettings>,
+}
+
+impl Default for DecoderPool {
+    fn default() -> Self {
+        let (returned, receiver) = mpsc::channel();
+        Self {
+            state: Arc::new(Mutex::new(PoolState {
+                idle: Vec::new(),
+                size: 0,
+                built_for: None,
+                returned: receiver,
+            })),
+            returned,
+            lent: Arc::default(),
+        }
+    }
+}
+
+fn audio_key(audio_settings: &AudioSettings) -> (u32, u32) {
+    (audio_settings.frame_size(), audio_settings.sampling_rate())
+}
+
+impl DecoderPool {
+    /// Idle settings for a new voice, or new ones when the pool is exhausted.
+    pub(crate) fn take_or_build(
+        &self,
+        audio_settings: &AudioSettings,
+        hrtf: &SharedHrtf,
+    ) -> PooledSettings {
+        let idle = {
+            let mut state = self.state.lock().unwrap();
+            let current = state.built_for == Some(audio_key(audio_settings));
+            current.then(|| state.idle.pop()).flatten()
+        };
+
+        match idle {
+            Some(mut settings) => {
+                settings.reset();
+                self.lent.fetch_add(1, Ordering::Relaxed);
+                PooledSettings {
+                    settings: Some(settings),
+                    pool: Some(self.clone()),
+                }
+            }
+            None => {
+                warn!(
+                    "AudioSourcePool exhausted, building a decoder, consider raising `pool_size`."
+                );
+                PooledSettings::new(DecoderSettings::new(audio_settings, hrtf))
+            }
+        }
+    }
+
+    /// Returns settings taken with [`Self::take_or_build`], called from the audio thread.
+    ///
+    /// Never waits on the game thread and never frees the IPL objects here, [`Self::fill`]
+    /// sorts them back in or drops them.
+    fn give_back(&self, settings: DecoderSettings) {
+        self.lent.fetch_sub(1, Ordering::Relaxed);
+        // Only fails once the pool is gone along with the app.
+        let _ = self.returned.send(settings);
+    }
+
+    /// Builds settings until `size` are either idle or lent, starting over when the audio
+    /// settings changed.
+    fn fill(&self, size: usize, audio_settings: &AudioSettings, hrtf: &SharedHrtf) {
+        let key = audio_key(audio_settings);
+        let missing = {
+            let mut state = self.state.lock().unwrap();
+            if state.built_for != Some(key) {
+                state.idle.clear();
+                state.built_for = Some(key);
+            }
+            // Settings built for other audio settings, or beyond the size, are dropped here.
+            while let Ok(settings) = state.returned.try_recv() {
+                if audio_key(&settings.audio_settings) == key {
+                    state.idle.push(settings);
+                }
+            }
+            state.size = size;
+            state.idle.truncate(size);
+            size.saturating_sub(state.idle.len() + self.lent.load(Ordering::Relaxed))
+        };
+        if missing == 0 {
+            return;
+        }
+
+        // Built without holding the lock, returning voices don't have to skip the pool.
+        let built: Vec<_> = (0..missing)
+            .map(|_| DecoderSettings::new(audio_settings, hrtf))
+            .collect();
+        let mut state = self.state.lock().unwrap();
+        let room = size.saturating_sub(state.idle.len());
+        state.idle.extend(built.into_iter().take(room));
+    }
+}
+
+/// The [`DecoderSettings`] of a decoder, given back to their [`DecoderPool`] when it is dropped.
+pub(crate) struct PooledSettings {
+    /// Only `None` while being dropped.
+    settings: Option<DecoderSettings>,
+    pool: Option<DecoderPool>,
+}
+
+impl PooledSettings {
+    /// Settings that aren't part of a pool.
+    pub(crate) fn new(settings: DecoderSettings) -> Self {
+        Self {
+            settings: Some(settings),
+            pool: None,
+        }
+    }
+}
+
+impl Deref for PooledSettings {
+    type Target = DecoderSettings;
+
+    fn deref(&self) -> &Self::Target {
+        self.settings.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledSettings {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.settings.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledSettings {
+    fn drop(&mut self) {
+        if let (Some(pool), Some(settings)) = (&self.pool, self.settings.take()) {
+            pool.give_back(settings);
+        }
+    }
+}
+
+/// Keeps [`AudioSourcePool::pool_size`] decoder settings ready, rebuilding them when the frame
+/// size or sampling rate changes.
+pub fn fill_decoder_pool(pool: Res<AudioSourcePool>, settings: Res<SpatialAudioSettings>) {
+    settings.decoder_pool.fill(
+        pool.pool_size as usize,
+        &settings.audio_settings,
+        &settings.shared_hrtf,
+    );
+}
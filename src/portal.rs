@@ -0,0 +1,104 @@
+use bevy::{
+    math::Vec3,
+    prelude::{
+        Added, Changed, Component, Entity, GlobalTransform, Or, Query, Reflect, ReflectComponent,
+        RemovedComponents, ResMut,
+    },
+};
+use steam_audio::prelude::Material;
+
+use crate::{geometry::SteamAudioScene, mesh::AudioMesh};
+
+/// A closed portal lets nothing through.
+const CLOSED: Material = Material {
+    absorption: [1.0; 3],
+    scattering: 0.0,
+    transmission: [0.0; 3],
+};
+
+/// A door or window, a `width` by `height` rectangle facing the entity's forward axis.
+///
+/// Closed, it's a wall with full transmission loss. Open, it leaves a gap the simulator routes
+/// sound through when [`PathingConfig`](crate::pathing::PathingConfig) is enabled.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AudioPortal {
+    pub open: bool,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for AudioPortal {
+    fn default() -> Self {
+        Self {
+            open: true,
+            width: 1.0,
+            height: 2.0,
+        }
+    }
+}
+
+impl AudioPortal {
+    /// The rectangle of the portal in world space.
+    pub fn audio_mesh(&self, transform: &GlobalTransform) -> AudioMesh {
+        let (x, y) = (self.width * 0.5, self.height * 0.5);
+        AudioMesh {
+            vertices: vec![
+                Vec3::new(-x, -y, 0.0),
+                Vec3::new(x, -y, 0.0),
+                Vec3::new(x, y, 0.0),
+                Vec3::new(-x, y, 0.0),
+            ],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        }
+        .transformed(transform)
+        .with_material(CLOSED)
+    }
+}
+
+/// Opens the [`AudioPortal`] of the entity while it's present, for doors driven by animation.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct DoorOpen;
+
+/// Opens portals when [`DoorOpen`] is added and closes them when it's removed.
+pub fn update_door_portals(
+    mut portals: Query<&mut AudioPortal>,
+    opened: Query<Entity, Added<DoorOpen>>,
+    mut closed: RemovedComponents<DoorOpen>,
+) {
+    for (entity, open) in opened
+        .iter()
+        .map(|entity| (entity, true))
+        .chain(closed.read().map(|entity| (entity, false)))
+    {
+        if let Ok(mut portal) = portals.get_mut(entity) {
+            if portal.open != open {
+                portal.open = open;
+            }
+        }
+    }
+}
+
+/// Adds closed portals to the scene as walls and takes open ones out again.
+pub fn update_portal_geometry(
+    mut scene: ResMut<SteamAudioScene>,
+    portals: Query<
+        (Entity, &AudioPortal, &GlobalTransform),
+        Or<(Changed<AudioPortal>, Changed<GlobalTransform>)>,
+    >,
+    mut removed: RemovedComponents<AudioPortal>,
+) {
+    for (entity, portal, transform) in portals.iter() {
+        if portal.open {
+            scene.remove(entity);
+        } else {
+            scene.insert(entity, &portal.audio_mesh(transform));
+        }
+    }
+
+    for entity in removed.read() {
+        scene.remove(entity);
+    }
+}
@@ -0,0 +1,497 @@
+use bevy::{
+    asset::{
+        io::{Reader, Writer},
+        saver::{AssetSaver, SavedAsset},
+        Asset, AssetLoader, Assets, AsyncWriteExt, Handle, LoadContext,
+    },
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    log::warn,
+    math::{Mat4, Vec3},
+    prelude::{
+        Commands, Component, Entity, Event, EventReader, EventWriter, GlobalTransform, Query,
+        Reflect, ReflectComponent, RemovedComponents, Res, ResMut, Resource,
+    },
+    reflect::TypePath,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+use std::{collections::VecDeque, sync::Arc};
+use steam_audio::prelude::{
+    BakedDataIdentifier, BakedDataVariation, BakedReflectionsSettings, ProbeArray, ProbeBatch,
+    ProbeGenerationParams, ProbeGenerationType, Simulator, Sphere,
+};
+
+use crate::{
+    area::AudioArea,
+    coords::{bevy_distance_to_phonon, bevy_position_to_phonon},
+    geometry::SteamAudioScene,
+    pathing::PathingConfig,
+    playback::AtomicF32,
+    reflections::{ReflectionConfig, ReflectionState},
+    settings::AudioUnitsPerMeter,
+    simulation::DirectSimulationState,
+    source::SpatialAudioSettings,
+};
+
+/// Height above the floor the probes are placed at, in meters.
+const PROBE_HEIGHT: f32 = 1.5;
+
+/// A box of probes whose reflections are baked offline against the
+/// [`SteamAudioScene`](crate::geometry::SteamAudioScene) instead of simulated in real time.
+///
+/// Probes are placed on the floor every `spacing` units within `half_extents` of the entity.
+/// Send [`BakeReflections`] to bake it, afterwards the entity gets a [`BakedProbeVolume`] and
+/// sources with [`BakedReflections`] inside the box use it.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct ProbeVolume {
+    pub half_extents: Vec3,
+    pub spacing: f32,
+}
+
+impl Default for ProbeVolume {
+    fn default() -> Self {
+        Self {
+            half_extents: Vec3::splat(10.0),
+            spacing: 2.0,
+        }
+    }
+}
+
+impl ProbeVolume {
+    /// Whether `position` lies inside the volume placed at `transform`.
+    pub fn contains(&self, transform: &GlobalTransform, position: Vec3) -> bool {
+        let local = transform.affine().inverse().transform_point3(position);
+        local.abs().cmple(self.half_extents).all()
+    }
+}
+
+/// Uses the baked reflections of the [`ProbeVolume`] the source is in instead of tracing rays.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct BakedReflections;
+
+/// What the reflections of a [`ProbeVolume`] are baked for.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BakeVariation {
+    /// Listener-centric reverb, shared by every source in the volume.
+    #[default]
+    Reverb,
+    /// Reflections from a source that doesn't move, to a listener anywhere in the volume.
+    StaticSource { position: Vec3, radius: f32 },
+    /// Reflections from a source anywhere in the volume, to a listener that doesn't move.
+    StaticListener { position: Vec3, radius: f32 },
+}
+
+impl BakeVariation {
+    pub(crate) fn identifier(&self, units: AudioUnitsPerMeter) -> BakedDataIdentifier {
+        let variation = match *self {
+            Self::Reverb => BakedDataVariation::Reverb,
+            Self::StaticSource { position, radius } => BakedDataVariation::StaticSource {
+                endpoint: Sphere {
+                    center: bevy_position_to_phonon(position, units),
+                    radius: bevy_distance_to_phonon(radius, units),
+                },
+            },
+            Self::StaticListener { position, radius } => BakedDataVariation::StaticListener {
+                endpoint: Sphere {
+                    center: bevy_position_to_phonon(position, units),
+                    radius: bevy_distance_to_phonon(radius, units),
+                },
+            },
+        };
+
+        BakedDataIdentifier::Reflections { variation }
+    }
+
+    /// A tag followed by the endpoint, little endian.
+    fn to_bytes(self) -> [u8; 17] {
+        let (tag, position, radius) = match self {
+            Self::Reverb => (0, Vec3::ZERO, 0.0),
+            Self::StaticSource { position, radius } => (1, position, radius),
+            Self::StaticListener { position, radius } => (2, position, radius),
+        };
+
+        let mut bytes = [0; 17];
+        bytes[0] = tag;
+        for (index, value) in position.to_array().into_iter().chain([radius]).enumerate() {
+            bytes[1 + index * 4..5 + index * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; 17]) -> Option<Self> {
+        let value = |index: usize| {
+            f32::from_le_bytes(bytes[1 + index * 4..5 + index * 4].try_into().unwrap())
+        };
+        let position = Vec3::new(value(0), value(1), value(2));
+        let radius = value(3);
+
+        match bytes[0] {
+            0 => Some(Self::Reverb),
+            1 => Some(Self::StaticSource { position, radius }),
+            2 => Some(Self::StaticListener { position, radius }),
+            _ => None,
+        }
+    }
+}
+
+/// The baked data of a [`ProbeVolume`], save it with [`BakedDataSaver`] and insert a
+/// [`BakedProbeVolume`] with the loaded handle so shipping builds skip baking.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct BakedProbeVolume(pub Handle<BakedDataAsset>);
+
+/// A serialized Steam Audio probe batch holding baked reflections.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct BakedDataAsset {
+    pub variation: BakeVariation,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct BakedDataLoader;
+
+impl AssetLoader for BakedDataLoader {
+    type Asset = BakedDataAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+
+        let variation = data
+            .get(..17)
+            .and_then(|header| BakeVariation::from_bytes(header.try_into().unwrap()))
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid baked data header")
+            })?;
+        data.drain(..17);
+
+        Ok(BakedDataAsset { variation, data })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["probes"]
+    }
+}
+
+#[derive(Default)]
+pub struct BakedDataSaver;
+
+impl AssetSaver for BakedDataSaver {
+    type Asset = BakedDataAsset;
+    type Settings = ();
+    type OutputLoader = BakedDataLoader;
+    type Error = std::io::Error;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, Self::Asset>,
+        _settings: &(),
+    ) -> Result<(), Self::Error> {
+        writer.write_all(&asset.variation.to_bytes()).await?;
+        writer.write_all(&asset.data).await
+    }
+}
+
+/// Bakes the reflections of a [`ProbeVolume`], requests are baked one at a time in the order
+/// they were sent.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BakeReflections {
+    pub volume: Entity,
+    pub variation: BakeVariation,
+}
+
+/// Sent every frame while a volume bakes.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BakeProgress {
+    pub volume: Entity,
+    /// From `0.0` to `1.0`.
+    pub progress: f32,
+}
+
+/// Sent when a bake ended, the volume has a [`BakedProbeVolume`] if it succeeded.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BakeFinished {
+    pub volume: Entity,
+    pub succeeded: bool,
+}
+
+/// The bake job and the requests waiting for it.
+#[derive(Resource, Default)]
+pub struct BakeReflectionsTask {
+    task: Option<(BakeReflections, Task<Result<Vec<u8>, String>>)>,
+    queue: VecDeque<BakeReflections>,
+    progress: Arc<AtomicF32>,
+}
+
+impl BakeReflectionsTask {
+    /// Progress of the running bake from `0.0` to `1.0`.
+    pub fn progress(&self) -> f32 {
+        self.progress.load()
+    }
+
+    /// The volume being baked, if any.
+    pub fn volume(&self) -> Option<Entity> {
+        self.task.as_ref().map(|(request, _)| request.volume)
+    }
+
+    /// Whether a bake is running, the scene mustn't be committed until it's done.
+    pub(crate) fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+}
+
+/// The probe batches added to the simulator, one per [`BakedProbeVolume`] and [`AudioArea`].
+#[derive(Resource, Default)]
+pub struct ProbeBatches {
+    simulator: Option<Arc<Simulator>>,
+    batches: EntityHashMap<(ProbeBatch, BakeVariation)>,
+    removed: Vec<ProbeBatch>,
+    /// Volumes whose data couldn't be loaded, they aren't retried.
+    failed: EntityHashSet,
+}
+
+impl ProbeBatches {
+    /// The baked data of `volume`, if it's in the simulator.
+    pub(crate) fn identifier(
+        &self,
+        volume: Entity,
+        units: AudioUnitsPerMeter,
+    ) -> Option<BakedDataIdentifier> {
+        self.batches
+            .get(&volume)
+            .map(|(_, variation)| variation.identifier(units))
+    }
+
+    /// The probes of `volume`, if they're in the simulator.
+    pub(crate) fn batch(&self, volume: Entity) -> Option<&ProbeBatch> {
+        self.batches.get(&volume).map(|(batch, _)| batch)
+    }
+}
+
+/// Starts the next requested bake and stores its result once it's done.
+#[allow(clippy::too_many_arguments)]
+pub fn bake_probe_volumes(
+    mut commands: Commands,
+    mut bake: ResMut<BakeReflectionsTask>,
+    mut requests: EventReader<BakeReflections>,
+    mut progress_events: EventWriter<BakeProgress>,
+    mut finished: EventWriter<BakeFinished>,
+    mut assets: ResMut<Assets<BakedDataAsset>>,
+    settings: Res<SpatialAudioSettings>,
+    config: Res<ReflectionConfig>,
+    pathing: Res<PathingConfig>,
+    units: Res<AudioUnitsPerMeter>,
+    scene: Res<SteamAudioScene>,
+    volumes: Query<(&ProbeVolume, &GlobalTransform)>,
+) {
+    bake.queue.extend(requests.read().copied());
+
+    if let Some((request, task)) = &bake.task {
+        if !task.is_finished() {
+            progress_events.send(BakeProgress {
+                volume: request.volume,
+                progress: bake.progress.load(),
+            });
+            return;
+        }
+
+        let (request, task) = bake.task.take().unwrap();
+        let volume = request.volume;
+        let succeeded = match block_on(task) {
+            Ok(data) => {
+                bake.progress.store(1.0);
+                let handle = assets.add(BakedDataAsset {
+                    variation: request.variation,
+                    data,
+                });
+                if let Some(mut entity) = commands.get_entity(volume) {
+                    entity.insert(BakedProbeVolume(handle));
+                }
+                true
+            }
+            Err(err) => {
+                warn!("Could not bake reflections for {volume:?}: {err}");
+                false
+            }
+        };
+        finished.send(BakeFinished { volume, succeeded });
+        return;
+    }
+
+    // Probes are placed against the committed scene.
+    if scene.is_dirty() {
+        return;
+    }
+
+    let Some(request) = bake.queue.pop_front() else {
+        return;
+    };
+    let volume = request.volume;
+    let Some(simulator) = settings.simulator.clone() else {
+        warn!("Could not bake reflections for {volume:?}, the simulation is disabled");
+        finished.send(BakeFinished {
+            volume,
+            succeeded: false,
+        });
+        return;
+    };
+    let Ok((probe_volume, transform)) = volumes.get(volume) else {
+        warn!("Could not bake reflections for {volume:?}, it has no ProbeVolume");
+        finished.send(BakeFinished {
+            volume,
+            succeeded: false,
+        });
+        return;
+    };
+
+    let batch = ProbeArray::new(&settings.context).and_then(|mut probe_array| {
+        // Probes are generated in meters.
+        let size = Mat4::from_scale(Vec3::splat(units.get().recip()))
+            * transform.compute_matrix()
+            * Mat4::from_scale(probe_volume.half_extents * 2.0);
+        probe_array.generate_probes(
+            &scene.scene,
+            &ProbeGenerationParams {
+                type_: ProbeGenerationType::UniformFloor,
+                spacing: bevy_distance_to_phonon(probe_volume.spacing, *units),
+                height: PROBE_HEIGHT,
+                transform: size.into(),
+            },
+        );
+
+        let mut batch = ProbeBatch::new(&settings.context)?;
+        batch.add_probe_array(&probe_array);
+        batch.commit();
+        Ok(batch)
+    });
+    let mut batch = match batch {
+        Ok(batch) => batch,
+        Err(err) => {
+            warn!("Could not generate probes for {volume:?}: {err:?}");
+            finished.send(BakeFinished {
+                volume,
+                succeeded: false,
+            });
+            return;
+        }
+    };
+
+    let bake_settings = BakedReflectionsSettings {
+        identifier: request.variation.identifier(*units),
+        num_rays: config.rays,
+        num_bounces: config.bounces,
+        duration: config.duration,
+        order: config.order,
+        ..Default::default()
+    };
+    let path_settings = pathing.enabled.then(|| pathing.bake_settings());
+    let scene = scene.scene.clone();
+    let progress = bake.progress.clone();
+    progress.store(0.0);
+
+    // Baking traces every probe and takes seconds to minutes.
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        // Pathing is baked into the same batch, each half of the progress is one bake.
+        let share = if path_settings.is_some() { 0.5 } else { 1.0 };
+        simulator
+            .bake_reflections(&scene, &mut batch, &bake_settings, |done| {
+                progress.store(done.clamp(0.0, 1.0) * share);
+            })
+            .map_err(|err| format!("{err:?}"))?;
+        if let Some(path_settings) = path_settings {
+            simulator
+                .bake_pathing(&scene, &mut batch, &path_settings, |done| {
+                    progress.store(0.5 + done.clamp(0.0, 1.0) * 0.5);
+                })
+                .map_err(|err| format!("{err:?}"))?;
+        }
+        Ok(batch.save())
+    });
+    bake.task = Some((request, task));
+}
+
+/// Adds the probe batch of every loaded [`BakedProbeVolume`] and [`AudioArea`] reverb to the
+/// simulator.
+#[allow(clippy::too_many_arguments)]
+pub fn load_baked_data(
+    mut batches: ResMut<ProbeBatches>,
+    settings: Res<SpatialAudioSettings>,
+    reflections: Res<ReflectionState>,
+    direct: Res<DirectSimulationState>,
+    assets: Res<Assets<BakedDataAsset>>,
+    volumes: Query<(Entity, &BakedProbeVolume)>,
+    areas: Query<(Entity, &AudioArea)>,
+    mut removed: RemovedComponents<BakedProbeVolume>,
+    mut removed_areas: RemovedComponents<AudioArea>,
+) {
+    let Some(simulator) = &settings.simulator else {
+        return;
+    };
+    // Batches belong to the simulator they were added to, start over after a rebuild.
+    if !batches
+        .simulator
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, simulator))
+    {
+        batches.simulator = Some(simulator.clone());
+        batches.batches.clear();
+        batches.removed.clear();
+        batches.failed.clear();
+    }
+
+    for entity in removed.read().chain(removed_areas.read()) {
+        batches.failed.remove(&entity);
+        if let Some((batch, _)) = batches.batches.remove(&entity) {
+            batches.removed.push(batch);
+        }
+    }
+
+    // The simulator can't change while it simulates, try next frame.
+    if reflections.is_running() || direct.is_running() {
+        return;
+    }
+
+    let mut changed = !batches.removed.is_empty();
+    for batch in batches.removed.drain(..) {
+        simulator.remove_probe_batch(&batch);
+    }
+
+    let handles = volumes
+        .iter()
+        .map(|(entity, volume)| (entity, &volume.0))
+        .chain(areas.iter().map(|(entity, area)| (entity, &area.reverb_ir)));
+    for (entity, handle) in handles {
+        if batches.batches.contains_key(&entity) || batches.failed.contains(&entity) {
+            continue;
+        }
+        // Not loaded yet.
+        let Some(baked) = assets.get(handle) else {
+            continue;
+        };
+
+        match ProbeBatch::load(&settings.context, &baked.data) {
+            Ok(batch) => {
+                simulator.add_probe_batch(&batch);
+                batches.batches.insert(entity, (batch, baked.variation));
+                changed = true;
+            }
+            Err(err) => {
+                warn!("Could not load baked reflections for {entity:?}: {err:?}");
+                batches.failed.insert(entity);
+            }
+        }
+    }
+
+    if changed {
+        simulator.commit();
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::{
+    log::warn,
+    prelude::{Event, Reflect, ReflectResource, Resource},
+};
+use steam_audio::prelude::{
+    Context, EmbreeDevice, OpenCLDevice, RadeonRaysDevice, SceneSettings, SceneType,
+    SimulationSettings,
+};
+
+use crate::opencl::OpenClConfig;
+
+/// The ray tracer the scene and the simulator trace with.
+///
+/// Embree is faster on large levels, Radeon Rays traces on the GPU through OpenCL. Both are
+/// behind cargo features of the same name, and fall back to Steam Audio's own ray tracer when
+/// their device can't be created.
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RayTracerBackend {
+    #[default]
+    Phonon,
+    #[cfg(feature = "embree")]
+    Embree,
+    #[cfg(feature = "radeon-rays")]
+    RadeonRays,
+}
+
+impl RayTracerBackend {
+    /// The backend tracing on the GPU, `None` without the `radeon-rays` feature.
+    #[cfg(feature = "radeon-rays")]
+    pub fn gpu() -> Option<Self> {
+        Some(Self::RadeonRays)
+    }
+
+    /// The backend tracing on the GPU, `None` without the `radeon-rays` feature.
+    #[cfg(not(feature = "radeon-rays"))]
+    pub fn gpu() -> Option<Self> {
+        None
+    }
+}
+
+/// Whether the simulation ended up on the GPU, through Radeon Rays or TrueAudio Next.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct GpuSimulationAvailable(pub bool);
+
+/// What the plugin ended up initializing, for debug overlays.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct SteamAudioInfo {
+    /// The backend in use, [`RayTracerBackend::Phonon`] after a fallback.
+    pub ray_tracer: RayTracerBackend,
+    /// Whether reflections are convolved with TrueAudio Next, see [`OpenClConfig`].
+    pub true_audio_next: bool,
+}
+
+/// Sent when the requested [`RayTracerBackend`] couldn't be initialized and Phonon is used
+/// instead.
+#[derive(Event, Debug, Clone)]
+pub struct RayTracerFallback {
+    pub requested: RayTracerBackend,
+    pub reason: String,
+}
+
+/// The devices of a [`RayTracerBackend`], shared by the scene, its sub-scenes and the
+/// simulator.
+#[derive(Clone, Default)]
+pub(crate) struct RayTracer {
+    pub(crate) backend: RayTracerBackend,
+    embree: Option<EmbreeDevice>,
+    opencl: Option<OpenCLDevice>,
+    radeon_rays: Option<RadeonRaysDevice>,
+    /// See [`OpenClConfig::bake_batch_size`].
+    bake_batch_size: u32,
+}
+
+impl RayTracer {
+    /// Creates the devices of `backend`, or falls back to Phonon with the reason they failed.
+    pub(crate) fn new(
+        context: &Context,
+        backend: RayTracerBackend,
+        opencl: &OpenClConfig,
+    ) -> (Self, Option<String>) {
+        match Self::try_new(context, backend, opencl) {
+            Ok(ray_tracer) => (ray_tracer, None),
+            Err(reason) => {
+                warn!("{reason}, falling back to the Phonon ray tracer.");
+                (Self::default(), Some(reason))
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "radeon-rays"), allow(unused_variables))]
+    fn try_new(
+        context: &Context,
+        backend: RayTracerBackend,
+        opencl: &OpenClConfig,
+    ) -> Result<Self, String> {
+        match backend {
+            RayTracerBackend::Phonon => Ok(Self::default()),
+            #[cfg(feature = "embree")]
+            RayTracerBackend::Embree => {
+                let embree = EmbreeDevice::new(context)
+                    .map_err(|err| format!("could not create embree device: {err:?}"))?;
+                Ok(Self {
+                    backend,
+                    embree: Some(embree),
+                    ..Default::default()
+                })
+            }
+            #[cfg(feature = "radeon-rays")]
+            RayTracerBackend::RadeonRays => {
+                let device = opencl.device(context)?;
+                let radeon_rays = RadeonRaysDevice::new(&device)
+                    .map_err(|err| format!("could not create radeon rays device: {err:?}"))?;
+                Ok(Self {
+                    backend,
+                    opencl: Some(device),
+                    radeon_rays: Some(radeon_rays),
+                    bake_batch_size: opencl.bake_batch_size.max(1),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    /// The OpenCL device of Radeon Rays, TrueAudio Next runs on the same one.
+    pub(crate) fn opencl(&self) -> Option<&OpenCLDevice> {
+        self.opencl.as_ref()
+    }
+
+    fn scene_type(&self) -> SceneType {
+        match self.backend {
+            RayTracerBackend::Phonon => SceneType::Default,
+            #[cfg(feature = "embree")]
+            RayTracerBackend::Embree => SceneType::Embree,
+            #[cfg(feature = "radeon-rays")]
+            RayTracerBackend::RadeonRays => SceneType::RadeonRays,
+        }
+    }
+
+    pub(crate) fn scene_settings(&self) -> SceneSettings {
+        SceneSettings {
+            type_: self.scene_type(),
+            embree_device: self.embree.clone(),
+            radeon_rays_device: self.radeon_rays.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn apply(&self, settings: &mut SimulationSettings) {
+        settings.scene_type = self.scene_type();
+        settings.opencl_device = self.opencl.clone();
+        settings.radeon_rays_device = self.radeon_rays.clone();
+        if self.radeon_rays.is_some() {
+            settings.bake_batch_size = self.bake_batch_size;
+        }
+    }
+}
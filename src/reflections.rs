@@ -0,0 +1,346 @@
+use bevy::{
+    log::warn,
+    prelude::{
+        DetectChanges, GlobalTransform, Query, Reflect, ReflectResource, Res, ResMut, Resource,
+        With,
+    },
+    tasks::{AsyncComputeTaskPool, Task},
+    time::Time,
+};
+use std::sync::Arc;
+use steam_audio::{
+    hrtf::AudioSettings,
+    prelude::{
+        Context, DeinterleavedFrame, ReflectionEffect, ReflectionEffectParams,
+        ReflectionEffectSettings, ReflectionEffectType, SimulationFlags, SimulationSettings,
+        SimulationSharedInputs,
+    },
+};
+
+use crate::{
+    ambisonics::AmbisonicsBus,
+    opencl::{TanSlot, TrueAudioNext},
+    pathing::PathingConfig,
+    playback::SpatialAudioSource,
+    settings::AudioUnitsPerMeter,
+    simulation::SimulationSource,
+    source::{PrimaryListener, SourceOrientation, SpatialAudioSettings},
+};
+
+/// Real-time reflection simulation, traced against the
+/// [`SteamAudioScene`](crate::geometry::SteamAudioScene) on a background task.
+///
+/// `rays`, `duration` and `order` are the most the simulator is built for, so changing them at
+/// runtime only takes effect up to the values the plugin started with. Values out of range are
+/// clamped with a warning by [`validate_reflection_config`].
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct ReflectionConfig {
+    pub enabled: bool,
+    /// Rays traced from the listener per simulation.
+    pub rays: u32,
+    /// Times each ray is reflected.
+    pub bounces: u32,
+    /// Length of the impulse responses in seconds.
+    pub duration: f32,
+    /// Ambisonics order of the impulse responses.
+    pub order: u32,
+    /// Simulations per second.
+    pub update_hz: f32,
+}
+
+impl Default for ReflectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rays: 4096,
+            bounces: 4,
+            duration: 1.0,
+            order: 1,
+            update_hz: 10.0,
+        }
+    }
+}
+
+impl ReflectionConfig {
+    pub(crate) fn apply(&self, settings: &mut SimulationSettings) {
+        if !self.enabled {
+            return;
+        }
+
+        settings.flags |= SimulationFlags::REFLECTIONS;
+        settings.max_num_rays = self.rays;
+        settings.max_duration = self.duration;
+        settings.max_order = self.order;
+    }
+
+    /// Shared simulation inputs for a listener at `listener`.
+    pub(crate) fn shared_inputs(
+        &self,
+        listener: SourceOrientation,
+        units: AudioUnitsPerMeter,
+    ) -> SimulationSharedInputs {
+        SimulationSharedInputs {
+            listener: listener.to_phonon(units),
+            num_rays: self.rays,
+            num_bounces: self.bounces,
+            duration: self.duration,
+            order: self.order,
+            ..Default::default()
+        }
+    }
+
+    /// The config with every value in the range Steam Audio accepts, and at most the limits in
+    /// `max` the simulator was built with.
+    fn clamped(&self, max: Option<&SimulationSettings>) -> Self {
+        let mut clamped = Self {
+            rays: self.rays.max(1),
+            bounces: self.bounces.max(1),
+            duration: self.duration.max(0.01),
+            update_hz: self.update_hz.max(0.1),
+            ..*self
+        };
+        if let Some(max) = max {
+            clamped.rays = clamped.rays.min(max.max_num_rays.max(1));
+            clamped.duration = clamped.duration.min(max.max_duration);
+            clamped.order = clamped.order.min(max.max_order);
+        }
+        clamped
+    }
+
+    /// Samples in the impulse responses at the rate of `audio_settings`.
+    pub(crate) fn ir_size(&self, audio_settings: &AudioSettings) -> u32 {
+        (self.duration * audio_settings.sampling_rate() as f32).ceil() as u32
+    }
+
+    fn channels(&self) -> usize {
+        let order = self.order as usize;
+        (order + 1) * (order + 1)
+    }
+}
+
+/// The reflection task tracing for the [`SimulationSource`]s.
+#[derive(Resource, Default)]
+pub struct ReflectionState {
+    task: Option<Task<()>>,
+    since_update: f32,
+    /// Frames [`simulate_reflections`] has run for.
+    frame: u64,
+    /// The frame the running task was started on.
+    started_frame: u64,
+    /// The frame the simulation behind the voices' current results was started on.
+    result_frame: Option<u64>,
+}
+
+impl ReflectionState {
+    /// Whether a simulation is running, the simulator mustn't be committed until it's done.
+    pub(crate) fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// How many frames ago the listener and sources of the current results were captured,
+    /// `None` before the first simulation finishes.
+    pub(crate) fn latency_frames(&self) -> Option<u64> {
+        self.result_frame.map(|frame| self.frame - frame)
+    }
+}
+
+/// Clamps a changed [`ReflectionConfig`] instead of handing bad values to Steam Audio.
+pub fn validate_reflection_config(
+    mut config: ResMut<ReflectionConfig>,
+    settings: Res<SpatialAudioSettings>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    // The limits only exist when the simulator was built with reflections.
+    let max = Some(&settings.simulation_settings).filter(|simulation| {
+        settings.simulator.is_some() && simulation.flags.contains(SimulationFlags::REFLECTIONS)
+    });
+    let clamped = config.clamped(max);
+    if clamped != *config {
+        warn!(
+            "ReflectionConfig {:?} is out of range, clamped to {clamped:?}",
+            *config
+        );
+        *config = clamped;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_reflections(
+    mut state: ResMut<ReflectionState>,
+    config: Res<ReflectionConfig>,
+    pathing: Res<PathingConfig>,
+    settings: Res<SpatialAudioSettings>,
+    units: Res<AudioUnitsPerMeter>,
+    time: Res<Time>,
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    sources: Query<(&SimulationSource, &SpatialAudioSource)>,
+) {
+    state.since_update += time.delta_secs();
+    state.frame += 1;
+
+    if let Some(task) = &state.task {
+        if !task.is_finished() {
+            return;
+        }
+
+        state.task = None;
+        state.result_frame = Some(state.started_frame);
+        for (simulation_source, source) in sources.iter() {
+            if source.voice.is_paused() {
+                continue;
+            }
+            if source.voice.reflection_config.is_some() {
+                let outputs = simulation_source
+                    .source()
+                    .get_outputs(SimulationFlags::REFLECTIONS);
+                *source.voice.reflections.lock().unwrap() = Some(outputs.reflections);
+            }
+            if source.voice.pathing_config.is_some() {
+                let outputs = simulation_source
+                    .source()
+                    .get_outputs(SimulationFlags::PATHING);
+                *source.voice.pathing.lock().unwrap() = Some(outputs.pathing);
+            }
+        }
+    }
+
+    if !(config.enabled || pathing.enabled)
+        || sources.is_empty()
+        || state.since_update < 1.0 / config.update_hz.max(f32::EPSILON)
+    {
+        return;
+    }
+    state.since_update = 0.0;
+
+    let Some(listener) = listener.iter().next() else {
+        return;
+    };
+    let Some(simulator) = settings.simulator.clone() else {
+        return;
+    };
+    let mut flags = SimulationFlags::empty();
+    if config.enabled {
+        flags |= SimulationFlags::REFLECTIONS;
+    }
+    if pathing.enabled {
+        flags |= SimulationFlags::PATHING;
+    }
+    simulator.set_shared_inputs(
+        flags,
+        &config.shared_inputs(SourceOrientation::from(listener), *units),
+    );
+
+    // Ray tracing takes far longer than an audio block, keep it off both the game and audio
+    // threads. Pathing searches the baked probes and rides along on the same task.
+    let (reflections, pathing) = (config.enabled, pathing.enabled);
+    state.started_frame = state.frame;
+    state.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        if reflections {
+            simulator.run_reflections();
+        }
+        if pathing {
+            simulator.run_pathing();
+        }
+    }));
+}
+
+/// Where the reflections of a voice started while reflections were enabled are rendered.
+#[derive(Clone)]
+pub(crate) struct ReflectionTarget {
+    pub(crate) config: ReflectionConfig,
+    pub(crate) bus: Arc<AmbisonicsBus>,
+    /// Set when the plugin created a TrueAudio Next device.
+    pub(crate) true_audio_next: Option<Arc<TrueAudioNext>>,
+}
+
+/// Convolves a voice with its simulated reflections and mixes them into the shared
+/// [`AmbisonicsBus`].
+pub(crate) struct ReflectionPipeline {
+    config: ReflectionConfig,
+    audio_settings: AudioSettings,
+    reflection_effect: ReflectionEffect,
+    bus: Arc<AmbisonicsBus>,
+    /// The TrueAudio Next slot the voice is convolved in, `None` on the CPU.
+    tan_slot: Option<TanSlot>,
+    /// The send the last block ended on, the next one ramps from it.
+    gain: f32,
+    /// The block's reflections before they are mixed into the bus, kept between blocks.
+    sound_field: DeinterleavedFrame,
+}
+
+impl ReflectionPipeline {
+    pub(crate) fn new(
+        context: &Context,
+        audio_settings: &AudioSettings,
+        target: ReflectionTarget,
+    ) -> Self {
+        let ReflectionTarget {
+            config,
+            bus,
+            true_audio_next,
+        } = target;
+        // Voices past the device's slots convolve on the CPU.
+        let tan_slot = true_audio_next.and_then(TanSlot::take);
+
+        let effect_settings = ReflectionEffectSettings {
+            type_: match tan_slot {
+                Some(_) => ReflectionEffectType::TrueAudioNext,
+                None => ReflectionEffectType::Convolution,
+            },
+            ir_size: config.ir_size(audio_settings),
+            num_channels: config.channels() as u32,
+        };
+        let reflection_effect = ReflectionEffect::new(context, audio_settings, &effect_settings)
+            .expect("could not build steam audio reflection effect");
+
+        let sound_field = DeinterleavedFrame::new(
+            audio_settings.frame_size() as usize,
+            config.channels(),
+            audio_settings.sampling_rate(),
+        );
+        Self {
+            config,
+            audio_settings: audio_settings.clone(),
+            reflection_effect,
+            bus,
+            tan_slot,
+            gain: 0.0,
+            sound_field,
+        }
+    }
+
+    /// Mixes the reflections of the mono `input` into the bus, ramping from the previous block's
+    /// send to `gain` so changes don't click.
+    pub(crate) fn apply(
+        &mut self,
+        params: &ReflectionEffectParams,
+        input: &mut DeinterleavedFrame,
+        gain: f32,
+    ) {
+        let sound_field = &mut self.sound_field;
+        match &self.tan_slot {
+            Some(slot) => {
+                let params = ReflectionEffectParams {
+                    type_: ReflectionEffectType::TrueAudioNext,
+                    tan_device: Some(slot.device().clone()),
+                    tan_slot: slot.index,
+                    ..params.clone()
+                };
+                self.reflection_effect
+                    .apply_to_buffer(&params, input, sound_field)
+                    .unwrap();
+            }
+            None => self
+                .reflection_effect
+                .apply_to_buffer(params, input, sound_field)
+                .unwrap(),
+        }
+        let gain = gain.max(0.0);
+        self.bus.mix(sound_field, self.gain, gain);
+        self.gain = gain;
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::utils::Duration;
+use std::{fmt, sync::Arc};
+
+use rodio::Source;
+
+use crate::source::{asset_file_path, ASSET_FOLDER};
+
+/// Builds a fresh source each time a voice starts or seeks backwards.
+pub type SourceFactory = Arc<dyn Fn() -> Box<dyn Source<Item = f32> + Send> + Send + Sync>;
+
+/// Sample rate of the silence played in place of audio that couldn't be read.
+const SILENCE_RATE: u32 = 44_100;
+
+/// Why the samples of a [`SteamAudio`](crate::source::SteamAudio) couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SpatialAudioErrorKind {
+    #[error("could not open {path}: {reason}")]
+    Open { path: String, reason: String },
+    #[error("could not decode {path}: {reason}")]
+    Decode { path: String, reason: String },
+}
+
+/// Where the samples of a [`SteamAudio`](crate::source::SteamAudio) come from.
+#[derive(Clone)]
+pub enum AudioData {
+    /// An audio file on disk, streamed as it plays.
+    File(String),
+    /// Interleaved samples in memory.
+    Samples {
+        samples: Arc<[f32]>,
+        sample_rate: u32,
+        channels: u16,
+    },
+    /// A procedural or streamed source, like a synthesizer or a network voice.
+    Source(SourceFactory),
+}
+
+impl fmt::Debug for AudioData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Samples {
+                samples,
+                sample_rate,
+                channels,
+            } => f
+                .debug_struct("Samples")
+                .field("len", &samples.len())
+                .field("sample_rate", sample_rate)
+                .field("channels", channels)
+                .finish(),
+            Self::Source(_) => f.write_str("Source"),
+        }
+    }
+}
+
+impl AudioData {
+    /// Starts reading from the beginning.
+    pub(crate) fn open(&self) -> Result<SampleProvider, SpatialAudioErrorKind> {
+        let input = match self {
+            Self::File(path) => {
+                // Paths that don't exist relative to the working directory are looked up in the
+                // asset folder.
+                let file = std::fs::File::open(path)
+                    .or_else(|_| std::fs::File::open(asset_file_path(ASSET_FOLDER, path)))
+                    .map_err(|err| SpatialAudioErrorKind::Open {
+                        path: path.clone(),
+                        reason: err.to_string(),
+                    })?;
+                let decoder =
+                    rodio::Decoder::new(file).map_err(|err| SpatialAudioErrorKind::Decode {
+                        path: path.clone(),
+                        reason: err.to_string(),
+                    })?;
+                Input::File(decoder)
+            }
+            Self::Samples {
+                samples,
+                sample_rate,
+                channels,
+            } => Input::Samples {
+                samples: samples.clone(),
+                position: 0,
+                sample_rate: *sample_rate,
+                channels: *channels,
+            },
+            Self::Source(factory) => Input::Source(factory()),
+        };
+
+        Ok(SampleProvider { input })
+    }
+}
+
+/// The mono signal a decoder reads, multichannel input is averaged down.
+pub(crate) struct SampleProvider {
+    input: Input,
+}
+
+enum Input {
+    File(rodio::Decoder<std::fs::File>),
+    Samples {
+        samples: Arc<[f32]>,
+        position: usize,
+        sample_rate: u32,
+        channels: u16,
+    },
+    Source(Box<dyn Source<Item = f32> + Send>),
+    /// Frames of silence left.
+    Silence(u64),
+}
+
+impl SampleProvider {
+    /// `duration` of silence, played in place of audio that couldn't be read.
+    pub(crate) fn silence(duration: Duration) -> Self {
+        let frames = duration.as_secs_f64() * SILENCE_RATE as f64;
+        Self {
+            input: Input::Silence(frames as u64),
+        }
+    }
+
+    pub(crate) fn sample_rate(&self) -> u32 {
+        match &self.input {
+            Input::File(decoder) => decoder.sample_rate(),
+            Input::Samples { sample_rate, .. } => *sample_rate,
+            Input::Source(source) => source.sample_rate(),
+            Input::Silence(_) => SILENCE_RATE,
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        match &self.input {
+            Input::File(decoder) => decoder.channels(),
+            Input::Samples { channels, .. } => *channels,
+            Input::Source(source) => source.channels(),
+            Input::Silence(_) => 1,
+        }
+    }
+
+    fn next_sample(&mut self) -> Option<f32> {
+        match &mut self.input {
+            Input::File(decoder) => decoder
+                .next()
+                .map(|sample| rodio::cpal::Sample::to_f32(&sample)),
+            Input::Samples {
+                samples, position, ..
+            } => {
+                let sample = samples.get(*position).copied();
+                *position += 1;
+                sample
+            }
+            Input::Source(source) => source.next(),
+            Input::Silence(remaining) => {
+                *remaining = remaining.checked_sub(1)?;
+                Some(0.0)
+            }
+        }
+    }
+}
+
+impl Iterator for SampleProvider {
+    type Item = f32;
+
+    /// The next frame, averaged over its channels. A partial final frame ends the input, as
+    /// does a source without channels.
+    fn next(&mut self) -> Option<f32> {
+        let channels = self.channels();
+        if channels == 0 {
+            return None;
+        }
+        let mut sum = 0.0;
+        for _ in 0..channels {
+            sum += self.next_sample()?;
+        }
+        Some(sum / channels as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(samples: &[f32], channels: u16) -> SampleProvider {
+        AudioData::Samples {
+            samples: samples.into(),
+            sample_rate: SILENCE_RATE,
+            channels,
+        }
+        .open()
+        .unwrap()
+    }
+
+    #[test]
+    fn frames_are_averaged_to_mono() {
+        let stereo = samples(&[1.0, 3.0, -1.0, 0.0, 0.5, 0.5], 2);
+        assert_eq!(stereo.collect::<Vec<_>>(), [2.0, -0.5, 0.5]);
+
+        let quad = samples(&[1.0, 2.0, 3.0, 4.0], 4);
+        assert_eq!(quad.collect::<Vec<_>>(), [2.5]);
+
+        let source = AudioData::Source(Arc::new(|| {
+            Box::new(rodio::buffer::SamplesBuffer::new(
+                2,
+                SILENCE_RATE,
+                vec![0.25, 0.75, 1.0, 0.0],
+            ))
+        }));
+        assert_eq!(source.open().unwrap().collect::<Vec<_>>(), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn partial_frame_ends_the_input() {
+        let stereo = samples(&[1.0, 1.0, 1.0], 2);
+        assert_eq!(stereo.collect::<Vec<_>>(), [1.0]);
+    }
+
+    #[test]
+    fn no_channels_ends_the_input() {
+        let mut empty = samples(&[1.0, 1.0], 0);
+        assert_eq!(empty.next(), None);
+        assert_eq!(empty.next(), None);
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::{
+    asset::Assets,
+    log::warn,
+    prelude::{
+        Added, Changed, Component, Entity, GlobalTransform, Local, Mesh, Mesh3d, Or, Query,
+        Reflect, ReflectComponent, RemovedComponents, Res, ResMut, Resource, With,
+    },
+};
+
+use crate::{
+    material::{AudioMaterial, MaterialLibrary},
+    mesh::{AudioMesh, AudioSceneBuilder, MaterialPalette, WELD_TOLERANCE},
+    portal::AudioPortal,
+};
+
+/// Marks an entity's `Mesh3d` as geometry sound can be occluded and reflected by.
+///
+/// Obstacles are added to the [`SteamAudioScene`](crate::geometry::SteamAudioScene) the
+/// simulator traces against, and merged into the [`AudioSceneMesh`] used for transmission.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct AudioObstacle;
+
+/// Marks an entity's `Mesh3d` as geometry that moves, like a door or a vehicle.
+///
+/// Unlike an [`AudioObstacle`], the mesh is added once in its local space and only its
+/// transform is updated as the entity moves, without rebuilding the scene. It occludes and
+/// reflects sound, but isn't part of the [`AudioSceneMesh`] used for transmission.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct DynamicAudioGeometry;
+
+/// All [`AudioObstacle`]s merged into one world space mesh by [`extract_audio_scene`].
+#[derive(Resource, Default)]
+pub struct AudioSceneMesh(pub Option<AudioMesh>);
+
+/// Rebuilds the [`AudioSceneMesh`] whenever obstacles are added or removed, or a portal opens
+/// or closes.
+#[allow(clippy::too_many_arguments)]
+pub fn extract_audio_scene(
+    mut scene_mesh: ResMut<AudioSceneMesh>,
+    meshes: Res<Assets<Mesh>>,
+    library: Res<MaterialLibrary>,
+    obstacles: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &GlobalTransform,
+            Option<&AudioMaterial>,
+            Option<&MaterialPalette>,
+        ),
+        With<AudioObstacle>,
+    >,
+    added: Query<(), Added<AudioObstacle>>,
+    mut removed: RemovedComponents<AudioObstacle>,
+    portals: Query<(&AudioPortal, &GlobalTransform)>,
+    changed_portals: Query<
+        (),
+        (
+            With<AudioPortal>,
+            Or<(Changed<AudioPortal>, Changed<GlobalTransform>)>,
+        ),
+    >,
+    mut removed_portals: RemovedComponents<AudioPortal>,
+    // Obstacles whose mesh hadn't loaded yet when the scene was last built.
+    mut pending: Local<bool>,
+) {
+    let removed = removed.read().count() + removed_portals.read().count() > 0;
+    if added.is_empty() && !removed && changed_portals.is_empty() && !*pending {
+        return;
+    }
+
+    *pending = false;
+    let mut builder = AudioSceneBuilder::default();
+    for (entity, mesh, transform, material, palette) in obstacles.iter() {
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            *pending = true;
+            continue;
+        };
+
+        let audio_mesh = AudioMesh::from_mesh_transformed(mesh, transform)
+            .and_then(|audio_mesh| audio_mesh.validate_and_weld(WELD_TOLERANCE))
+            // A palette assigns materials per triangle, otherwise the whole mesh shares one.
+            .and_then(|audio_mesh| match palette {
+                Some(palette) => audio_mesh.with_palette(palette),
+                None => Ok(audio_mesh
+                    .with_material(material.cloned().unwrap_or_default().resolve(&library))),
+            });
+        match audio_mesh {
+            Ok(audio_mesh) => {
+                builder.add_audio_mesh(audio_mesh);
+            }
+            Err(err) => warn!("Could not add audio obstacle {entity:?} to the scene: {err}"),
+        }
+    }
+
+    for (portal, transform) in portals.iter().filter(|(portal, _)| !portal.open) {
+        builder.add_audio_mesh(portal.audio_mesh(transform));
+    }
+
+    scene_mesh.0 = Some(builder.build());
+}
@@ -0,0 +1,343 @@
+use bevy::{
+    log::warn,
+    prelude::{Deref, DerefMut, DetectChanges, Reflect, ReflectResource, Res, ResMut, Resource},
+};
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+use steam_audio::{
+    hrtf::{AudioSettings, HRTFSettings, HRTF},
+    prelude::{Context, ContextSettings, SimulationSettings, Simulator},
+    simulation::source::AirAbsorptionModel,
+};
+
+use crate::{
+    geometry::{AudioSceneState, SteamAudioScene},
+    source::SpatialAudioSettings,
+};
+
+/// The medium sound travels through, shared by every source.
+///
+/// The defaults are Steam Audio's for air. Lower the speed of sound and raise the absorption to
+/// simulate underwater, or zero the absorption for space. An [`EnvironmentPreset`] sets the
+/// absorption for a few common surroundings.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct GlobalAudioSettings {
+    /// Speed of sound in meters per second, drives the Doppler shift.
+    pub speed_of_sound: f32,
+    /// Air absorption coefficients per meter for the low, mid and high bands.
+    pub air_absorption_low: f32,
+    pub air_absorption_mid: f32,
+    pub air_absorption_high: f32,
+}
+
+impl Default for GlobalAudioSettings {
+    fn default() -> Self {
+        Self {
+            speed_of_sound: 343.0,
+            air_absorption_low: 0.0002,
+            air_absorption_mid: 0.0017,
+            air_absorption_high: 0.0182,
+        }
+    }
+}
+
+impl GlobalAudioSettings {
+    /// The model every source's air absorption is simulated with.
+    pub fn air_absorption_model(&self) -> AirAbsorptionModel {
+        AirAbsorptionModel::Exponential {
+            coefficients: [
+                self.air_absorption_low,
+                self.air_absorption_mid,
+                self.air_absorption_high,
+            ],
+        }
+    }
+}
+
+/// Air absorption coefficients for common surroundings, insert or change it to overwrite those
+/// of [`GlobalAudioSettings`].
+///
+/// Sources with a custom or disabled [`AirAbsorption`](crate::attenuation::AirAbsorption) keep
+/// theirs, the speed of sound is left as is.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum EnvironmentPreset {
+    /// Steam Audio's defaults for air.
+    #[default]
+    Outdoor,
+    /// Distant sounds lose their highs quickly and everything is muffled.
+    Underwater,
+    /// Like outdoors, with the highs absorbed a few times faster.
+    Fog,
+    /// Nothing is absorbed.
+    Vacuum,
+}
+
+impl EnvironmentPreset {
+    /// Absorption coefficients per meter for the low, mid and high bands.
+    pub fn coefficients(&self) -> [f32; 3] {
+        match self {
+            Self::Outdoor => [0.0002, 0.0017, 0.0182],
+            Self::Underwater => [0.01, 0.08, 0.4],
+            Self::Fog => [0.0005, 0.006, 0.06],
+            Self::Vacuum => [0.0; 3],
+        }
+    }
+}
+
+pub fn apply_environment_preset(
+    preset: Option<Res<EnvironmentPreset>>,
+    mut global: ResMut<GlobalAudioSettings>,
+) {
+    let Some(preset) = preset.filter(|preset| preset.is_changed()) else {
+        return;
+    };
+
+    let [low, mid, high] = preset.coefficients();
+    global.air_absorption_low = low;
+    global.air_absorption_mid = mid;
+    global.air_absorption_high = high;
+}
+
+/// How many Bevy units make up a meter, for scenes not built in meters.
+///
+/// Positions and distances are divided by it on their way to Steam Audio, so attenuation, air
+/// absorption and Doppler behave as in a scene built in meters. Changing it rebuilds the scene
+/// at the new scale, baked probes keep the scale they were baked with.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct AudioUnitsPerMeter(pub f32);
+
+impl Default for AudioUnitsPerMeter {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl AudioUnitsPerMeter {
+    /// Bevy units in a meter, `1.0` while the scale isn't positive.
+    pub fn get(self) -> f32 {
+        if self.0 > 0.0 && self.0.is_finite() {
+            self.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Rebuilds the scene with a changed [`AudioUnitsPerMeter`], geometry already in it was
+/// converted with the old scale.
+pub fn update_units_per_meter(
+    units: Res<AudioUnitsPerMeter>,
+    mut scene: ResMut<SteamAudioScene>,
+    mut scene_state: ResMut<AudioSceneState>,
+) {
+    if !units.is_changed() {
+        return;
+    }
+
+    if !(units.0 > 0.0 && units.0.is_finite()) {
+        warn!("AudioUnitsPerMeter must be positive, got {}", units.0);
+    }
+    scene.set_units_per_meter(*units);
+    // The scene is still empty when the resource is first added.
+    if !units.is_added() {
+        *scene_state = AudioSceneState::Dirty;
+    }
+}
+
+/// Samples in each block Steam Audio processes. Smaller frames lower the latency, larger ones
+/// spend less CPU per sample.
+///
+/// Set through [`SpatialAudioPlugin::frame_size`](crate::source::SpatialAudioPlugin::frame_size)
+/// and used by the shared [`AudioSettings`] as well as every voice's effects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSize(u32);
+
+impl Default for FrameSize {
+    fn default() -> Self {
+        Self(AudioSettings::default().frame_size())
+    }
+}
+
+impl FrameSize {
+    pub const MIN: u32 = 64;
+    pub const MAX: u32 = 4096;
+
+    pub fn new(samples: u32) -> Result<Self, FrameSizeError> {
+        if !samples.is_power_of_two() {
+            return Err(FrameSizeError::NotPowerOfTwo(samples));
+        }
+        if !(Self::MIN..=Self::MAX).contains(&samples) {
+            return Err(FrameSizeError::OutOfRange(samples));
+        }
+        Ok(Self(samples))
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.0
+    }
+
+    /// The default [`AudioSettings`] with this frame size.
+    pub fn audio_settings(&self) -> AudioSettings {
+        AudioSettings::new(AudioSettings::default().sampling_rate(), self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FrameSizeError {
+    #[error("frame size {0} is not a power of two")]
+    NotPowerOfTwo(u32),
+    #[error("frame size {0} is outside of {}..={}", FrameSize::MIN, FrameSize::MAX)]
+    OutOfRange(u32),
+}
+
+/// The [`AudioSettings`] Steam Audio objects are built with, changing it rebuilds the HRTF and
+/// simulator.
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct AudioConfig(pub AudioSettings);
+
+/// The [`ContextSettings`] of the Steam Audio context, changing it rebuilds the context along with
+/// the HRTF and simulator built from it.
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct ContextConfig(pub ContextSettings);
+
+/// The [`HRTFSettings`] used for binaural rendering, changes are picked up by playing voices at
+/// their next block.
+///
+/// This is also where the HRTF volume and normalization are tuned.
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct HrtfConfig(pub HRTFSettings);
+
+/// The [`SimulationSettings`] of the simulator, changing it rebuilds the simulator.
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct SimulationConfig(pub SimulationSettings);
+
+/// HRTF settings shared with every voice, versioned so decoders notice when they change.
+#[derive(Default)]
+pub(crate) struct SharedHrtf {
+    settings: Mutex<HRTFSettings>,
+    generation: AtomicU32,
+}
+
+impl SharedHrtf {
+    pub(crate) fn new(settings: HRTFSettings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+            generation: AtomicU32::new(0),
+        }
+    }
+
+    pub(crate) fn publish(&self, settings: HRTFSettings) {
+        *self.settings.lock().unwrap() = settings;
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn settings(&self) -> HRTFSettings {
+        self.settings.lock().unwrap().clone()
+    }
+}
+
+/// Rebuilds the context and everything built from it when [`ContextConfig`] changes.
+///
+/// The scene belongs to the old context, so it's rebuilt from the current geometry. Simulation
+/// sources and probe batches notice the new simulator and add themselves to it again.
+pub fn context_update(
+    mut settings: ResMut<SpatialAudioSettings>,
+    mut scene_state: ResMut<AudioSceneState>,
+    context_config: Res<ContextConfig>,
+    audio_config: Res<AudioConfig>,
+    hrtf_config: Res<HrtfConfig>,
+    simulation_config: Res<SimulationConfig>,
+) {
+    if !context_config.is_changed() || context_config.is_added() {
+        return;
+    }
+
+    // Build everything before swapping anything in, so a failure leaves the old set intact. A
+    // disabled simulation stays disabled.
+    let simulate = settings.simulator.is_some();
+    let rebuilt = Context::new(&context_config).and_then(|context| {
+        let hrtf = HRTF::new(&context, &audio_config, &hrtf_config)?;
+        let simulator = simulate
+            .then(|| Simulator::new(&context, &simulation_config))
+            .transpose()?;
+        Ok((context, hrtf, simulator))
+    });
+
+    match rebuilt {
+        Ok((context, hrtf, simulator)) => {
+            settings.context = context;
+            settings.hrtf = hrtf;
+            settings.simulator = simulator.map(Arc::new);
+            settings.context_settings = context_config.0.clone();
+            settings.audio_settings = audio_config.0.clone();
+            settings.hrtf_settings = hrtf_config.0.clone();
+            settings.simulation_settings = simulation_config.0.clone();
+            settings.shared_hrtf.publish(hrtf_config.0.clone());
+            *scene_state = AudioSceneState::Dirty;
+        }
+        Err(err) => warn!("Could not rebuild steam audio context, keeping the old one: {err:?}"),
+    }
+}
+
+pub fn hrtf_update(
+    mut settings: ResMut<SpatialAudioSettings>,
+    audio_config: Res<AudioConfig>,
+    hrtf_config: Res<HrtfConfig>,
+) {
+    let changed = (audio_config.is_changed() && !audio_config.is_added())
+        || (hrtf_config.is_changed() && !hrtf_config.is_added());
+    if !changed {
+        return;
+    }
+
+    match HRTF::new(&settings.context, &audio_config, &hrtf_config) {
+        Ok(hrtf) => {
+            settings.hrtf = hrtf;
+            settings.audio_settings = audio_config.0.clone();
+            settings.hrtf_settings = hrtf_config.0.clone();
+            settings.shared_hrtf.publish(hrtf_config.0.clone());
+        }
+        Err(err) => warn!("Could not rebuild steam audio hrtf, keeping the old one: {err:?}"),
+    }
+}
+
+/// Rebuilds the simulator when [`AudioConfig`] or [`SimulationConfig`] change.
+///
+/// The new simulator starts without a scene, it's set again at the next commit. Simulation
+/// sources and probe batches notice the new simulator and add themselves to it again.
+pub fn simulation_update(
+    mut settings: ResMut<SpatialAudioSettings>,
+    mut scene: ResMut<SteamAudioScene>,
+    audio_config: Res<AudioConfig>,
+    simulation_config: Res<SimulationConfig>,
+) {
+    let changed = (audio_config.is_changed() && !audio_config.is_added())
+        || (simulation_config.is_changed() && !simulation_config.is_added());
+    if !changed {
+        return;
+    }
+    if settings.simulator.is_none() {
+        settings.audio_settings = audio_config.0.clone();
+        settings.simulation_settings = simulation_config.0.clone();
+        return;
+    }
+
+    match Simulator::new(&settings.context, &simulation_config) {
+        Ok(simulator) => {
+            settings.simulator = Some(Arc::new(simulator));
+            settings.audio_settings = audio_config.0.clone();
+            settings.simulation_settings = simulation_config.0.clone();
+            scene.mark_dirty();
+        }
+        Err(err) => warn!("Could not rebuild steam audio simulator, keeping the old one: {err:?}"),
+    }
+}
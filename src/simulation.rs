@@ -0,0 +1,736 @@
+use bevy::{
+    asset::Assets,
+    ecs::entity::EntityHashMap,
+    log::warn,
+    math::Vec3,
+    prelude::{
+        Commands, Component, DetectChanges, Entity, GlobalTransform, Has, Query, Reflect,
+        ReflectResource, RemovedComponents, Res, ResMut, Resource, SystemSet, With, Without,
+    },
+    tasks::{AsyncComputeTaskPool, Task},
+    time::Time,
+};
+use std::{sync::Arc, time::Instant};
+use steam_audio::{
+    prelude::{
+        Context, DirectSimulationFlags, DistanceAttenuationModel, SimulationFlags,
+        SimulationInputs, Simulator,
+    },
+    simulation::source::{
+        AirAbsorptionModel, Directivity, SimulationSource as SteamSimulationSource,
+        SimulationSourceSettings,
+    },
+};
+
+use crate::{
+    area::ListenerReverbState,
+    attenuation::{
+        AirAbsorption, AirAbsorptionOverride, AttenuationCurveAsset, AudioDirectivity,
+        DistanceAttenuation, DistanceAttenuationCurve, SourceRadius,
+    },
+    coords::bevy_position_to_phonon,
+    occlusion::Occlusion,
+    params::Snapshot,
+    pathing::{PathingConfig, PathingSource, PATHING_VISIBILITY_THRESHOLD},
+    playback::SpatialAudioSource,
+    probe::{BakedReflections, ProbeBatches, ProbeVolume},
+    reflections::{ReflectionConfig, ReflectionState},
+    settings::{AudioUnitsPerMeter, GlobalAudioSettings},
+    source::{ActiveListener, SourceOrientation, SpatialAudioSettings},
+};
+
+/// The steps the plugin hands the game state to the audio pipeline in, run in this order in
+/// `PostUpdate` after transform propagation, so every `GlobalTransform` is final.
+///
+/// Systems updating sources, like storing [`SourceParams`](crate::params::SourceParams) or
+/// changing source components, belong in [`AudioSystemSet::UpdateInputs`] to be heard in the same
+/// frame, systems reading simulation outputs after [`AudioSystemSet::RunSimulation`].
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioSystemSet {
+    /// The listener and every source hand their parameters to the voices and the simulator.
+    UpdateInputs,
+    /// Added and removed sources are committed to the simulator.
+    CommitSimulation,
+    /// Voices get the outputs of the last direct simulation, and the next direct and reflection
+    /// simulations are started.
+    RunSimulation,
+}
+
+/// The simulator's view of a [`SpatialAudioSource`], added to every playing source.
+///
+/// Registered with the simulator when added and removed from it again when the entity is
+/// despawned.
+#[derive(Component, Clone)]
+pub struct SimulationSource(Arc<SteamSimulationSource>);
+
+impl SimulationSource {
+    pub fn source(&self) -> &SteamSimulationSource {
+        &self.0
+    }
+}
+
+/// Sources waiting to be added to or removed from the simulator, which can only change while no
+/// simulation is running.
+#[derive(Resource, Default)]
+pub struct SimulationSources {
+    simulator: Option<Arc<Simulator>>,
+    registered: EntityHashMap<Arc<SteamSimulationSource>>,
+    added: Vec<Arc<SteamSimulationSource>>,
+    removed: Vec<Arc<SteamSimulationSource>>,
+    /// Committed sources with the number of direct runs started before their commit, they have
+    /// outputs once a later run finished.
+    committed: Vec<(Arc<SteamSimulationSource>, u64)>,
+}
+
+impl SimulationSources {
+    pub fn len(&self) -> usize {
+        self.registered.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registered.is_empty()
+    }
+
+    /// Whether `source` is still waiting to be added to the simulator, or to be simulated for the
+    /// first time.
+    fn is_pending(&self, source: &SimulationSource) -> bool {
+        self.added.iter().any(|added| Arc::ptr_eq(added, &source.0))
+            || self
+                .committed
+                .iter()
+                .any(|(committed, _)| Arc::ptr_eq(committed, &source.0))
+    }
+}
+
+/// Direct simulations per second, independent of the frame rate. Slow moving sources rarely
+/// need 60, halving it halves the occlusion rays traced.
+///
+/// Voices ease between the outputs of consecutive simulations, so low rates don't step. The
+/// reflection rate is set separately by [`ReflectionConfig::update_hz`].
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct SimulationTickRate(pub f32);
+
+impl Default for SimulationTickRate {
+    fn default() -> Self {
+        Self(60.0)
+    }
+}
+
+/// Fraction of the [`SimulationTickRate`] interval a tick may come early by.
+const TICK_TOLERANCE: f32 = 1e-3;
+
+/// The direct simulation task, run on the [`AsyncComputeTaskPool`] so occlusion rays never hold
+/// up the frame.
+///
+/// Voices get the outputs of the run started the tick before.
+#[derive(Resource, Default)]
+pub struct DirectSimulationState {
+    task: Option<Task<()>>,
+    started: u64,
+    finished: u64,
+    /// Seconds accumulated towards the next [`SimulationTickRate`] tick.
+    since_tick: f32,
+    /// Seconds since the outputs were last read, for smoothing occlusion.
+    since_read: f32,
+}
+
+impl DirectSimulationState {
+    /// Whether a simulation is running, the simulator mustn't be committed until it's done.
+    pub(crate) fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    /// Whether a tick at `rate` is due after `delta` more seconds. Ticks are only consumed by
+    /// [`Self::consume_tick`], so one missed while a run is still going isn't lost.
+    fn tick_due(&mut self, delta: f32, rate: f32) -> bool {
+        self.since_tick += delta;
+        self.since_read += delta;
+        // Frame times don't add up to the interval exactly, a tick that's due but for rounding
+        // isn't put off to the next frame.
+        self.since_tick >= Self::interval(rate) * (1.0 - TICK_TOLERANCE)
+    }
+
+    fn consume_tick(&mut self, rate: f32) {
+        let interval = Self::interval(rate);
+        // Frames slower than the tick rate run every frame without building up a backlog.
+        self.since_tick = (self.since_tick - interval).min(interval);
+        self.since_read = 0.0;
+    }
+
+    fn interval(rate: f32) -> f32 {
+        1.0 / rate.max(f32::EPSILON)
+    }
+}
+
+/// The per-block gains of the direct path, simulated on the game thread and picked up by the
+/// decoder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DirectOutputs {
+    pub(crate) distance_attenuation: f32,
+    pub(crate) air_absorption: [f32; 3],
+    pub(crate) directivity: f32,
+    /// Scales the binaural spatial blend, below 1.0 while the listener is inside a
+    /// [`SourceRadius`].
+    pub(crate) directionality: f32,
+    /// Fraction of the source visible to the listener, smoothed by its [`Occlusion`].
+    pub(crate) occlusion: f32,
+}
+
+impl Default for DirectOutputs {
+    fn default() -> Self {
+        Self {
+            distance_attenuation: 1.0,
+            air_absorption: [1.0; 3],
+            directivity: 1.0,
+            directionality: 1.0,
+            occlusion: 1.0,
+        }
+    }
+}
+
+impl DirectOutputs {
+    /// Linearly interpolates every output towards `to`.
+    pub(crate) fn lerp(&self, to: &Self, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            distance_attenuation: lerp(self.distance_attenuation, to.distance_attenuation),
+            air_absorption: [0, 1, 2]
+                .map(|band| lerp(self.air_absorption[band], to.air_absorption[band])),
+            directivity: lerp(self.directivity, to.directivity),
+            directionality: lerp(self.directionality, to.directionality),
+            occlusion: lerp(self.occlusion, to.occlusion),
+        }
+    }
+
+    /// The outputs of an unoccluded source, calculated from the models without the simulator.
+    pub(crate) fn from_models(
+        context: &Context,
+        air_absorption: &AirAbsorptionModel,
+        source: SourceOrientation,
+        directivity: AudioDirectivity,
+        listener: Vec3,
+        units: AudioUnitsPerMeter,
+    ) -> Self {
+        Self {
+            distance_attenuation: DistanceAttenuationModel::default().calculate(
+                context,
+                bevy_position_to_phonon(source.origin, units),
+                bevy_position_to_phonon(listener, units),
+            ),
+            air_absorption: air_absorption.calculate(
+                context,
+                bevy_position_to_phonon(source.origin, units),
+                bevy_position_to_phonon(listener, units),
+            ),
+            directivity: Directivity::from(directivity).calculate(
+                context,
+                source.to_phonon(units),
+                bevy_position_to_phonon(listener, units),
+            ),
+            directionality: 1.0,
+            occlusion: 1.0,
+        }
+    }
+}
+
+impl Snapshot for DirectOutputs {
+    const WORDS: usize = 7;
+
+    fn write_words(&self, words: &mut [f32]) {
+        words[0] = self.distance_attenuation;
+        self.air_absorption.write_words(&mut words[1..4]);
+        words[4..].copy_from_slice(&[self.directivity, self.directionality, self.occlusion]);
+    }
+
+    fn read_words(words: &[f32]) -> Self {
+        Self {
+            distance_attenuation: words[0],
+            air_absorption: <[f32; 3]>::read_words(&words[1..4]),
+            directivity: words[4],
+            directionality: words[5],
+            occlusion: words[6],
+        }
+    }
+}
+
+pub fn add_simulation_sources(
+    mut commands: Commands,
+    mut sources: ResMut<SimulationSources>,
+    settings: Res<SpatialAudioSettings>,
+    query: Query<Entity, (With<SpatialAudioSource>, Without<SimulationSource>)>,
+    registered: Query<Entity, With<SimulationSource>>,
+) {
+    let Some(simulator) = &settings.simulator else {
+        return;
+    };
+    // Sources belong to the simulator they were created with, start over after a rebuild.
+    if !sources
+        .simulator
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, simulator))
+    {
+        sources.simulator = Some(simulator.clone());
+        sources.registered.clear();
+        sources.added.clear();
+        sources.removed.clear();
+        sources.committed.clear();
+        for entity in registered.iter() {
+            commands.entity(entity).remove::<SimulationSource>();
+        }
+        return;
+    }
+
+    let source_settings = SimulationSourceSettings {
+        flags: settings.simulation_settings.flags,
+    };
+    for entity in query.iter() {
+        match SteamSimulationSource::new(simulator, &source_settings) {
+            Ok(source) => {
+                let source = Arc::new(source);
+                sources.registered.insert(entity, source.clone());
+                sources.added.push(source.clone());
+                commands.entity(entity).insert(SimulationSource(source));
+            }
+            Err(err) => warn!("Could not add simulation source for {entity:?}: {err:?}"),
+        }
+    }
+}
+
+pub fn cleanup_simulation_sources(
+    mut sources: ResMut<SimulationSources>,
+    mut removed: RemovedComponents<SimulationSource>,
+) {
+    for entity in removed.read() {
+        let Some(source) = sources.registered.remove(&entity) else {
+            continue;
+        };
+
+        // Never made it into the simulator, nothing to remove.
+        let pending = sources.added.len();
+        sources.added.retain(|added| !Arc::ptr_eq(added, &source));
+        if sources.added.len() == pending {
+            sources.removed.push(source);
+        }
+    }
+}
+
+/// Whether the [`SpatialAudioPlugin`](crate::source::SpatialAudioPlugin) built a simulator, the
+/// systems feeding it are skipped otherwise.
+pub fn simulation_enabled(settings: Res<SpatialAudioSettings>) -> bool {
+    settings.simulator.is_some()
+}
+
+/// Writes the transform of every source to the simulator.
+///
+/// [`BakedReflections`] sources inside a baked [`ProbeVolume`] use its reflections instead of
+/// tracing their own, every other source uses the reverb of the listener's [`AudioArea`].
+///
+/// [`AudioArea`]: crate::area::AudioArea
+#[allow(clippy::too_many_arguments)]
+pub fn update_simulation_inputs(
+    reflections: Res<ReflectionConfig>,
+    pathing: Res<PathingConfig>,
+    global: Res<GlobalAudioSettings>,
+    units: Res<AudioUnitsPerMeter>,
+    reverb: Res<ListenerReverbState>,
+    batches: Res<ProbeBatches>,
+    volumes: Query<(Entity, &ProbeVolume, &GlobalTransform)>,
+    query: Query<(
+        &SimulationSource,
+        &SpatialAudioSource,
+        &GlobalTransform,
+        Has<BakedReflections>,
+        Has<PathingSource>,
+        Option<&SourceRadius>,
+        Option<&Occlusion>,
+        Option<&AudioDirectivity>,
+        Option<&AirAbsorption>,
+    )>,
+) {
+    let mut base_flags = SimulationFlags::DIRECT;
+    if reflections.enabled {
+        base_flags |= SimulationFlags::REFLECTIONS;
+    }
+
+    // Listener-centric, so every source shares the reverb of the area the listener is in.
+    let area_reverb = reverb
+        .dominant()
+        .and_then(|area| batches.identifier(area, *units))
+        .filter(|_| reflections.enabled);
+
+    for (
+        source,
+        voice_source,
+        transform,
+        use_baked,
+        pathed,
+        radius,
+        occlusion,
+        directivity,
+        air_absorption,
+    ) in query.iter()
+    {
+        let position = transform.translation();
+        let volume = volumes
+            .iter()
+            .filter(|(_, volume, volume_transform)| volume.contains(volume_transform, position))
+            .find_map(|(entity, ..)| batches.identifier(entity, *units).map(|_| entity));
+        let baked = volume
+            .filter(|_| use_baked)
+            .and_then(|entity| batches.identifier(entity, *units))
+            .or(area_reverb);
+        // Paths are only found between the probes of the volume the source is in.
+        let pathing_probes = volume
+            .filter(|_| pathing.enabled && pathed)
+            .and_then(|entity| batches.batch(entity));
+
+        let mut flags = base_flags;
+        if pathing_probes.is_some() {
+            flags |= SimulationFlags::PATHING;
+        }
+
+        let mut inputs = SimulationInputs {
+            flags,
+            direct_flags: DirectSimulationFlags::DISTANCE_ATTENUATION
+                | DirectSimulationFlags::AIR_ABSORPTION
+                | DirectSimulationFlags::DIRECTIVITY
+                | DirectSimulationFlags::OCCLUSION,
+            source: SourceOrientation::from(transform).to_phonon(*units),
+            distance_attenuation_model: DistanceAttenuationModel::default(),
+            air_absorption_model: air_absorption.copied().unwrap_or_default().model(&global),
+            directivity: directivity.copied().unwrap_or_default().into(),
+            ..Default::default()
+        };
+        // Volumetric occlusion tests the whole sphere of the source instead of its centre.
+        occlusion.copied().unwrap_or_default().apply(
+            &mut inputs,
+            radius.map_or(0.0, |radius| radius.0.max(0.0)),
+            *units,
+        );
+        if let Some(identifier) = baked {
+            inputs.baked = true;
+            inputs.baked_data_identifier = identifier;
+        }
+        if let Some(probes) = pathing_probes {
+            inputs.pathing_probes = Some(probes);
+            inputs.pathing_order = pathing.order;
+            inputs.visibility_radius = pathing.visibility_radius;
+            inputs.visibility_threshold = PATHING_VISIBILITY_THRESHOLD;
+            inputs.visibility_range = pathing.visibility_range;
+            inputs.find_alternate_paths = true;
+        }
+        // Paused voices keep their inputs but are left out of every simulation.
+        if voice_source.voice.is_paused() {
+            inputs.flags = SimulationFlags::empty();
+        }
+        source.0.set_inputs(flags, &inputs);
+    }
+}
+
+/// Applies pending additions and removals, once no simulation is running.
+pub fn commit_simulation_sources(
+    mut sources: ResMut<SimulationSources>,
+    settings: Res<SpatialAudioSettings>,
+    reflections: Res<ReflectionState>,
+    direct: Res<DirectSimulationState>,
+) {
+    let Some(simulator) = &settings.simulator else {
+        return;
+    };
+    if reflections.is_running()
+        || direct.is_running()
+        || (sources.added.is_empty() && sources.removed.is_empty())
+    {
+        return;
+    }
+
+    for source in sources.removed.drain(..) {
+        simulator.remove_source(&source);
+    }
+    let added: Vec<_> = sources.added.drain(..).collect();
+    for source in added {
+        simulator.add_source(&source);
+        sources.committed.push((source, direct.started));
+    }
+    simulator.commit();
+}
+
+/// Hands every voice the outputs of the last direct simulation and starts the next one, once
+/// per [`SimulationTickRate`] tick.
+///
+/// Sources the simulator hasn't simulated yet are calculated from the same models directly
+/// every frame, so a voice never starts on unattenuated defaults.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_direct(
+    mut state: ResMut<DirectSimulationState>,
+    tick_rate: Res<SimulationTickRate>,
+    settings: Res<SpatialAudioSettings>,
+    global: Res<GlobalAudioSettings>,
+    units: Res<AudioUnitsPerMeter>,
+    mut sources: ResMut<SimulationSources>,
+    listener: Res<ActiveListener>,
+    query: Query<(
+        &SpatialAudioSource,
+        &GlobalTransform,
+        Option<&SimulationSource>,
+        Option<&DistanceAttenuation>,
+        Option<&DistanceAttenuationCurve>,
+        Option<&AttenuationCurveAsset>,
+        Option<&SourceRadius>,
+        Option<&Occlusion>,
+        Option<&AudioDirectivity>,
+        Option<&AirAbsorption>,
+        Option<&AirAbsorptionOverride>,
+    )>,
+    curves: Res<Assets<DistanceAttenuationCurve>>,
+    time: Res<Time>,
+) {
+    if tick_rate.is_changed() {
+        settings.simulation_tick_rate.store(tick_rate.0);
+    }
+
+    if listener.entity.is_none() {
+        return;
+    }
+    let listener = listener.orientation.origin;
+
+    // Outputs are only read in between runs, while one is still going voices keep the last
+    // outputs of their simulated sources.
+    let idle = !state.is_running();
+    let tick = state.tick_due(time.delta_secs(), tick_rate.0) && idle;
+    let since_read = state.since_read;
+    if idle && state.task.take().is_some() {
+        state.finished = state.started;
+        let finished = state.finished;
+        sources
+            .committed
+            .retain(|(_, started_before)| *started_before >= finished);
+    }
+
+    for (
+        source,
+        transform,
+        simulation_source,
+        attenuation,
+        curve,
+        curve_asset,
+        radius,
+        occlusion,
+        directivity,
+        air_absorption,
+        absorption_override,
+    ) in query.iter()
+    {
+        if source.voice.is_paused() {
+            continue;
+        }
+
+        let position = transform.translation();
+        let mut outputs = match simulation_source {
+            Some(simulation_source) if !sources.is_pending(simulation_source) => {
+                if !tick {
+                    continue;
+                }
+
+                let direct = simulation_source
+                    .source()
+                    .get_outputs(SimulationFlags::DIRECT)
+                    .direct;
+                let previous = source.voice.direct.load().occlusion;
+                DirectOutputs {
+                    distance_attenuation: direct.distance_attenuation,
+                    air_absorption: direct.air_absorption,
+                    directivity: direct.directivity,
+                    directionality: 1.0,
+                    // A single ray flips between occluded and not, ease between the two.
+                    occlusion: occlusion.copied().unwrap_or_default().smooth(
+                        previous,
+                        direct.occlusion,
+                        since_read,
+                    ),
+                }
+            }
+            _ => DirectOutputs::from_models(
+                &settings.context,
+                &air_absorption.copied().unwrap_or_default().model(&global),
+                SourceOrientation::from(transform),
+                directivity.copied().unwrap_or_default(),
+                listener,
+                *units,
+            ),
+        };
+
+        let mut distance = position.distance(listener);
+        if let Some(radius) = radius.filter(|radius| radius.0 > 0.0) {
+            // Attenuated from the point of the surface closest to the listener, which never gets
+            // closer than the model's minimum distance.
+            let surface_distance = radius.effective_distance(distance);
+            let surface = listener + (position - listener).normalize_or_zero() * surface_distance;
+            outputs.distance_attenuation = DistanceAttenuationModel::default().calculate(
+                &settings.context,
+                bevy_position_to_phonon(surface, *units),
+                bevy_position_to_phonon(listener, *units),
+            );
+            outputs.directionality = radius.directionality(distance);
+            distance = surface_distance;
+        }
+
+        // An inline curve wins over a curve asset, which wins over the attenuation model.
+        let curve = curve.or_else(|| curve_asset.and_then(|asset| curves.get(&asset.0)));
+        let gain = match curve {
+            Some(curve) => curve.gain(distance),
+            None => attenuation.copied().unwrap_or_default().gain(distance),
+        };
+        if let Some(gain) = gain {
+            outputs.distance_attenuation = gain;
+        }
+        if let Some(absorption_override) = absorption_override {
+            outputs.air_absorption = absorption_override.gains();
+        }
+
+        source.voice.direct.store(outputs);
+    }
+
+    let Some(simulator) = settings.simulator.clone() else {
+        return;
+    };
+    if !tick {
+        return;
+    }
+    state.consume_tick(tick_rate.0);
+    if sources.is_empty() {
+        return;
+    }
+
+    // Uses the inputs set this frame, its outputs are picked up next tick.
+    let stats = settings.stats.clone();
+    state.started += 1;
+    state.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        let started = Instant::now();
+        simulator.run_direct();
+        stats.record_simulation(started.elapsed());
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ticks at `rate` over the frames at `frame_rate` it takes for `seconds` to pass.
+    fn ticks(rate: f32, frame_rate: f32, seconds: f32) -> u32 {
+        let mut state = DirectSimulationState::default();
+        let frames = (seconds * frame_rate).ceil() as u32;
+        (0..frames)
+            .filter(|_| {
+                let due = state.tick_due(1.0 / frame_rate, rate);
+                if due {
+                    state.consume_tick(rate);
+                }
+                due
+            })
+            .count() as u32
+    }
+
+    #[test]
+    fn ticks_follow_the_rate_whatever_the_frame_rate() {
+        for rate in [10.0, 20.0, 30.0, 60.0] {
+            for frame_rate in [60.0, 75.0, 120.0, 144.0, 165.0, 240.0] {
+                for ticks_expected in [1, 7, 30, 60] {
+                    let seconds = ticks_expected as f32 / rate;
+                    assert_eq!(
+                        ticks(rate, frame_rate, seconds),
+                        ticks_expected,
+                        "{ticks_expected} ticks at {rate} Hz, {frame_rate} frames per second"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slow_frames_tick_every_frame_without_a_backlog() {
+        assert_eq!(ticks(60.0, 30.0, 1.0), 30);
+        // Back at full speed the rate holds again, the slow frames aren't caught up on.
+        let mut state = DirectSimulationState::default();
+        for _ in 0..30 {
+            assert!(state.tick_due(1.0 / 30.0, 60.0));
+            state.consume_tick(60.0);
+        }
+        let caught_up = (0..120)
+            .filter(|_| {
+                let due = state.tick_due(1.0 / 120.0, 60.0);
+                if due {
+                    state.consume_tick(60.0);
+                }
+                due
+            })
+            .count();
+        assert!((60..=61).contains(&caught_up), "{caught_up} ticks");
+    }
+}
+
+#[cfg(all(test, feature = "native-tests"))]
+mod native_tests {
+    use bevy::{ecs::system::RunSystemOnce, prelude::World};
+    use steam_audio::{
+        hrtf::AudioSettings,
+        prelude::{ContextSettings, SimulationSettings},
+    };
+
+    use super::*;
+
+    /// A world with one [`SimulationSource`], registered and committed unless `pending`.
+    fn world_with_source(pending: bool) -> (World, Entity) {
+        let context = Context::new(&ContextSettings::default()).unwrap();
+        let settings = SimulationSettings::from_audio_settings(&AudioSettings::default());
+        let simulator = Simulator::new(&context, &settings).unwrap();
+        let source = Arc::new(
+            SteamSimulationSource::new(
+                &simulator,
+                &SimulationSourceSettings {
+                    flags: settings.flags,
+                },
+            )
+            .unwrap(),
+        );
+
+        let mut world = World::new();
+        let entity = world.spawn(SimulationSource(source.clone())).id();
+        let mut sources = SimulationSources::default();
+        sources.registered.insert(entity, source.clone());
+        if pending {
+            sources.added.push(source);
+        } else {
+            sources.committed.push((source, 0));
+        }
+        world.insert_resource(sources);
+        (world, entity)
+    }
+
+    #[test]
+    fn despawned_sources_are_removed_once() {
+        let (mut world, entity) = world_with_source(false);
+        world.despawn(entity);
+
+        // A second reader sees the same despawn again.
+        world.run_system_once(cleanup_simulation_sources).unwrap();
+        world.run_system_once(cleanup_simulation_sources).unwrap();
+
+        let sources = world.resource::<SimulationSources>();
+        assert_eq!(sources.removed.len(), 1);
+        assert!(sources.is_empty());
+    }
+
+    #[test]
+    fn uncommitted_sources_are_never_removed() {
+        let (mut world, entity) = world_with_source(true);
+        world.despawn(entity);
+        world.run_system_once(cleanup_simulation_sources).unwrap();
+
+        let sources = world.resource::<SimulationSources>();
+        assert!(sources.removed.is_empty());
+        assert!(sources.added.is_empty());
+        assert!(sources.is_empty());
+    }
+}
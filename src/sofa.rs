@@ -0,0 +1,73 @@
+use bevy::{
+    asset::{io::Reader, Asset, AssetEvent, AssetLoader, Assets, Handle, LoadContext},
+    prelude::{EventReader, Reflect, ReflectResource, Res, ResMut, Resource},
+    reflect::TypePath,
+};
+use steam_audio::hrtf::HRTFSettings;
+
+use crate::settings::HrtfConfig;
+
+/// The raw contents of a `.sofa` HRTF file loaded through the asset server.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct SofaHrtf {
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct SofaHrtfLoader;
+
+impl AssetLoader for SofaHrtfLoader {
+    type Asset = SofaHrtf;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        Ok(SofaHrtf { data })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sofa"]
+    }
+}
+
+/// The [`SofaHrtf`] used for binaural rendering once it has loaded, until then the current HRTF
+/// keeps playing.
+///
+/// Replace it at runtime to switch to another player's personalized HRTF.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct HrtfAsset(pub Handle<SofaHrtf>);
+
+/// Points the [`HrtfConfig`] at the [`HrtfAsset`] whenever it changes or (re)loads.
+pub fn apply_sofa_hrtf(
+    hrtf_asset: Option<Res<HrtfAsset>>,
+    sofa_assets: Res<Assets<SofaHrtf>>,
+    mut events: EventReader<AssetEvent<SofaHrtf>>,
+    mut hrtf_config: ResMut<HrtfConfig>,
+) {
+    let Some(hrtf_asset) = hrtf_asset else {
+        events.clear();
+        return;
+    };
+
+    let reloaded = events.read().any(|event| {
+        event.is_loaded_with_dependencies(&hrtf_asset.0) || event.is_modified(&hrtf_asset.0)
+    });
+    if !hrtf_asset.is_changed() && !reloaded {
+        return;
+    }
+
+    // Not loaded yet, the load event brings us back here.
+    let Some(sofa) = sofa_assets.get(&hrtf_asset.0) else {
+        return;
+    };
+
+    hrtf_config.0 = HRTFSettings::sofa_data(&sofa.data);
+}
@@ -1,31 +1,112 @@
 use bevy::{
-    app::{App, Plugin},
-    asset::Asset,
-    audio::Decodable,
-    math::{Dir3, Vec3},
-    prelude::{Component, GlobalTransform, Mesh, Query, Res, Resource, With},
+    app::{App, Plugin, PostUpdate, PreUpdate, Startup},
+    asset::{
+        io::{file::FileAssetReader, Reader},
+        Asset, AssetApp, AssetLoader, AssetPlugin, AssetServer, LoadContext,
+    },
+    audio::{AddAudioSource, Decodable},
+    log::warn,
+    math::Vec3,
+    prelude::{
+        Commands, Component, Entity, Event, GlobalTransform, IntoSystemConfigs,
+        IntoSystemSetConfigs, Local, Query, Reflect, ReflectComponent, Res, ResMut, Resource, With,
+    },
     reflect::TypePath,
+    time::Time,
+    transform::TransformSystem,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc},
+    time::Instant,
 };
-use std::sync::{Arc, Mutex};
 
 use bevy::audio::Source;
 use bevy::utils::Duration;
 
 use steam_audio::{
-    hrtf::{AudioSettings, HRTFInterpolation, HRTFSettings, HRTF},
+    hrtf::{AudioSettings, HRTFSettings, HRTF},
     prelude::{
         BinauralEffect, BinauralParams, Context, ContextSettings, DeinterleavedFrame, DirectEffect,
-        DirectEffectFlags, DirectEffectParams, DistanceAttenuationModel, SimulationFlags,
-        SimulationSettings, SimulationSharedInputs, Simulator,
+        DirectEffectFlags, DirectEffectParams, PanningEffect, PanningEffectParams,
+        PathEffectParams, ReflectionEffectParams, SimulationFlags, SimulationSettings,
+        SimulationSharedInputs, Simulator, TransmissionType,
     },
-    simulation::source::{AirAbsorptionModel, Directivity},
     Orientation,
 };
 
-use bevy::render::{
-    mesh::{Indices, VertexAttributeValues},
-    render_resource::PrimitiveTopology,
+use crate::ambisonics::{
+    spawn_ambisonics_bed, AmbisonicsBed, AmbisonicsBus, AmbisonicsConfig, AmbisonicsPipeline,
+};
+use crate::area::{update_listener_reverb, ListenerReverbState};
+use crate::attenuation::{
+    AudioDirectivity, DistanceAttenuationCurve, DistanceAttenuationCurveLoader,
+};
+use crate::binaural::{
+    update_binaural_config, update_near_field, BinauralConfig, NearFieldCorrection,
+};
+use crate::chain::{StageChain, StageParams};
+use crate::convolution::{
+    update_convolution_reverb, ConvolutionReverbIR, ConvolutionReverbIRLoader, Convolver,
+};
+#[cfg(feature = "extended-inputs")]
+use crate::coords::bevy_velocity_to_phonon;
+use crate::coords::{bevy_position_to_phonon, bevy_to_phonon};
+use crate::culling::update_audible;
+use crate::diagnostics::AudioStats;
+use crate::doppler::{
+    update_doppler, AudioVelocity, DopplerConfig, ListenerVelocity, VelocitySmoothingFactor,
+};
+use crate::eq::{update_headphone_eq, HeadphoneEqFilter, HeadphoneEqPreset};
+use crate::geometry::{
+    commit_audio_scene, move_dynamic_geometry, rebuild_audio_scene, register_audio_obstacles,
+    register_dynamic_geometry, remove_audio_obstacles, remove_dynamic_geometry, AudioSceneRebuilt,
+    AudioSceneState, SteamAudioScene,
+};
+use crate::material::MaterialLibrary;
+use crate::mix::{
+    update_mixer, update_source_mix, update_spatial_blend, MixerRamp, SourceMix, SteamAudioMixer,
+};
+use crate::opencl::{OpenClConfig, TrueAudioNext};
+use crate::output::{update_output_mode, OutputMode};
+use crate::params::{SharedParams, SourceParams};
+use crate::pathing::{PathPipeline, PathingConfig};
+use crate::pitch::update_pitch;
+use crate::playback::{
+    pause_voices, playback_events, queue_voices, reload_voices, seek_voices,
+    update_playback_position, update_source_params, AudioErrorPolicy, SpatialAudioError,
+    SpatialPlaybackFinished, SpatialPlaybackStarted, TailBlocks, VoiceState, WarmupBlocks,
+};
+use crate::pool::{fill_decoder_pool, AudioSourcePool, DecoderPool, PooledAudio, PooledSettings};
+use crate::portal::{update_door_portals, update_portal_geometry};
+use crate::probe::{
+    bake_probe_volumes, load_baked_data, BakeFinished, BakeProgress, BakeReflections,
+    BakeReflectionsTask, BakedDataAsset, BakedDataLoader, ProbeBatches,
+};
+use crate::ray_tracer::{
+    GpuSimulationAvailable, RayTracer, RayTracerBackend, RayTracerFallback, SteamAudioInfo,
+};
+use crate::reflections::{
+    simulate_reflections, validate_reflection_config, ReflectionConfig, ReflectionPipeline,
+    ReflectionState,
+};
+use crate::samples::{AudioData, SampleProvider};
+use crate::scene::{extract_audio_scene, AudioSceneMesh};
+use crate::settings::{
+    apply_environment_preset, context_update, hrtf_update, simulation_update,
+    update_units_per_meter, AudioConfig, AudioUnitsPerMeter, ContextConfig, FrameSize,
+    GlobalAudioSettings, HrtfConfig, SharedHrtf, SimulationConfig,
 };
+use crate::simulation::{
+    add_simulation_sources, cleanup_simulation_sources, commit_simulation_sources, simulate_direct,
+    simulation_enabled, update_simulation_inputs, AudioSystemSet, DirectOutputs,
+    DirectSimulationState, SimulationSources, SimulationTickRate,
+};
+use crate::sofa::{apply_sofa_hrtf, HrtfAsset, SofaHrtf, SofaHrtfLoader};
+use crate::terrain::{update_audio_terrain, AudioTerrain};
+use crate::transmission::{update_transmission, TransmissionConfig};
+use crate::virtual_voice::{limit_voices, MaxVoices, VirtualVoiceThreshold, VoiceCounts};
+use crate::volume::{update_bus_volumes, update_fade_in, update_volume_scale, BusVolumes};
 
 // This struct usually contains the data for the audio being played.
 // This is where data read from an audio file would be stored, for example.
@@ -33,213 +114,1311 @@ use bevy::render::{
 // This allows the type to be registered as an asset.
 #[derive(TypePath, Asset)]
 pub struct SteamAudio {
-    pub path: String,
-    pub direction: Arc<Mutex<Vec3>>,
-    pub source_position: Arc<Mutex<Vec3>>,
-    pub listener_position: Arc<Mutex<Vec3>>,
+    pub data: AudioData,
+    /// Set on the copy [`queue_voices`] makes for each `AudioPlayer<SteamAudio>` entity.
+    pub(crate) voice: Option<Arc<VoiceState>>,
+}
+
+/// Tail blocks whose samples all stay below this end the voice.
+const TAIL_SILENCE: f32 = 1e-4;
+
+/// The folder Bevy's `AssetPlugin` reads from by default.
+pub(crate) const ASSET_FOLDER: &str = "assets";
+
+impl SteamAudio {
+    /// A sound at a Bevy asset path like `"sounds/explosion.ogg"`, inside the `assets` folder.
+    pub fn from_asset_path(path: &str) -> Self {
+        Self::new(AudioData::File(
+            asset_file_path(ASSET_FOLDER, path)
+                .to_string_lossy()
+                .into_owned(),
+        ))
+    }
+
+    /// Interleaved samples in memory, like a decoded buffer or a text-to-speech line.
+    pub fn from_samples(samples: impl Into<Arc<[f32]>>, sample_rate: u32, channels: u16) -> Self {
+        Self::new(AudioData::Samples {
+            samples: samples.into(),
+            sample_rate,
+            channels,
+        })
+    }
+
+    /// A source built by `factory` for every voice playing it, and again when a voice seeks
+    /// backwards.
+    pub fn from_source(
+        factory: impl Fn() -> Box<dyn rodio::Source<Item = f32> + Send> + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(AudioData::Source(Arc::new(factory)))
+    }
+
+    /// An endless sine tone at `frequency` Hz, handy for testing the spatialization.
+    pub fn sine(frequency: f32) -> Self {
+        Self::from_source(move || Box::new(rodio::source::SineWave::new(frequency)))
+    }
+
+    fn new(data: AudioData) -> Self {
+        Self { data, voice: None }
+    }
+}
+
+/// Where `path` in the asset folder `folder` is on disk, resolved like Bevy's file asset reader.
+pub(crate) fn asset_file_path(folder: &str, path: impl AsRef<Path>) -> PathBuf {
+    FileAssetReader::get_base_path().join(folder).join(path)
+}
+
+/// Loads [`SteamAudio`] from the asset folder, `asset_server.load::<SteamAudio>(path)`.
+///
+/// The decoder streams the file from disk once it plays, the loader only records where it is.
+pub struct SteamAudioLoader {
+    asset_folder: String,
+}
+
+impl AssetLoader for SteamAudioLoader {
+    type Asset = SteamAudio;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let path = asset_file_path(&self.asset_folder, load_context.path());
+        // Fail the load instead of the audio thread.
+        rodio::Decoder::new(std::fs::File::open(&path)?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+        Ok(SteamAudio::new(AudioData::File(
+            path.to_string_lossy().into_owned(),
+        )))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ogg", "oga", "wav", "flac", "mp3"]
+    }
+}
+
+/// Snapshot of a source's basis, used to aim its directivity pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceOrientation {
+    pub origin: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub ahead: Vec3,
+}
+
+impl Default for SourceOrientation {
+    fn default() -> Self {
+        Self {
+            origin: Vec3::ZERO,
+            right: Vec3::X,
+            up: Vec3::Y,
+            ahead: Vec3::NEG_Z,
+        }
+    }
+}
+
+impl From<&GlobalTransform> for SourceOrientation {
+    fn from(transform: &GlobalTransform) -> Self {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        Self {
+            origin: translation,
+            right: rotation * Vec3::X,
+            up: rotation * Vec3::Y,
+            ahead: rotation * Vec3::NEG_Z,
+        }
+    }
+}
+
+impl SourceOrientation {
+    /// Direction from the origin to `position` in this orientation's own space, see
+    /// [`listener_direction`](crate::coords::listener_direction).
+    pub fn local_direction(&self, position: Vec3) -> Vec3 {
+        let offset = position - self.origin;
+        Vec3::new(
+            offset.dot(self.right),
+            offset.dot(self.up),
+            -offset.dot(self.ahead),
+        )
+        .normalize_or_zero()
+    }
+
+    /// Moves `t` of the way towards `other`, keeping the axes unit length.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let axis = |from: Vec3, to: Vec3| from.lerp(to, t).try_normalize().unwrap_or(to);
+        Self {
+            origin: self.origin.lerp(other.origin, t),
+            right: axis(self.right, other.right),
+            up: axis(self.up, other.up),
+            ahead: axis(self.ahead, other.ahead),
+        }
+    }
+}
+
+impl SourceOrientation {
+    /// The orientation in Steam Audio's space, with the origin in meters.
+    pub fn to_phonon(&self, units: AudioUnitsPerMeter) -> Orientation {
+        Orientation {
+            origin: bevy_position_to_phonon(self.origin, units),
+            ..Orientation::from(*self)
+        }
+    }
+}
+
+/// The axes of an orientation, for the effects that rotate into the listener's space. The origin
+/// is left in Bevy units, simulation inputs go through [`SourceOrientation::to_phonon`].
+impl From<SourceOrientation> for Orientation {
+    fn from(orientation: SourceOrientation) -> Self {
+        Orientation {
+            origin: bevy_to_phonon(orientation.origin),
+            right: bevy_to_phonon(orientation.right),
+            up: bevy_to_phonon(orientation.up),
+            ahead: bevy_to_phonon(orientation.ahead),
+        }
+    }
+}
+
+/// Frames the effects render into, kept from block to block and only rebuilt when the output
+/// mode or the frame size changes.
+struct BlockFrames {
+    frame_size: usize,
+    output_mode: OutputMode,
+    /// Copies of the input block for the effects that consume theirs.
+    mono: DeinterleavedFrame,
+    /// The binaural, panning or Ambisonics output, one channel per output channel.
+    output: DeinterleavedFrame,
+    /// The outgoing HRTF's output while crossfading an HRTF swap.
+    previous_binaural: DeinterleavedFrame,
+    near_field: DeinterleavedFrame,
+    pathing: DeinterleavedFrame,
+}
+
+impl BlockFrames {
+    fn new(audio_settings: &AudioSettings, output_mode: OutputMode) -> Self {
+        let frame_size = audio_settings.frame_size() as usize;
+        let frame = |channels| {
+            DeinterleavedFrame::new(frame_size, channels, audio_settings.sampling_rate())
+        };
+        Self {
+            frame_size,
+            output_mode,
+            mono: frame(1),
+            output: frame(output_mode.channels() as usize),
+            previous_binaural: frame(2),
+            near_field: frame(2),
+            pathing: frame(2),
+        }
+    }
+
+    /// Rebuilds the frames if they don't fit `audio_settings` and `output_mode` anymore.
+    fn fit(&mut self, audio_settings: &AudioSettings, output_mode: OutputMode) {
+        if self.frame_size != audio_settings.frame_size() as usize
+            || self.output_mode != output_mode
+        {
+            *self = Self::new(audio_settings, output_mode);
+        }
+    }
 }
 
 // This decoder is responsible for playing the audio,
 // and so stores data about the audio being played.
 pub struct SteamDecoder {
     // Reader
-    decoder: rodio::Decoder<std::fs::File>,
+    decoder: SampleProvider,
+    data: AudioData,
     sample_rate: u32,
-    current_channel: bool,
-    current_block_offset: u32,
-    current_block1: Vec<f32>,
-    current_block2: Vec<f32>,
+    current_channel: usize,
+    current_block_offset: usize,
+    /// One block per output channel.
+    current_blocks: Vec<Vec<f32>>,
+    /// The mono block read from the source. The direct effect takes its input by value and
+    /// renders into a new frame that takes its place, the only frame built every block.
+    input_buffer: DeinterleavedFrame,
+    frames: BlockFrames,
+    /// Set once the voice has stopped or drained, `next` returns `None` from then on.
+    ended: bool,
+    /// The mode the effects are built for, `panning` is set for [`OutputMode::Panning`].
+    output_mode: OutputMode,
+    panning: Option<PanningEffect>,
     binaural_params: BinauralParams,
-    binaural_effect: BinauralEffect,
+    /// The [`BinauralConfig`] spatial blend, scaled by `spatial_blend` before each block.
+    binaural_blend: f32,
+    /// The [`SpatialBlend`](crate::mix::SpatialBlend) eased towards the voice's over a few
+    /// blocks.
+    spatial_blend: f32,
+    /// The effect and HRTF replaced at the last HRTF swap, crossfaded out over one block.
+    previous_binaural: Option<(BinauralEffect, HRTF)>,
+    /// Renders the [`NearFieldCorrection`], built the first time the source comes close enough.
+    near_field: Option<BinauralEffect>,
+    /// Replaces the binaural stage for sources with an
+    /// [`AmbisonicsOrder`](crate::ambisonics::AmbisonicsOrder).
+    ambisonics: Option<AmbisonicsPipeline>,
+    /// Replaces the direct, binaural and Ambisonics stages for sources with an
+    /// [`EffectChain`](crate::chain::EffectChain).
+    chain: Option<StageChain>,
+    /// Set for voices started while [`ReflectionConfig::enabled`] was on.
+    reflections: Option<ReflectionPipeline>,
+    /// The latest reflection simulation results, `None` until the first simulation finishes.
+    reflection_params: Option<ReflectionEffectParams>,
+    /// Set for voices started while [`PathingConfig::enabled`] was on.
+    pathing: Option<PathPipeline>,
+    /// The latest pathing simulation results, `None` until the first simulation finishes.
+    pathing_params: Option<PathEffectParams>,
+    /// Set once the voice's [`ConvolutionReverbSend`](crate::convolution::ConvolutionReverbSend)
+    /// response has loaded.
+    convolver: Option<Box<Convolver>>,
+    headphone_eq: HeadphoneEqFilter,
     direct_params: DirectEffectParams,
-    direct_effect: DirectEffect,
-    settings: SpatialAudioSettings,
+    /// Taken from the [`AudioSourcePool`] for [`PooledAudio`] voices.
+    settings: PooledSettings,
     blocks_played: u32,
-    direction: Arc<Mutex<Vec3>>,
-    source_position: Arc<Mutex<Vec3>>,
-    listener_position: Arc<Mutex<Vec3>>,
+    /// Frames read from the source so far, at its own sample rate. Pitch and Doppler read more
+    /// or fewer of them per block.
+    source_frames: u64,
+    /// Gain applied while fading in or out of [`PauseAudio`](crate::playback::PauseAudio).
+    pause_gain: f32,
+    /// The [`VolumeScale`](crate::volume::VolumeScale) gain the last block ended on.
+    volume_gain: f32,
+    /// Samples played so far, for [`FadeIn`](crate::volume::FadeIn).
+    samples_faded_in: u64,
+    /// The [`MaxAudibleDistance`](crate::culling::MaxAudibleDistance) gain the last block ended
+    /// on.
+    distance_gain: f32,
+    /// Source samples consumed per output sample, eased towards the Doppler and
+    /// [`PitchShift`](crate::pitch::PitchShift) pitch.
+    playback_rate: f32,
+    /// State of the xorshift generator behind [`PitchVariance`](crate::pitch::PitchVariance).
+    pitch_rng: u32,
+    /// Set once the rate has left 1.0, after which blocks are always read through the resampler.
+    resampling: bool,
+    /// Set once a short final block was read, the voice ends instead of reading the next one.
+    source_ended: bool,
+    /// Tail blocks left to render once the source is exhausted, `None` while it plays.
+    tail: Option<u32>,
+    /// Set when the last block skipped the effects, the next rendered block fades in.
+    was_culled: bool,
+    /// Fractional position between `resample_from` and `resample_to`.
+    resample_offset: f32,
+    resample_from: f32,
+    resample_to: f32,
+    /// The last complete snapshot of the voice's [`SourceParams`], kept when a read races with
+    /// the game thread.
+    current_params: SourceParams,
+    /// The last complete snapshot of the listener orientation.
+    current_listener: SourceOrientation,
+    /// The last complete snapshot of the [`ListenerReverbState`](crate::area::ListenerReverbState)
+    /// wet mix.
+    current_reverb_wet: f32,
+    /// The last complete snapshot of the simulator's direct outputs.
+    current_direct: DirectOutputs,
+    /// The outputs heard when `current_direct` arrived, eased away from over one simulation
+    /// tick.
+    previous_direct: DirectOutputs,
+    /// How far the ease from `previous_direct` has come, from `0.0` to `1.0`.
+    direct_progress: f32,
+    simulation_tick_rate: f32,
+    /// The last complete snapshot of the voice's transmission bands.
+    current_transmission: Option<[f32; 3]>,
+    /// The last complete snapshot of the voice's [`SourceMix`].
+    current_mix: SourceMix,
+    /// The [`SteamAudioMixer`] levels, eased towards the latest snapshot.
+    mixer: MixerRamp,
+    voice: Arc<VoiceState>,
 }
 
 impl SteamDecoder {
-    fn new(
-        direction: Arc<Mutex<Vec3>>,
-        source_position: Arc<Mutex<Vec3>>,
-        listener_position: Arc<Mutex<Vec3>>,
-        path: String,
-    ) -> Self {
+    fn new(voice: Arc<VoiceState>, data: AudioData) -> Self {
         // Create reader
-        let file = std::fs::File::open(path).unwrap();
-        let dec = rodio::Decoder::new(file).unwrap();
-
-        let audio_settings = AudioSettings::default();
-        let context_settings = ContextSettings::default();
-        let hrtf_settings = HRTFSettings::default();
-        let simulation_settings = SimulationSettings::from_audio_settings(&audio_settings);
+        let dec = Self::open(&voice, &data);
 
-        let context = Context::new(&context_settings).expect("could not build steam audio context");
-        let hrtf = HRTF::new(&context, &audio_settings, &hrtf_settings)
-            .expect("could not build steam audio hrtf");
-        let simulator = Simulator::new(&context, &simulation_settings)
-            .expect("could not build steam audio simulation");
+        let audio_settings = voice.audio_settings.clone();
+        let settings = match &voice.pool {
+            Some(pool) => pool.take_or_build(&audio_settings, &voice.hrtf),
+            None => PooledSettings::new(DecoderSettings::new(&audio_settings, &voice.hrtf)),
+        };
+        let context = &settings.context;
+        let hrtf = &settings.hrtf;
 
+        let binaural_config = voice.binaural.load();
         let mut binaural_params = BinauralParams::default();
-        binaural_params.interpolation = HRTFInterpolation::Bilinear;
+        binaural_params.interpolation = binaural_config.interpolation;
+        binaural_params.spatial_blend = binaural_config.spatial_blend;
 
-        let binaural_effect = BinauralEffect::new(&context, &audio_settings, &hrtf).unwrap();
+        let warmup_blocks = voice.warmup_blocks;
+        let ambisonics = voice.ambisonics.map(|(order, binaural)| {
+            AmbisonicsPipeline::new(context, &audio_settings, hrtf, order, binaural)
+        });
+
+        // Effect chains mix their own reflections in a `Reverb` stage.
+        let reflections = voice
+            .reflection_target()
+            .filter(|_| voice.effect_chain.is_none())
+            .map(|target| ReflectionPipeline::new(context, &audio_settings, target));
+        let pathing = voice
+            .pathing_config
+            .map(|config| PathPipeline::new(context, &audio_settings, hrtf, config));
+
+        let headphone_eq =
+            HeadphoneEqFilter::new(context, &audio_settings, voice.headphone_eq.load());
 
         let mut direct_params = DirectEffectParams::default();
         direct_params.flags = DirectEffectFlags::AIR_ABSORPTION
             | DirectEffectFlags::DISTANCE_ATTENUATION
             | DirectEffectFlags::DIRECTIVITY;
-        let direct_effect = DirectEffect::new(&context, &audio_settings, 1).unwrap();
 
         // standard sample rate for most recordings
         let sample_rate = 44_100;
-        SteamDecoder {
+        let source_rate = dec.sample_rate() as f32 / sample_rate as f32;
+        let input_buffer = DeinterleavedFrame::new(
+            audio_settings.frame_size() as usize,
+            1,
+            audio_settings.sampling_rate(),
+        );
+        let mut decoder = SteamDecoder {
             decoder: dec,
+            data,
             sample_rate,
-            current_channel: true,
+            current_channel: 0,
             current_block_offset: 0,
-            current_block1: Vec::new(),
-            current_block2: Vec::new(),
+            current_blocks: Vec::new(),
+            input_buffer,
+            frames: BlockFrames::new(&audio_settings, OutputMode::Binaural),
+            ended: false,
+            output_mode: OutputMode::Binaural,
+            panning: None,
             binaural_params,
-            binaural_effect,
+            binaural_blend: binaural_config.spatial_blend,
+            spatial_blend: voice.spatial_blend.load(),
+            previous_binaural: None,
+            near_field: None,
+            ambisonics,
+            chain: None,
+            reflections,
+            reflection_params: None,
+            pathing,
+            pathing_params: None,
+            convolver: None,
+            headphone_eq,
             direct_params,
-            direct_effect,
-            settings: SpatialAudioSettings {
-                audio_settings,
-                context_settings,
-                hrtf_settings,
-                simulation_settings,
-                context,
-                hrtf,
-                simulator,
-            },
+            settings,
             blocks_played: 0,
-            direction,
-            source_position,
-            listener_position,
+            source_frames: 0,
+            pause_gain: 1.0,
+            volume_gain: 1.0,
+            samples_faded_in: 0,
+            distance_gain: 1.0,
+            playback_rate: source_rate,
+            // Any non-zero seed, differing per voice so varied voices don't move in lockstep.
+            pitch_rng: (Arc::as_ptr(&voice) as usize as u32) | 1,
+            resampling: false,
+            source_ended: false,
+            tail: None,
+            was_culled: false,
+            resample_offset: 2.0,
+            resample_from: 0.0,
+            resample_to: 0.0,
+            current_params: voice.params.load(),
+            current_listener: voice.listener_orientation.load(),
+            current_reverb_wet: voice.reverb_wet.load(),
+            current_direct: voice.direct.load(),
+            previous_direct: voice.direct.load(),
+            direct_progress: 1.0,
+            simulation_tick_rate: voice.simulation_tick_rate.load(),
+            current_transmission: voice.transmission.load(),
+            current_mix: voice.mix.load(),
+            mixer: MixerRamp::new(voice.mixer.load()),
+            voice,
+        };
+        decoder.set_output_mode(decoder.voice.output_mode.load());
+        decoder.warm_up(warmup_blocks);
+        decoder
+    }
+
+    /// Rebuilds the effects for `mode`, keeping the current ones if that fails.
+    fn set_output_mode(&mut self, mode: OutputMode) {
+        let panning = match mode {
+            OutputMode::Binaural => None,
+            OutputMode::Panning(layout) => match PanningEffect::new(
+                &self.settings.context,
+                &self.settings.audio_settings,
+                layout,
+            ) {
+                Ok(effect) => Some(effect),
+                Err(err) => {
+                    warn!("Could not build steam audio panning effect: {err:?}");
+                    return;
+                }
+            },
+        };
+
+        // The last stages of a chain render for the output mode.
+        self.chain = self.voice.effect_chain.as_ref().map(|chain| {
+            StageChain::new(
+                chain,
+                &self.settings.context,
+                &self.settings.audio_settings,
+                &self.settings.hrtf,
+                mode,
+                &self.voice.reflection_target(),
+            )
+        });
+        self.output_mode = mode;
+        self.panning = panning;
+        self.previous_binaural = None;
+    }
+
+    /// Runs silent blocks through the effects without reading the source, filling their
+    /// internal state before the first audible block.
+    fn warm_up(&mut self, blocks: u32) {
+        for _ in 0..blocks {
+            self.input_buffer.current_frame[0].fill(0.0);
+            if self.chain.is_some() {
+                self.render_chain(0.0);
+            } else {
+                self.spatialize();
+            }
         }
+
+        self.current_blocks.clear();
     }
-}
 
-// The decoder must implement iterator so that it can implement `Decodable`.
-impl Iterator for SteamDecoder {
-    type Item = f32;
+    /// Opens the source, or plays silence and flags the voice when it can't be read, so a bad
+    /// path doesn't take down the app.
+    fn open(voice: &VoiceState, data: &AudioData) -> SampleProvider {
+        data.open().unwrap_or_else(|err| {
+            warn!("{err}, playing silence instead.");
+            if let Ok(mut error) = voice.error.try_lock() {
+                *error = Some(err);
+            }
+            SampleProvider::silence(voice.error_silence)
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            // todo: len() can be determined at creation
-            if self.current_block_offset < self.current_block1.len() as u32 {
-                // Read from the current block
-                let raw_val: f32;
-
-                if self.current_channel {
-                    raw_val = self.current_block1[self.current_block_offset as usize];
-                } else {
-                    raw_val = self.current_block2[self.current_block_offset as usize];
-                    self.current_block_offset += 1;
-                }
+    /// Fast-forwards to the block of source frames containing `position`, reopening the source
+    /// when seeking backwards. Skipped frames are read but never run through the effects.
+    fn seek(&mut self, position: Duration) {
+        let frame_size = self.settings.audio_settings.frame_size() as u64;
+        let target_block =
+            (position.as_secs_f64() * self.decoder.sample_rate() as f64) as u64 / frame_size;
+        let target_frame = target_block * frame_size;
+
+        if target_frame < self.source_frames {
+            self.decoder = Self::open(&self.voice, &self.data);
+            self.source_frames = 0;
+        }
+
+        self.resample_offset = 2.0;
+        self.source_ended = false;
+        self.tail = None;
+
+        let skip = (target_frame - self.source_frames) as usize;
+        self.source_frames += self.decoder.by_ref().take(skip).count() as u64;
+
+        // Drop whatever is left of the block we were playing, and ramp back up from silence so
+        // the jump doesn't click.
+        self.current_block_offset = 0;
+        self.current_blocks.clear();
+        self.volume_gain = 0.0;
+        self.store_position();
+    }
+
+    /// Publishes the position of `source_frames` for [`SpatialPlaybackControl::position`](crate::playback::SpatialPlaybackControl::position)
+    /// and [`PlaybackPosition`](crate::playback::PlaybackPosition).
+    fn store_position(&self) {
+        let frames = self.source_frames;
+        let nanos = frames * 1_000_000_000 / self.decoder.sample_rate().max(1) as u64;
+        self.voice.frames.store(frames, Ordering::Relaxed);
+        self.voice.position.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Rebuilds the HRTF and binaural effect from the shared settings, keeping the old effect
+    /// around so the next block can crossfade between the two.
+    fn swap_hrtf(&mut self, generation: u32) {
+        self.settings.hrtf_generation = generation;
+        let hrtf_settings = self.voice.hrtf.settings();
 
-                self.current_channel = !self.current_channel;
-                return Some(raw_val);
+        let swapped = HRTF::new(
+            &self.settings.context,
+            &self.settings.audio_settings,
+            &hrtf_settings,
+        )
+        .and_then(|hrtf| {
+            BinauralEffect::new(&self.settings.context, &self.settings.audio_settings, &hrtf)
+                .map(|effect| (effect, hrtf))
+        });
+
+        match swapped {
+            Ok((effect, hrtf)) => {
+                let previous_effect = std::mem::replace(&mut self.settings.binaural_effect, effect);
+                let previous_hrtf = std::mem::replace(&mut self.settings.hrtf, hrtf);
+                self.previous_binaural = Some((previous_effect, previous_hrtf));
+                // Rebuilt with the new HRTF the next time it's needed.
+                self.near_field = None;
+                if let Some(ambisonics) = &mut self.ambisonics {
+                    ambisonics.set_hrtf(&self.settings.context, &self.settings.hrtf);
+                }
+                if let Some(pathing) = &mut self.pathing {
+                    pathing.set_hrtf(&self.settings.context, &self.settings.hrtf);
+                }
+                if let Some(chain) = &mut self.chain {
+                    chain.set_hrtf(&self.settings.context, &self.settings.hrtf);
+                }
+                self.settings.hrtf_settings = hrtf_settings;
             }
+            Err(err) => warn!("Could not swap steam audio hrtf, keeping the old one: {err:?}"),
+        }
+    }
 
-            // Load the next block
-            self.current_block_offset = 0;
+    /// Mixes the reflections of the input block into the shared Ambisonics bed, before the direct
+    /// effect consumes it.
+    fn render_reflections(&mut self, gain: f32) {
+        let (Some(reflections), Some(params)) = (&mut self.reflections, &self.reflection_params)
+        else {
+            return;
+        };
 
-            let mut input_buffer = DeinterleavedFrame::new(
-                self.settings.audio_settings.frame_size() as usize,
-                1,
-                self.settings.audio_settings.sampling_rate(),
-            );
+        self.frames
+            .fit(&self.settings.audio_settings, self.output_mode);
+        let mono = &mut self.frames.mono;
+        mono.current_frame[0].clone_from(&self.input_buffer.current_frame[0]);
+        reflections.apply(params, mono, gain);
+    }
 
-            let mut intermediate_buffer = DeinterleavedFrame::new(
+    /// Renders the simulated paths around geometry of the input block into the pathing frame,
+    /// before the direct effect consumes it. `false` when there are no paths to hear.
+    fn render_pathing(&mut self) -> bool {
+        let (Some(pathing), Some(params)) = (&mut self.pathing, &self.pathing_params) else {
+            return false;
+        };
+
+        self.frames
+            .fit(&self.settings.audio_settings, self.output_mode);
+        let frames = &mut self.frames;
+        frames.mono.current_frame[0].clone_from(&self.input_buffer.current_frame[0]);
+        pathing.apply(
+            params,
+            &mut frames.mono,
+            self.current_listener,
+            &mut frames.pathing,
+        );
+        true
+    }
+
+    /// Runs the input block through the voice's [`EffectChain`](crate::chain::EffectChain) into
+    /// the current blocks, its `Reverb` stages mixing with `reverb_gain`.
+    fn render_chain(&mut self, reverb_gain: f32) {
+        self.update_effect_params();
+        let params = StageParams {
+            direct: self.direct_params.clone(),
+            binaural: self.binaural_params.clone(),
+            direction: (self.current_params.source_position
+                - self.current_params.listener_position)
+                .normalize_or_zero(),
+            listener: self.current_listener,
+            reflections: self.reflection_params.clone(),
+            reverb_gain,
+            eq_gains: self.headphone_eq.gains(),
+        };
+
+        if let Some(chain) = &mut self.chain {
+            self.current_blocks = chain.process(&params, &mut self.input_buffer, self.output_mode);
+        }
+    }
+
+    /// The direct outputs of this block, between the previous and the latest snapshot.
+    fn interpolated_direct(&self) -> DirectOutputs {
+        self.previous_direct
+            .lerp(&self.current_direct, self.direct_progress)
+    }
+
+    /// Moves the direct and binaural parameters to the latest snapshots.
+    fn update_effect_params(&mut self) {
+        // A partially spatialized source is only partially affected by distance.
+        let blend = self.spatial_blend;
+        let direct = self.interpolated_direct();
+        self.direct_params.distance_attenuation = 1.0 + (direct.distance_attenuation - 1.0) * blend;
+        self.direct_params.air_absorption =
+            direct.air_absorption.map(|band| 1.0 + (band - 1.0) * blend);
+        self.direct_params.directivity = direct.directivity;
+
+        // The occluded part of the source is filtered by the obstacle in the way, or just
+        // attenuated when there is none to filter by.
+        self.direct_params.occlusion = direct.occlusion;
+        self.direct_params.flags.set(
+            DirectEffectFlags::OCCLUSION,
+            direct.occlusion < 1.0 || self.current_transmission.is_some(),
+        );
+        match self.current_transmission {
+            Some(transmission) => {
+                self.direct_params.flags |= DirectEffectFlags::TRANSMISSION;
+                self.direct_params.transmission = transmission;
+                self.direct_params.transmission_type = TransmissionType::FrequencyDependent;
+            }
+            None => self.direct_params.flags &= !DirectEffectFlags::TRANSMISSION,
+        }
+
+        self.binaural_params.direction = bevy_to_phonon(self.current_params.direction);
+        // A listener inside a large source hears it from all around.
+        self.binaural_params.spatial_blend = self.binaural_blend * blend * direct.directionality;
+    }
+
+    /// Runs the input block through the direct and binaural effects into the current blocks.
+    fn spatialize(&mut self) {
+        // The direct effect consumes its input, it renders into a new frame that takes the input
+        // buffer's place and is read into on the next block.
+        let input_buffer = std::mem::replace(
+            &mut self.input_buffer,
+            DeinterleavedFrame::new(
                 self.settings.audio_settings.frame_size() as usize,
                 1,
                 self.settings.audio_settings.sampling_rate(),
-            );
+            ),
+        );
+        self.frames
+            .fit(&self.settings.audio_settings, self.output_mode);
 
-            // move the stuff below to the struct?
-            let mut output_buffer = DeinterleavedFrame::new(
-                self.settings.audio_settings.frame_size() as usize,
-                2,
-                self.settings.audio_settings.sampling_rate(),
-            );
+        let dir = self.current_params.direction;
+        let source_pos = self.current_params.source_position;
+        let listener_pos = self.current_params.listener_position;
 
-            // todo: len() can be determined at creation
-            if input_buffer.push_source(&mut self.decoder) {
-                let dir: Vec3 = *self.direction.lock().unwrap();
-                let source_pos: Vec3 = *self.source_position.lock().unwrap();
-                let listener_pos: Vec3 = *self.listener_position.lock().unwrap();
-
-                let attenuation_model = DistanceAttenuationModel::default();
-                let attenuation = attenuation_model.calculate(
-                    &self.settings.context,
-                    source_pos.into(),
-                    listener_pos.into(),
-                );
+        self.update_effect_params();
 
-                let absorption_model = AirAbsorptionModel::default();
-                let absorption = absorption_model.calculate(
-                    &self.settings.context,
-                    source_pos.into(),
-                    listener_pos.into(),
-                );
+        self.settings
+            .direct_effect
+            .apply_to_buffer(&self.direct_params, input_buffer, &mut self.input_buffer)
+            .unwrap();
 
-                let directivity_model = Directivity {
-                    dipole_weight: 0.0,
-                    dipole_power: 1.0,
-                };
-                let directivity = directivity_model.calculate(
-                    &self.settings.context,
-                    Orientation {
-                        right: Vec3::X.into(),
-                        up: Vec3::Y.into(),
-                        ahead: Vec3::NEG_Z.into(),
-                        origin: Vec3::ZERO.into(),
-                    },
-                    listener_pos.into(),
-                );
+        // Speakers replace both the binaural and the Ambisonics rendering.
+        if let Some(panning) = &mut self.panning {
+            let params = PanningEffectParams {
+                direction: bevy_to_phonon(dir),
+            };
+            panning
+                .apply_to_buffer(&params, &mut self.input_buffer, &mut self.frames.output)
+                .unwrap();
 
-                self.direct_params.distance_attenuation = attenuation;
-                self.direct_params.air_absorption = absorption;
-                self.direct_params.directivity = directivity;
+            self.current_blocks
+                .clone_from(&self.frames.output.current_frame);
+            return;
+        }
 
-                // todo: why is direct effect apply_to_buffer input not mut compared to binaural effect?
-                self.direct_effect
-                    .apply_to_buffer(&self.direct_params, input_buffer, &mut intermediate_buffer)
-                    .unwrap();
+        if let Some(ambisonics) = &mut self.ambisonics {
+            let listener = self.current_listener;
+            ambisonics.apply(
+                &mut self.input_buffer,
+                (source_pos - listener_pos).normalize_or_zero(),
+                listener,
+                &mut self.frames.output,
+            );
 
-                self.binaural_params.direction = dir.into();
+            self.previous_binaural = None;
+            self.current_blocks
+                .clone_from(&self.frames.output.current_frame);
+            return;
+        }
 
-                self.binaural_effect
+        // The binaural effect consumes its input, so the outgoing HRTF gets a copy of it.
+        let crossfade = match self.previous_binaural.take() {
+            Some((mut effect, _hrtf)) => {
+                let frames = &mut self.frames;
+                frames.mono.current_frame[0].clone_from(&self.input_buffer.current_frame[0]);
+                effect
                     .apply_to_buffer(
                         &self.binaural_params,
-                        &mut intermediate_buffer,
-                        &mut output_buffer,
+                        &mut frames.mono,
+                        &mut frames.previous_binaural,
                     )
                     .unwrap();
+                true
+            }
+            None => false,
+        };
+
+        let near_field_weight = NearFieldCorrection::weight(
+            self.voice.near_field_threshold.load(),
+            (source_pos - listener_pos).length(),
+        );
+        let near_field = near_field_weight > 0.0 && self.render_near_field();
+
+        self.settings
+            .binaural_effect
+            .apply_to_buffer(
+                &self.binaural_params,
+                &mut self.input_buffer,
+                &mut self.frames.output,
+            )
+            .unwrap();
+
+        self.current_blocks
+            .clone_from(&self.frames.output.current_frame);
+
+        // Crossfade from the old HRTF to the new one over the block so the swap doesn't click.
+        if crossfade {
+            let previous = &self.frames.previous_binaural.current_frame;
+            for (block, previous) in self.current_blocks.iter_mut().zip(previous) {
+                let len = block.len() as f32;
+                for (index, (sample, previous)) in block.iter_mut().zip(previous).enumerate() {
+                    let t = (index as f32 + 1.0) / len;
+                    *sample = t * *sample + (1.0 - t) * previous;
+                }
+            }
+        }
+
+        if near_field {
+            for (block, near_field) in self
+                .current_blocks
+                .iter_mut()
+                .zip(&self.frames.near_field.current_frame)
+            {
+                for (sample, near_field) in block.iter_mut().zip(near_field) {
+                    *sample += (near_field - *sample) * near_field_weight;
+                }
+            }
+        }
+    }
+
+    /// Runs a copy of the direct output through the binaural effect with near-field correction
+    /// into the near-field frame, `false` if the effect couldn't be built.
+    fn render_near_field(&mut self) -> bool {
+        if self.near_field.is_none() {
+            self.near_field = BinauralEffect::new(
+                &self.settings.context,
+                &self.settings.audio_settings,
+                &self.settings.hrtf,
+            )
+            .ok();
+        }
+        let Some(effect) = &mut self.near_field else {
+            return false;
+        };
+
+        let frames = &mut self.frames;
+        frames.mono.current_frame[0].clone_from(&self.input_buffer.current_frame[0]);
+        let mut params = self.binaural_params.clone();
+        params.near_field_correction = true;
+        effect
+            .apply_to_buffer(&params, &mut frames.mono, &mut frames.near_field)
+            .unwrap();
+        true
+    }
+
+    /// Fills the input buffer from the source at the current playback rate, `false` once there
+    /// was nothing left to read.
+    ///
+    /// A final block shorter than the frame size is zero-padded and played, the voice ends at the
+    /// block after it.
+    fn read_block(&mut self) -> bool {
+        if self.source_ended {
+            return false;
+        }
+
+        // Ease towards the new rate so pitch changes don't zipper. Sources recorded at another
+        // rate are resampled to the output rate along the way.
+        let source_rate = self.decoder.sample_rate() as f32 / self.sample_rate as f32;
+        let target = self.voice.doppler_pitch.load() * self.pitch() * source_rate;
+        self.playback_rate += (target - self.playback_rate) * 0.5;
+
+        let mut samples = std::mem::take(&mut self.input_buffer.current_frame[0]);
+        let read = if !self.resampling && (self.playback_rate - 1.0).abs() < 1e-4 {
+            self.read_samples(&mut samples)
+        } else {
+            self.resampling = true;
+            self.read_resampled(&mut samples)
+        };
+
+        if read < samples.len() {
+            samples[read..].fill(0.0);
+            self.source_ended = true;
+        }
+        self.input_buffer.current_frame[0] = samples;
+        read > 0
+    }
+
+    /// Copies the source into `samples`, returning how many were read.
+    fn read_samples(&mut self, samples: &mut [f32]) -> usize {
+        let mut read = 0;
+        for (sample, next) in samples.iter_mut().zip(self.decoder.by_ref()) {
+            *sample = next;
+            read += 1;
+        }
+        self.source_frames += read as u64;
+        read
+    }
+
+    /// The [`PitchShift`](crate::pitch::PitchShift) of this block, offset by the variance.
+    fn pitch(&mut self) -> f32 {
+        let pitch = self.voice.pitch.load();
+        let variance = self.voice.pitch_variance.load();
+        if variance == 0.0 {
+            return pitch;
+        }
+
+        self.pitch_rng ^= self.pitch_rng << 13;
+        self.pitch_rng ^= self.pitch_rng >> 17;
+        self.pitch_rng ^= self.pitch_rng << 5;
+        let random = self.pitch_rng as f32 / u32::MAX as f32 * 2.0 - 1.0;
+
+        (pitch + random * variance).max(0.01)
+    }
+
+    /// Linearly interpolates the source, stepping `playback_rate` source samples per sample.
+    /// Returns how many samples were written.
+    fn read_resampled(&mut self, samples: &mut [f32]) -> usize {
+        for (index, sample) in samples.iter_mut().enumerate() {
+            while self.resample_offset >= 1.0 {
+                let Some(next) = self.decoder.next() else {
+                    return index;
+                };
+
+                self.source_frames += 1;
+                self.resample_offset -= 1.0;
+                self.resample_from = self.resample_to;
+                self.resample_to = next;
+            }
+
+            *sample =
+                self.resample_from + (self.resample_to - self.resample_from) * self.resample_offset;
+            self.resample_offset += self.playback_rate;
+        }
+
+        samples.len()
+    }
+
+    /// Applies the voice's volume to `samples`, ramping from the previous volume over the first
+    /// 64 samples and scaled by the fade in while it lasts.
+    fn apply_volume(&mut self, samples: &mut [f32]) {
+        const RAMP: usize = 64;
+
+        let target = self.voice.volume.load() * self.voice.bus_volume.load();
+        let fade_in = self.voice.fade_in.load(Ordering::Relaxed);
+        let fade_in_samples = fade_in as f64 * self.sample_rate as f64 / 1_000_000_000.0;
+
+        let start = self.volume_gain;
+        let ramp = samples.len().min(RAMP);
+        for (index, sample) in samples.iter_mut().enumerate() {
+            let gain = if index < ramp {
+                start + (target - start) * (index + 1) as f32 / ramp as f32
+            } else {
+                target
+            };
+
+            let fade = if self.samples_faded_in as f64 >= fade_in_samples {
+                1.0
+            } else {
+                self.samples_faded_in += 1;
+                (self.samples_faded_in as f64 / fade_in_samples) as f32
+            };
+
+            *sample *= gain * fade;
+        }
+
+        self.volume_gain = target;
+    }
+
+    /// Scales every output channel of the current block.
+    fn apply_gain(&mut self, gain: f32) {
+        if gain == 1.0 {
+            return;
+        }
+
+        for sample in self.current_blocks.iter_mut().flatten() {
+            *sample *= gain;
+        }
+    }
+
+    /// Scales the current block by a [`SteamAudioMixer`] level, eased over the block.
+    fn apply_mixer_level(&mut self, level: fn(&SteamAudioMixer) -> f32) {
+        let len = self.current_blocks.first().map_or(0, Vec::len);
+        let gains = self.mixer.gains(level, len);
+        if gains.iter().all(|gain| *gain == 1.0) {
+            return;
+        }
+
+        for block in &mut self.current_blocks {
+            for (sample, gain) in block.iter_mut().zip(&gains) {
+                *sample *= gain;
+            }
+        }
+    }
+
+    /// Sets the current block to `samples` on the front left and right channels, silence on the
+    /// others.
+    fn set_unspatialized(&mut self, samples: &[f32]) {
+        let channels = self.output_mode.channels() as usize;
+        self.current_blocks = (0..channels)
+            .map(|channel| {
+                if channel < 2 {
+                    samples.to_vec()
+                } else {
+                    vec![0.0; samples.len()]
+                }
+            })
+            .collect();
+    }
+
+    /// Crossfades the current block towards the unprocessed `raw` signal.
+    fn bypass(&mut self, raw: &[f32], amount: f32) {
+        // Exactly the decoded file, so the spatialization can be A/B tested.
+        if amount >= 1.0 {
+            self.set_unspatialized(raw);
+            return;
+        }
+
+        for (channel, block) in self.current_blocks.iter_mut().enumerate() {
+            for (sample, raw) in block.iter_mut().zip(raw) {
+                let dry = if channel < 2 { amount * raw } else { 0.0 };
+                *sample = (1.0 - amount) * *sample + dry;
+            }
+        }
+    }
+
+    /// Moves the spatial blend a step towards the voice's, so switching between 2D and 3D
+    /// doesn't zipper.
+    fn ease_spatial_blend(&mut self) {
+        const STEP: f32 = 0.25;
+
+        let target = self.voice.spatial_blend.load();
+        self.spatial_blend += (target - self.spatial_blend).clamp(-STEP, STEP);
+    }
+
+    /// Ramps from the previous distance gain to the current one over `samples`.
+    fn apply_distance_gain(&mut self, samples: &mut [f32]) {
+        let start = self.distance_gain;
+        let target = self.voice.distance_gain.load();
+        if start == 1.0 && target == 1.0 {
+            return;
+        }
+
+        let len = samples.len() as f32;
+        for (index, sample) in samples.iter_mut().enumerate() {
+            *sample *= start + (target - start) * (index + 1) as f32 / len;
+        }
+        self.distance_gain = target;
+    }
+
+    /// Ramps `pause_gain` towards silence while paused (and back up once resumed) over
+    /// `fade_frames` samples, applying it to `samples`.
+    fn fade_pause(&mut self, samples: &mut [f32], paused: bool, fade_frames: u32) {
+        let target = if paused { 0.0 } else { 1.0 };
+        if self.pause_gain == target {
+            return;
+        }
+
+        let step = if fade_frames == 0 {
+            1.0
+        } else {
+            1.0 / fade_frames as f32
+        };
 
-                self.current_block1 = output_buffer.current_frame[0].clone();
-                self.current_block2 = output_buffer.current_frame[1].clone();
-                self.blocks_played += 1;
+        for sample in samples.iter_mut() {
+            self.pause_gain = if paused {
+                (self.pause_gain - step).max(0.0)
             } else {
-                return None;
+                (self.pause_gain + step).min(1.0)
+            };
+            *sample *= self.pause_gain;
+        }
+    }
+
+    /// Reads and renders the next block, `false` once the source has ended.
+    fn next_block(&mut self) -> bool {
+        if self.ended {
+            return false;
+        }
+        let block_started = Instant::now();
+
+        if let Some(position) = self.voice.take_seek() {
+            self.seek(position);
+        }
+
+        // Never wait on the game thread, a torn read keeps the previous block's values.
+        if let Some(params) = self.voice.params.try_load() {
+            self.current_params = params;
+        }
+        if let Some(listener) = self.voice.listener_orientation.try_load() {
+            self.current_listener = listener;
+        }
+        if let Some(wet) = self.voice.reverb_wet.try_load() {
+            self.current_reverb_wet = wet;
+        }
+        if let Some(rate) = self.voice.simulation_tick_rate.try_load() {
+            self.simulation_tick_rate = rate;
+        }
+        if let Some(direct) = self.voice.direct.try_load() {
+            if direct != self.current_direct {
+                self.previous_direct = self.interpolated_direct();
+                self.direct_progress = 0.0;
+                self.current_direct = direct;
             }
         }
+        // Slow tick rates would otherwise step the gains once per tick.
+        let block_seconds = self.settings.audio_settings.frame_size() as f32
+            / self.settings.audio_settings.sampling_rate() as f32;
+        self.direct_progress =
+            (self.direct_progress + block_seconds * self.simulation_tick_rate).min(1.0);
+        if let Some(transmission) = self.voice.transmission.try_load() {
+            self.current_transmission = transmission;
+        }
+        if let Some(mix) = self.voice.mix.try_load() {
+            self.current_mix = mix;
+        }
+        if let Some(levels) = self.voice.mixer.try_load() {
+            self.mixer.set_target(levels);
+        }
+        if let Some(gains) = self.voice.headphone_eq.try_load() {
+            self.headphone_eq.set_gains(gains);
+        }
+        if let Some(binaural) = self.voice.binaural.try_load() {
+            self.binaural_params.interpolation = binaural.interpolation;
+            self.binaural_blend = binaural.spatial_blend;
+        }
+        self.ease_spatial_blend();
+
+        // Picked up whenever the game thread isn't writing a new simulation result.
+        if let Ok(mut reflections) = self.voice.reflections.try_lock() {
+            if let Some(params) = reflections.take() {
+                self.reflection_params = Some(params);
+            }
+        }
+        if let Ok(mut pathing) = self.voice.pathing.try_lock() {
+            if let Some(params) = pathing.take() {
+                self.pathing_params = Some(params);
+            }
+        }
+        if let Ok(mut convolution) = self.voice.convolution.try_lock() {
+            if let Some(convolver) = convolution.take() {
+                self.convolver = Some(convolver);
+            }
+        }
+
+        let generation = self.voice.hrtf.generation();
+        if generation != self.settings.hrtf_generation {
+            self.swap_hrtf(generation);
+        }
+        if let Some(mode) = self.voice.output_mode.try_load() {
+            if mode != self.output_mode {
+                self.set_output_mode(mode);
+            }
+        }
+
+        let paused = self.voice.paused.load(Ordering::Relaxed);
+        let fade_frames = self.voice.pause_fade_frames.load(Ordering::Relaxed);
+        if paused && fade_frames == 0 {
+            self.pause_gain = 0.0;
+        }
+        // Paused voices keep running silence through the effects so the HRTF doesn't click.
+        let silent = paused && self.pause_gain <= 0.0;
+
+        // The buffer still holds the last block, silence replaces it when nothing is read.
+        if silent || self.tail.is_some() {
+            self.input_buffer.current_frame[0].fill(0.0);
+        } else if !self.read_block() {
+            self.input_buffer.current_frame[0].fill(0.0);
+            self.tail = Some(self.voice.tail_blocks);
+        }
+        // The exhausted source keeps feeding silence until the effect tails have rung out.
+        let drained = match &mut self.tail {
+            Some(0) => true,
+            Some(remaining) => {
+                *remaining -= 1;
+                false
+            }
+            None => false,
+        };
+        if self.voice.is_stopped() || drained {
+            // Latched, so the last block isn't played again.
+            self.ended = true;
+            self.current_blocks.clear();
+            self.voice.finished.store(true, Ordering::Release);
+            return false;
+        }
+
+        // The decoded signal as is, for `SourceMix::dry_bypass`.
+        let mix = self.current_mix;
+        let raw = (mix.dry_bypass > 0.0).then(|| self.input_buffer.current_frame[0].clone());
+
+        if !silent {
+            let mut samples = std::mem::take(&mut self.input_buffer.current_frame[0]);
+            self.fade_pause(&mut samples, paused, fade_frames);
+            self.apply_volume(&mut samples);
+            self.apply_distance_gain(&mut samples);
+            self.input_buffer.current_frame[0] = samples;
+        }
+
+        let convolution_wet = self.voice.convolution_wet.load();
+        let send = (self.convolver.is_some() && convolution_wet > 0.0)
+            .then(|| self.input_buffer.current_frame[0].clone());
+
+        // Out of range once the fade out has finished.
+        let inaudible = !self.voice.audible.load(Ordering::Relaxed) && self.distance_gain <= 0.0;
+
+        let culled = inaudible || self.voice.virtualized.load(Ordering::Relaxed);
+        let blend = self.spatial_blend;
+        let frame_size = self.input_buffer.current_frame[0].len();
+        // Reflections go to the shared bed, the master gain can't be applied to them afterwards.
+        let reflection_level = self.mixer.mean(|mixer| mixer.reflection_level, frame_size)
+            * self.mixer.mean(|mixer| mixer.master_gain, frame_size);
+        if culled {
+            // Keep time moving without paying for the effects.
+            self.current_blocks = vec![vec![0.0; frame_size]; self.output_mode.channels() as usize];
+        } else if blend > 0.0 && self.chain.is_some() {
+            let wet = blend * mix.wet_gain * self.current_reverb_wet * reflection_level;
+            self.render_chain(wet * (1.0 - mix.dry_bypass));
+            self.apply_gain(mix.direct_gain);
+            self.apply_mixer_level(|mixer| mixer.direct_level);
+        } else if blend > 0.0 {
+            // The bed isn't bypassed with the rest of the voice, fade the reflections instead.
+            let wet = blend * mix.wet_gain * self.current_reverb_wet * reflection_level;
+            self.render_reflections(wet * (1.0 - mix.dry_bypass));
+            let paths = self.render_pathing();
+            self.spatialize();
+            self.apply_gain(mix.direct_gain);
+            self.apply_mixer_level(|mixer| mixer.direct_level);
+
+            // Sound reaching the listener around geometry is still part of the direct path.
+            if paths {
+                let gain = blend * mix.direct_gain;
+                let levels = self.mixer.gains(|mixer| mixer.pathing_level, frame_size);
+                let paths = &self.frames.pathing.current_frame;
+                for (block, path) in self.current_blocks.iter_mut().zip(paths) {
+                    for ((sample, path), level) in block.iter_mut().zip(path).zip(&levels) {
+                        *sample += gain * level * path;
+                    }
+                }
+            }
+        } else {
+            let samples = std::mem::take(&mut self.input_buffer.current_frame[0]);
+            self.set_unspatialized(&samples);
+            self.input_buffer.current_frame[0] = samples;
+            self.apply_gain(mix.direct_gain);
+            self.apply_mixer_level(|mixer| mixer.direct_level);
+        }
+
+        if !culled {
+            if let (Some(convolver), Some(send)) = (&mut self.convolver, send) {
+                let level = self.mixer.mean(|mixer| mixer.reverb_level, frame_size);
+                let mut wet = vec![0.0; send.len()];
+                convolver.process(
+                    &send,
+                    convolution_wet * level * (1.0 - mix.dry_bypass),
+                    &mut wet,
+                );
+                // Front left and right in every layout.
+                for block in self.current_blocks.iter_mut().take(2) {
+                    for (sample, wet) in block.iter_mut().zip(&wet) {
+                        *sample += wet;
+                    }
+                }
+            }
+            // Speakers aren't headphones, chains place the EQ themselves.
+            if self.output_mode == OutputMode::Binaural && self.chain.is_none() {
+                if let [left, right] = &mut self.current_blocks[..] {
+                    self.headphone_eq.apply(left, right);
+                }
+            }
+            if let Some(raw) = raw {
+                self.bypass(&raw, mix.dry_bypass);
+            }
+            self.apply_mixer_level(|mixer| mixer.master_gain);
+            // The effects restart from stale state, fade in instead of clicking.
+            if self.was_culled {
+                for block in &mut self.current_blocks {
+                    let len = block.len() as f32;
+                    for (index, sample) in block.iter_mut().enumerate() {
+                        *sample *= (index + 1) as f32 / len;
+                    }
+                }
+            }
+        }
+        self.was_culled = culled;
+        self.mixer.advance(frame_size);
+        let audio_settings = &self.settings.audio_settings;
+        let block_duration = Duration::from_secs_f64(
+            audio_settings.frame_size() as f64 / audio_settings.sampling_rate() as f64,
+        );
+        self.voice
+            .stats
+            .record_block(block_started.elapsed(), block_duration);
+
+        // Nothing audible is left, end at the next block. Reflections ring out in the bed, where
+        // the voice can't hear them, so those voices play their whole tail.
+        if self.tail.is_some()
+            && self.voice.reflection_config.is_none()
+            && self
+                .current_blocks
+                .iter()
+                .flatten()
+                .all(|sample| sample.abs() < TAIL_SILENCE)
+        {
+            self.tail = Some(0);
+        }
+
+        if !silent && self.tail.is_none() {
+            if self.blocks_played == 0 {
+                self.voice.started.store(true, Ordering::Release);
+            }
+            self.blocks_played += 1;
+            self.store_position();
+        }
+
+        // Only now that there's a new block to play from its start.
+        self.current_block_offset = 0;
+        self.current_channel = 0;
+        true
+    }
+
+    fn block_exhausted(&self) -> bool {
+        self.current_block_offset >= self.current_blocks.first().map_or(0, Vec::len)
+    }
+}
+
+// The decoder must implement iterator so that it can implement `Decodable`.
+impl Iterator for SteamDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.block_exhausted() && !self.next_block() {
+            return None;
+        }
+
+        let sample = self.current_blocks[self.current_channel][self.current_block_offset];
+        self.current_channel += 1;
+        if self.current_channel == self.current_blocks.len() {
+            self.current_channel = 0;
+            self.current_block_offset += 1;
+
+            // Loaded right away, so `channels` already describes the next block when rodio asks
+            // at the frame boundary.
+            if self.block_exhausted() {
+                self.next_block();
+            }
+        }
+        Some(sample)
     }
 }
 
 // `Source` is what allows the audio source to be played by bevy.
 // This trait provides information on the audio.
 impl Source for SteamDecoder {
+    // Frames end with the blocks, the channel count can change in between when the
+    // `OutputMode` does.
     fn current_frame_len(&self) -> Option<usize> {
-        None
+        if self.ended {
+            return Some(0);
+        }
+        if self.block_exhausted() {
+            let frame_size = self.settings.audio_settings.frame_size() as usize;
+            return Some(frame_size * self.output_mode.channels() as usize);
+        }
+
+        let remaining = self.current_blocks[0].len() - self.current_block_offset;
+        Some(remaining * self.current_blocks.len() - self.current_channel)
     }
 
     fn channels(&self) -> u16 {
-        2
+        self.output_mode.channels()
     }
 
     fn sample_rate(&self) -> u32 {
@@ -257,201 +1436,734 @@ impl Decodable for SteamAudio {
     type Decoder = SteamDecoder;
 
     fn decoder(&self) -> Self::Decoder {
-        SteamDecoder::new(
-            self.direction.clone(),
-            self.source_position.clone(),
-            self.listener_position.clone(),
-            self.path.clone(),
-        )
+        // Decoders created outside of `queue_voices` get a detached state nobody listens to.
+        SteamDecoder::new(self.voice.clone().unwrap_or_default(), self.data.clone())
+    }
+}
+
+/// Renders `num_blocks` blocks of `asset` on the calling thread and returns the stereo frames,
+/// without an `App` or an audio device.
+///
+/// Each block takes the next of `params`, the last one is kept once they run out. Sources are
+/// unoccluded, distance attenuation, air absorption and directivity come from the default
+/// models. Rendering stops early when the source ends.
+pub fn render_offline(
+    asset: &SteamAudio,
+    params: impl IntoIterator<Item = SourceParams>,
+    num_blocks: usize,
+) -> Vec<[f32; 2]> {
+    let mut params = params.into_iter();
+    let voice = Arc::new(VoiceState::default());
+    let mut decoder = SteamDecoder::new(voice.clone(), asset.data.clone());
+    let global = GlobalAudioSettings::default();
+
+    let mut frames = Vec::new();
+    let mut current = SourceParams::default();
+    for _ in 0..num_blocks {
+        if let Some(next) = params.next() {
+            current = next;
+        }
+        voice.params.store(current);
+        voice.direct.store(DirectOutputs::from_models(
+            &decoder.settings.context,
+            &global.air_absorption_model(),
+            current.orientation,
+            AudioDirectivity::default(),
+            current.listener_position,
+            AudioUnitsPerMeter::default(),
+        ));
+
+        if !decoder.next_block() {
+            break;
+        }
+
+        // Detached voices render binaural, which is always two channels.
+        if let [left, right] = &decoder.current_blocks[..] {
+            frames.extend(left.iter().zip(right).map(|(&left, &right)| [left, right]));
+        }
     }
+    frames
 }
 
-// Todo implement default
 #[derive(Resource)]
 pub struct SpatialAudioSettings {
+    /// Settings the plugin built these from, change [`AudioConfig`], [`ContextConfig`],
+    /// [`HrtfConfig`] or [`SimulationConfig`] to rebuild them.
     pub audio_settings: AudioSettings,
     pub context_settings: ContextSettings,
     pub hrtf_settings: HRTFSettings,
     pub simulation_settings: SimulationSettings,
     pub context: Context,
     pub hrtf: HRTF,
-    pub simulator: Simulator,
+    /// `None` when the plugin was built with [`SpatialAudioPlugin::simulation_enabled`] off.
+    pub simulator: Option<Arc<Simulator>>,
+    pub(crate) shared_hrtf: Arc<SharedHrtf>,
+    pub(crate) listener_orientation: SharedParams<SourceOrientation>,
+    pub(crate) reverb_wet: SharedParams<f32>,
+    pub(crate) simulation_tick_rate: SharedParams<f32>,
+    pub(crate) output_mode: SharedParams<OutputMode>,
+    pub(crate) headphone_eq: SharedParams<[f32; 3]>,
+    pub(crate) mixer: SharedParams<SteamAudioMixer>,
+    pub(crate) stats: Arc<AudioStats>,
+    pub(crate) ambisonics_bus: Arc<AmbisonicsBus>,
+    pub(crate) decoder_pool: DecoderPool,
+    /// Set when reflections are convolved with TrueAudio Next.
+    pub(crate) true_audio_next: Option<Arc<TrueAudioNext>>,
 }
 
-pub struct SpatialAudioPlugin;
-
-impl Plugin for SpatialAudioPlugin {
-    fn build(&self, app: &mut App) {
-        let audio_settings = AudioSettings::default();
-        let context_settings = ContextSettings::default();
-        let hrtf_settings = HRTFSettings::default();
-        let simulation_settings = SimulationSettings::from_audio_settings(&audio_settings);
+/// The part of [`SpatialAudioSettings`] a decoder renders with, everything else is simulated on
+/// the game thread. Along with the effects every voice has, so they can be pooled.
+pub(crate) struct DecoderSettings {
+    pub(crate) audio_settings: AudioSettings,
+    hrtf_settings: HRTFSettings,
+    /// Generation of the shared HRTF settings `hrtf` was built from.
+    hrtf_generation: u32,
+    context: Context,
+    hrtf: HRTF,
+    binaural_effect: BinauralEffect,
+    direct_effect: DirectEffect,
+}
 
-        let context = Context::new(&context_settings).expect("could not build steam audio context");
-        let hrtf = HRTF::new(&context, &audio_settings, &hrtf_settings)
+impl DecoderSettings {
+    pub(crate) fn new(audio_settings: &AudioSettings, shared_hrtf: &SharedHrtf) -> Self {
+        let context =
+            Context::new(&ContextSettings::default()).expect("could not build steam audio context");
+        // The plugin already fell back to the default HRTF if the configured one didn't load.
+        let hrtf_generation = shared_hrtf.generation();
+        let hrtf_settings = shared_hrtf.settings();
+        let hrtf = HRTF::new(&context, audio_settings, &hrtf_settings)
             .expect("could not build steam audio hrtf");
-        let simulator = Simulator::new(&context, &simulation_settings)
-            .expect("could not build steam audio simulation");
+        let binaural_effect = BinauralEffect::new(&context, audio_settings, &hrtf).unwrap();
+        let direct_effect = DirectEffect::new(&context, audio_settings, 1).unwrap();
 
-        app.insert_resource(SpatialAudioSettings {
-            audio_settings,
-            context_settings,
+        Self {
+            audio_settings: audio_settings.clone(),
             hrtf_settings,
-            simulation_settings,
+            hrtf_generation,
             context,
             hrtf,
-            simulator,
-        });
+            binaural_effect,
+            direct_effect,
+        }
+    }
+
+    /// Clears the effects' state left over from the voice that used them before.
+    pub(crate) fn reset(&mut self) {
+        self.binaural_effect.reset();
+        self.direct_effect.reset();
     }
 }
 
-// pub fn context_update(mut commands: Commands, settings: Res<ContextSettings>) {
-//     if settings.is_changed() {
-//         match Context::new(&*settings) {
-//             Ok(context) => {
-//                 commands.insert_resource(context);
-//             }
-//             _ => {}
-//         }
-//     }
-// }
-
-// pub fn hrtf_update(
-//     mut commands: Commands,
-//     context: Res<Context>,
-//     audio_settings: Res<AudioSettings>,
-//     hrtf_settings: Res<HRTFSettings>,
-// ) {
-//     if context.is_changed() || audio_settings.is_changed() || hrtf_settings.is_changed() {
-//         match HRTF::new(&context, &audio_settings, &hrtf_settings) {
-//             Ok(hrtf) => {
-//                 commands.insert_resource(hrtf);
-//             }
-//             _ => {}
-//         };
-//     }
-// }
-
-// pub fn simulation_update(
-//     mut commands: Commands,
-//     context: Res<Context>,
-//     simulation_settings: Res<SimulationSettings>,
-// ) {
-//     if context.is_changed() || simulation_settings.is_changed() {
-//         match Simulator::new(&*context, &simulation_settings) {
-//             Ok(simulator) => {
-//                 commands.insert_resource(simulator);
-//             }
-//             _ => {}
-//         }
-//     }
-// }
-
-#[derive(Component)]
-pub struct Listener;
+/// Where the HRTF used for binaural rendering comes from.
+#[derive(Debug, Clone, Default)]
+pub enum HrtfSource {
+    /// Steam Audio's built in HRTF.
+    #[default]
+    Default,
+    /// A SOFA file on disk.
+    SofaFile(PathBuf),
+    /// The contents of a SOFA file.
+    SofaData(Vec<u8>),
+    /// A SOFA file loaded through the asset server into the [`HrtfAsset`], the default HRTF is
+    /// used until it has loaded.
+    Asset(String),
+}
 
-pub fn listener_update(
-    audio_resource: Res<SpatialAudioSettings>,
-    query: Query<&GlobalTransform, With<Listener>>,
-) {
-    for transform in query.iter() {
-        let flags = SimulationFlags::all();
-        let orientation = Orientation {
-            origin: transform.translation().into(),
-            right: transform.right().as_array(),
-            up: transform.up().as_array(),
-            ahead: transform.forward().to_array(),
-        };
+impl HrtfSource {
+    fn settings(&self) -> Result<HRTFSettings, String> {
+        match self {
+            Self::Default => Ok(HRTFSettings::default()),
+            Self::SofaFile(path) => std::fs::read(path)
+                .map(|data| HRTFSettings::sofa_data(&data))
+                .map_err(|err| format!("could not read {}: {err}", path.display())),
+            Self::SofaData(data) => Ok(HRTFSettings::sofa_data(data)),
+            Self::Asset(_) => Ok(HRTFSettings::default()),
+        }
+    }
+}
 
-        let shared_inputs = SimulationSharedInputs {
-            listener: orientation,
-            ..Default::default()
-        };
+/// Sent when the configured [`HrtfSource`] couldn't be loaded and the default HRTF is used
+/// instead.
+#[derive(Event, Debug, Clone)]
+pub struct HrtfFallback {
+    pub reason: String,
+}
 
-        audio_resource
-            .simulator
-            .set_shared_inputs(flags, &shared_inputs);
-    }
+pub struct SpatialAudioPlugin {
+    pub hrtf: HrtfSource,
+    pub max_voices: MaxVoices,
+    pub virtual_voice_threshold: VirtualVoiceThreshold,
+    pub reflections: ReflectionConfig,
+    /// Default binaural quality of new voices.
+    pub binaural: BinauralConfig,
+    pub warmup_blocks: WarmupBlocks,
+    pub tail_blocks: TailBlocks,
+    /// Decoders kept ready for [`PooledAudio`] voices.
+    pub source_pool: AudioSourcePool,
+    /// What players whose audio can't be read do instead of panicking.
+    pub audio_errors: AudioErrorPolicy,
+    pub pathing: PathingConfig,
+    /// Order of the bed every voice's reflections are decoded through.
+    pub ambisonics: AmbisonicsConfig,
+    /// Headphones or speakers, see [`OutputMode`].
+    pub output_mode: OutputMode,
+    /// Block size of the shared settings and every voice, trading latency for CPU.
+    pub frame_size: FrameSize,
+    /// Builds the simulator. Turn it off when only binaural rendering is needed, sources are
+    /// then attenuated by the distance models alone, without occlusion, reflections or pathing.
+    pub simulation_enabled: bool,
+    /// Direct simulations per second, see [`SimulationTickRate`].
+    pub simulation_tick_rate: SimulationTickRate,
+    /// Ray tracer of the scene and the simulator, falls back to Phonon with a
+    /// [`RayTracerFallback`] when it can't be initialized.
+    pub ray_tracer: RayTracerBackend,
+    /// Device of Radeon Rays and TrueAudio Next.
+    pub opencl: OpenClConfig,
 }
 
-pub struct AudioMesh {
-    pub vertices: Vec<Vec3>,
-    pub triangles: Vec<[u32; 3]>,
-    pub materials: Vec<steam_audio::prelude::Material>,
-    pub material_indices: Vec<u32>,
+impl Default for SpatialAudioPlugin {
+    fn default() -> Self {
+        Self {
+            hrtf: HrtfSource::default(),
+            max_voices: MaxVoices::default(),
+            virtual_voice_threshold: VirtualVoiceThreshold::default(),
+            reflections: ReflectionConfig::default(),
+            binaural: BinauralConfig::default(),
+            warmup_blocks: WarmupBlocks::default(),
+            tail_blocks: TailBlocks::default(),
+            source_pool: AudioSourcePool::default(),
+            audio_errors: AudioErrorPolicy::default(),
+            pathing: PathingConfig::default(),
+            ambisonics: AmbisonicsConfig::default(),
+            output_mode: OutputMode::default(),
+            frame_size: FrameSize::default(),
+            simulation_enabled: true,
+            simulation_tick_rate: SimulationTickRate::default(),
+            ray_tracer: RayTracerBackend::default(),
+            opencl: OpenClConfig::default(),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub enum AudioMeshError {
-    NoVertices,
-    NonTrianglePrimitiveTopology(PrimitiveTopology),
+impl SpatialAudioPlugin {
+    /// Traces the simulation on the GPU with Radeon Rays, on OpenCL device `device_index` or the
+    /// one in [`Self::opencl`] when `None`.
+    ///
+    /// Without the `radeon-rays` feature, or when the device can't be created, it simulates on
+    /// the CPU with a warning. [`GpuSimulationAvailable`] tells which one it ended up on.
+    pub fn with_gpu_simulation(mut self, device_index: Option<u32>) -> Self {
+        if let Some(device_index) = device_index {
+            self.opencl.device_index = device_index;
+        }
+        match RayTracerBackend::gpu() {
+            Some(backend) => self.ray_tracer = backend,
+            None => warn!("GPU simulation needs the `radeon-rays` feature, simulating on the CPU."),
+        }
+        self
+    }
 }
 
-impl TryFrom<Mesh> for AudioMesh {
-    type Error = AudioMeshError;
-    fn try_from(mesh: Mesh) -> Result<Self, Self::Error> {
-        let triangles = match mesh.indices() {
-            Some(indices) => {
-                let indices: Vec<_> = match indices {
-                    Indices::U16(indices) => {
-                        indices.iter().map(|indices| *indices as u32).collect()
-                    }
-                    Indices::U32(indices) => indices.iter().map(|indices| *indices).collect(),
-                };
+impl Plugin for SpatialAudioPlugin {
+    fn build(&self, app: &mut App) {
+        let audio_settings = self.frame_size.audio_settings();
+        let context_settings = ContextSettings::default();
+        let mut simulation_settings = SimulationSettings::from_audio_settings(&audio_settings);
+        self.reflections.apply(&mut simulation_settings);
+        self.pathing.apply(&mut simulation_settings);
 
-                match mesh.primitive_topology() {
-                    PrimitiveTopology::TriangleList => indices
-                        .chunks_exact(3)
-                        .map(|chunk| [chunk[0], chunk[1], chunk[2]])
-                        .collect(),
-                    PrimitiveTopology::TriangleStrip => {
-                        let mut indices: Vec<_> = indices
-                            .windows(3)
-                            .map(|indices| [indices[0], indices[1], indices[2]])
-                            .collect();
-
-                        for (index, indices) in indices.iter_mut().enumerate() {
-                            if (index + 1) % 2 == 0 {
-                                *indices = [indices[1], indices[0], indices[2]];
-                            }
-                        }
-
-                        indices
-                    }
-                    topology => return Err(AudioMeshError::NonTrianglePrimitiveTopology(topology)),
-                }
-            }
-            None => Vec::new(),
+        let context = Context::new(&context_settings).expect("could not build steam audio context");
+
+        let (ray_tracer, ray_tracer_fallback) =
+            RayTracer::new(&context, self.ray_tracer, &self.opencl);
+        ray_tracer.apply(&mut simulation_settings);
+        let true_audio_next = TrueAudioNext::new(
+            &context,
+            ray_tracer.opencl(),
+            &self.opencl,
+            &audio_settings,
+            &self.reflections,
+        );
+        if let Some(true_audio_next) = &true_audio_next {
+            true_audio_next.apply(&mut simulation_settings);
+        }
+        let info = SteamAudioInfo {
+            ray_tracer: ray_tracer.backend,
+            true_audio_next: true_audio_next.is_some(),
         };
+        let ray_tracer_fallback = ray_tracer_fallback.map(|reason| RayTracerFallback {
+            requested: self.ray_tracer,
+            reason,
+        });
 
-        let vertices = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
-            Some(positions) => match positions {
-                VertexAttributeValues::Float32x3(vertices) => {
-                    vertices.iter().map(|a| (*a).into()).collect()
-                }
-                _ => return Err(AudioMeshError::NoVertices),
-            },
-            _ => return Err(AudioMeshError::NoVertices),
+        let loaded = self.hrtf.settings().and_then(|hrtf_settings| {
+            HRTF::new(&context, &audio_settings, &hrtf_settings)
+                .map(|hrtf| (hrtf_settings, hrtf))
+                .map_err(|err| format!("could not build steam audio hrtf: {err:?}"))
+        });
+        let (hrtf_settings, hrtf, fallback) = match loaded {
+            Ok((hrtf_settings, hrtf)) => (hrtf_settings, hrtf, None),
+            Err(reason) => {
+                warn!("{reason}, falling back to the default HRTF.");
+                let hrtf_settings = HRTFSettings::default();
+                let hrtf = HRTF::new(&context, &audio_settings, &hrtf_settings)
+                    .expect("could not build steam audio hrtf");
+                (hrtf_settings, hrtf, Some(HrtfFallback { reason }))
+            }
         };
 
-        let materials = vec![steam_audio::materials::GENERIC];
-        let material_indices = triangles.iter().map(|_| 0 /* GENERIC index */).collect();
+        let simulator = self.simulation_enabled.then(|| {
+            Simulator::new(&context, &simulation_settings)
+                .expect("could not build steam audio simulation")
+        });
 
-        Ok(Self {
-            vertices: vertices,
-            triangles: triangles,
-            materials: materials,
-            material_indices: material_indices,
-        })
+        let scene = SteamAudioScene::new(&context, ray_tracer);
+
+        app.insert_resource(AudioConfig(audio_settings.clone()))
+            .insert_resource(ContextConfig(context_settings.clone()))
+            .insert_resource(HrtfConfig(hrtf_settings.clone()))
+            .insert_resource(SimulationConfig(simulation_settings.clone()))
+            .insert_resource(SpatialAudioSettings {
+                shared_hrtf: Arc::new(SharedHrtf::new(hrtf_settings.clone())),
+                listener_orientation: SharedParams::default(),
+                reverb_wet: SharedParams::new(1.0),
+                simulation_tick_rate: SharedParams::new(self.simulation_tick_rate.0),
+                output_mode: SharedParams::new(self.output_mode),
+                headphone_eq: SharedParams::new(HeadphoneEqPreset::Flat.gains()),
+                mixer: SharedParams::default(),
+                stats: Arc::default(),
+                decoder_pool: DecoderPool::default(),
+                true_audio_next,
+                ambisonics_bus: Arc::new(AmbisonicsBus::new(
+                    self.ambisonics.order(),
+                    audio_settings.frame_size() as usize,
+                )),
+                audio_settings,
+                context_settings,
+                hrtf_settings,
+                simulation_settings,
+                context,
+                hrtf,
+                simulator: simulator.map(Arc::new),
+            });
+
+        app.init_resource::<MaterialLibrary>()
+            .init_resource::<AudioSceneMesh>()
+            .init_resource::<AudioSceneState>()
+            .init_resource::<DopplerConfig>()
+            .init_resource::<VelocitySmoothingFactor>()
+            .init_resource::<ListenerVelocity>()
+            .init_resource::<ActiveListener>()
+            .init_resource::<GlobalAudioSettings>()
+            .init_resource::<AudioUnitsPerMeter>()
+            .init_resource::<VoiceCounts>()
+            .init_resource::<BusVolumes>()
+            .init_resource::<SteamAudioMixer>()
+            .init_resource::<TransmissionConfig>()
+            .init_resource::<ReflectionState>()
+            .init_resource::<DirectSimulationState>()
+            .init_resource::<ListenerReverbState>()
+            .init_resource::<SimulationSources>()
+            .init_resource::<BakeReflectionsTask>()
+            .init_resource::<ProbeBatches>()
+            .insert_resource(self.reflections)
+            .insert_resource(self.pathing)
+            .insert_resource(self.ambisonics)
+            .insert_resource(self.output_mode)
+            .insert_resource(self.binaural)
+            .insert_resource(scene)
+            .insert_resource(info)
+            .insert_resource(GpuSimulationAvailable(
+                RayTracerBackend::gpu() == Some(info.ray_tracer) || info.true_audio_next,
+            ))
+            .insert_resource(self.max_voices)
+            .insert_resource(self.virtual_voice_threshold)
+            .insert_resource(self.warmup_blocks)
+            .insert_resource(self.tail_blocks)
+            .insert_resource(self.source_pool)
+            .insert_resource(self.audio_errors)
+            .insert_resource(self.simulation_tick_rate)
+            .add_event::<HrtfFallback>()
+            .add_event::<RayTracerFallback>()
+            .add_event::<AudioSceneRebuilt>()
+            .add_event::<SpatialPlaybackStarted>()
+            .add_event::<SpatialPlaybackFinished>()
+            .add_event::<SpatialAudioError>()
+            .add_event::<BakeReflections>()
+            .add_event::<BakeProgress>()
+            .add_event::<BakeFinished>()
+            .add_systems(
+                PostUpdate,
+                (
+                    // Bevy plays queued audio after transform propagation.
+                    queue_voices.before(TransformSystem::TransformPropagate),
+                    reload_voices.before(queue_voices),
+                    playback_events,
+                    update_playback_position,
+                    (
+                        seek_voices,
+                        pause_voices,
+                        update_spatial_blend,
+                        update_source_mix,
+                        update_pitch,
+                        update_volume_scale,
+                        update_bus_volumes,
+                        update_fade_in,
+                        (update_binaural_config, update_near_field),
+                        update_doppler.after(listener_update),
+                        limit_voices,
+                        listener_update,
+                        update_source_params.after(listener_update),
+                        update_headphone_eq,
+                        update_output_mode,
+                        update_audible,
+                        update_transmission.after(extract_audio_scene),
+                        update_convolution_reverb,
+                        update_mixer,
+                    )
+                        .in_set(AudioSystemSet::UpdateInputs),
+                    extract_audio_scene.after(TransformSystem::TransformPropagate),
+                    (update_door_portals, update_portal_geometry)
+                        .chain()
+                        .before(commit_audio_scene)
+                        .after(TransformSystem::TransformPropagate),
+                    (
+                        rebuild_audio_scene,
+                        (
+                            register_audio_obstacles,
+                            remove_audio_obstacles,
+                            (register_dynamic_geometry, move_dynamic_geometry).chain(),
+                            remove_dynamic_geometry,
+                            update_audio_terrain,
+                        ),
+                        commit_audio_scene.run_if(simulation_enabled),
+                    )
+                        .chain()
+                        .after(TransformSystem::TransformPropagate),
+                    (add_simulation_sources, cleanup_simulation_sources)
+                        .after(queue_voices)
+                        .run_if(simulation_enabled),
+                    (
+                        bake_probe_volumes,
+                        load_baked_data.run_if(simulation_enabled),
+                    )
+                        .chain()
+                        .after(commit_audio_scene),
+                    (
+                        update_listener_reverb,
+                        update_simulation_inputs
+                            .after(load_baked_data)
+                            .run_if(simulation_enabled),
+                    )
+                        .chain()
+                        .in_set(AudioSystemSet::UpdateInputs),
+                    commit_simulation_sources
+                        .in_set(AudioSystemSet::CommitSimulation)
+                        .after(add_simulation_sources)
+                        .after(cleanup_simulation_sources)
+                        .after(commit_audio_scene)
+                        .run_if(simulation_enabled),
+                    // The direct models still attenuate voices without a simulator.
+                    (
+                        simulate_direct,
+                        simulate_reflections.run_if(simulation_enabled),
+                    )
+                        .chain()
+                        .in_set(AudioSystemSet::RunSimulation),
+                ),
+            )
+            .configure_sets(
+                PostUpdate,
+                (
+                    AudioSystemSet::UpdateInputs,
+                    AudioSystemSet::CommitSimulation,
+                    AudioSystemSet::RunSimulation,
+                )
+                    .chain()
+                    .after(TransformSystem::TransformPropagate)
+                    .after(queue_voices),
+            )
+            .add_audio_source::<AmbisonicsBed>()
+            .add_systems(Startup, spawn_ambisonics_bed)
+            .add_systems(
+                PreUpdate,
+                (
+                    primary_listener,
+                    update_units_per_meter,
+                    apply_environment_preset,
+                    (
+                        apply_sofa_hrtf,
+                        context_update,
+                        (hrtf_update, simulation_update),
+                        validate_reflection_config,
+                        fill_decoder_pool,
+                    )
+                        .chain(),
+                ),
+            );
+
+        #[cfg(any(feature = "rapier", feature = "avian"))]
+        app.add_systems(
+            PostUpdate,
+            crate::collider::remove_collider_obstacles.before(commit_audio_scene),
+        );
+        #[cfg(feature = "rapier")]
+        app.add_systems(
+            PostUpdate,
+            crate::collider::rapier::register_collider_obstacles
+                .before(commit_audio_scene)
+                .after(TransformSystem::TransformPropagate),
+        );
+        #[cfg(feature = "avian")]
+        app.add_systems(
+            PostUpdate,
+            crate::collider::avian::register_collider_obstacles
+                .before(commit_audio_scene)
+                .after(TransformSystem::TransformPropagate),
+        );
+
+        app.init_asset::<SofaHrtf>()
+            .init_asset_loader::<SofaHrtfLoader>()
+            .init_asset::<DistanceAttenuationCurve>()
+            .init_asset_loader::<DistanceAttenuationCurveLoader>()
+            .init_asset::<BakedDataAsset>()
+            .init_asset_loader::<BakedDataLoader>()
+            .init_asset::<ConvolutionReverbIR>()
+            .init_asset_loader::<ConvolutionReverbIRLoader>();
+
+        app.register_type::<Listener>()
+            .register_type::<PrimaryListener>()
+            .register_type::<GlobalAudioSettings>()
+            .register_type::<AudioUnitsPerMeter>()
+            .register_type::<DopplerConfig>()
+            .register_type::<VelocitySmoothingFactor>()
+            .register_type::<ListenerVelocity>()
+            .register_type::<crate::doppler::NoDoppler>()
+            .register_type::<crate::doppler::AudioVelocity>()
+            .register_type::<crate::pitch::PitchShift>()
+            .register_type::<crate::pitch::PitchVariance>()
+            .register_type::<crate::eq::HeadphoneEq>()
+            .register_type::<HeadphoneEqPreset>()
+            .register_type::<crate::attenuation::DistanceAttenuation>()
+            .register_type::<crate::attenuation::AttenuationCurveAsset>()
+            .register_type::<crate::attenuation::SourceRadius>()
+            .register_type::<crate::attenuation::AudioDirectivity>()
+            .register_type::<crate::attenuation::AirAbsorption>()
+            .register_type::<crate::attenuation::AirAbsorptionOverride>()
+            .register_type::<crate::settings::EnvironmentPreset>()
+            .register_type::<HrtfAsset>()
+            .register_type::<crate::volume::VolumeScale>()
+            .register_type::<crate::volume::AudioBus>()
+            .register_type::<crate::volume::BusVolumes>()
+            .register_type::<SteamAudioMixer>()
+            .register_type::<SteamAudioInfo>()
+            .register_type::<GpuSimulationAvailable>()
+            .register_type::<RayTracerBackend>()
+            .register_type::<crate::volume::FadeIn>()
+            .register_type::<crate::scene::AudioObstacle>()
+            .register_type::<crate::material::AudioMaterial>()
+            .register_type::<AudioSceneState>()
+            .register_type::<AudioTerrain>()
+            .register_type::<crate::scene::DynamicAudioGeometry>()
+            .register_type::<crate::occlusion::Occlusion>()
+            .register_type::<crate::convolution::ConvolutionReverbSend>()
+            .register_type::<crate::area::AudioArea>()
+            .register_type::<ListenerReverbState>()
+            .register_type::<PathingConfig>()
+            .register_type::<crate::pathing::PathingSource>()
+            .register_type::<crate::playback::SeekAudio>()
+            .register_type::<crate::playback::PauseAudio>()
+            .register_type::<crate::playback::PauseFadeFrames>()
+            .register_type::<WarmupBlocks>()
+            .register_type::<TailBlocks>()
+            .register_type::<AudioErrorPolicy>()
+            .register_type::<SimulationTickRate>()
+            .register_type::<crate::playback::KeepOnFinish>()
+            .register_type::<crate::playback::PlaybackPosition>()
+            .register_type::<AudioSourcePool>()
+            .register_type::<PooledAudio>()
+            .register_type::<MaxVoices>()
+            .register_type::<crate::virtual_voice::VirtualVoiceThreshold>()
+            .register_type::<crate::virtual_voice::SourcePriority>()
+            .register_type::<VoiceCounts>()
+            .register_type::<crate::portal::AudioPortal>()
+            .register_type::<crate::portal::DoorOpen>()
+            .register_type::<TransmissionConfig>()
+            .register_type::<crate::transmission::Transmission>()
+            .register_type::<crate::culling::MaxAudibleDistance>()
+            .register_type::<BinauralConfig>()
+            .register_type::<NearFieldCorrection>()
+            .register_type::<crate::ambisonics::AmbisonicsOrder>()
+            .register_type::<crate::ambisonics::AmbisonicsHrtf>()
+            .register_type::<AmbisonicsConfig>()
+            .register_type::<crate::mix::SpatialBlend>()
+            .register_type::<SourceMix>()
+            .register_type::<crate::probe::ProbeVolume>()
+            .register_type::<crate::probe::BakedReflections>()
+            .register_type::<crate::probe::BakedProbeVolume>()
+            .register_type::<ReflectionConfig>()
+            .register_type::<crate::chain::EffectChain>()
+            .register_type::<crate::chain::AudioEffect>();
+        #[cfg(any(feature = "rapier", feature = "avian"))]
+        app.register_type::<crate::collider::AudioFromCollider>();
+
+        let asset_folder = app
+            .get_added_plugins::<AssetPlugin>()
+            .first()
+            .map_or(ASSET_FOLDER.to_owned(), |plugin| plugin.file_path.clone());
+        app.register_asset_loader(SteamAudioLoader { asset_folder });
+        if let HrtfSource::Asset(path) = &self.hrtf {
+            let handle = app.world().resource::<AssetServer>().load(path.clone());
+            app.insert_resource(HrtfAsset(handle));
+        }
+
+        if let Some(fallback) = fallback {
+            app.world_mut().send_event(fallback);
+        }
+        if let Some(fallback) = ray_tracer_fallback {
+            app.world_mut().send_event(fallback);
+        }
     }
 }
 
-trait AsArray<const N: usize> {
-    fn as_array(self) -> [f32; N];
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Listener;
+
+/// Picks the [`Listener`] whose orientation reaches the simulator, which only supports one.
+///
+/// Added automatically to the first listener found when none is marked. Switch listeners with
+/// [`SpatialAudioCommands::set_primary_listener`](crate::playback::SpatialAudioCommands), the
+/// listener orientation is crossfaded over [`LISTENER_CROSSFADE`] so the switch doesn't pop.
+/// Only one listener is ever heard, mixing several for split screen isn't supported.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct PrimaryListener;
+
+pub fn primary_listener(
+    mut commands: Commands,
+    listeners: Query<Entity, With<Listener>>,
+    primary: Query<(), (With<Listener>, With<PrimaryListener>)>,
+) {
+    if primary.is_empty() {
+        if let Some(entity) = listeners.iter().next() {
+            commands.entity(entity).insert(PrimaryListener);
+        }
+    }
 }
 
-impl AsArray<3> for Dir3 {
-    fn as_array(self) -> [f32; 3] {
-        [self.x, self.y, self.z]
+/// Seconds the listener orientation takes to move over to a new [`PrimaryListener`].
+pub const LISTENER_CROSSFADE: f32 = 0.1;
+
+/// The [`PrimaryListener`] every system hears sources from, picked once a frame by
+/// [`listener_update`] so they all agree on it.
+///
+/// The orientation is the crossfaded one, so voices move over to a new listener with the
+/// simulator instead of jumping to it.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct ActiveListener {
+    pub entity: Option<Entity>,
+    pub orientation: SourceOrientation,
+}
+
+/// The listener [`listener_update`] last used, and the crossfade away from the one before it.
+#[derive(Default)]
+pub struct ListenerSwitch {
+    entity: Option<Entity>,
+    orientation: SourceOrientation,
+    /// The orientation faded away from and the seconds left, while crossfading.
+    fade: Option<(SourceOrientation, f32)>,
+    /// Where the listener was last frame, for [`ListenerVelocity`].
+    previous_position: Option<Vec3>,
+    warned_listeners: bool,
+    warned_primary: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn listener_update(
+    audio_resource: Res<SpatialAudioSettings>,
+    reflections: Res<ReflectionConfig>,
+    units: Res<AudioUnitsPerMeter>,
+    time: Res<Time>,
+    smoothing: Res<VelocitySmoothingFactor>,
+    mut listener_velocity: ResMut<ListenerVelocity>,
+    listeners: Query<(), With<Listener>>,
+    query: Query<
+        (Entity, &GlobalTransform, Option<&AudioVelocity>),
+        (With<Listener>, With<PrimaryListener>),
+    >,
+    mut active: ResMut<ActiveListener>,
+    mut switch: Local<ListenerSwitch>,
+) {
+    // The lowest entity wins so the choice doesn't depend on query order.
+    let listener = query.iter().min_by_key(|(entity, ..)| *entity);
+    let count = listeners.iter().count();
+    if count > 1 && !switch.warned_listeners {
+        warn!("{count} steam audio Listeners found, only the PrimaryListener is heard.");
+    }
+    switch.warned_listeners = count > 1;
+
+    let count = query.iter().count();
+    if count != 1 && !switch.warned_primary {
+        warn!("{count} steam audio PrimaryListeners found, exactly one should be marked.");
+    }
+    switch.warned_primary = count != 1;
+
+    if let Some((entity, transform, explicit_velocity)) = listener {
+        let flags = SimulationFlags::all();
+        let target = SourceOrientation::from(transform);
+
+        // A new listener starts from rest instead of moving across the gap to the old one.
+        if switch.entity.is_some_and(|previous| previous != entity) {
+            switch.fade = Some((switch.orientation, LISTENER_CROSSFADE));
+            switch.previous_position = None;
+            listener_velocity.0 = Vec3::ZERO;
+        }
+        switch.entity = Some(entity);
+
+        let position = transform.translation();
+        let delta = time.delta_secs();
+        let velocity = match (explicit_velocity, switch.previous_position) {
+            (Some(velocity), _) => velocity.0,
+            (None, Some(previous)) if delta > 0.0 => (position - previous) / delta,
+            _ => Vec3::ZERO,
+        };
+        switch.previous_position = Some(position);
+        listener_velocity.update(velocity, *smoothing);
+
+        let orientation = match &mut switch.fade {
+            Some((from, remaining)) => {
+                *remaining -= time.delta_secs();
+                let t = 1.0 - (*remaining / LISTENER_CROSSFADE).clamp(0.0, 1.0);
+                from.lerp(&target, t)
+            }
+            None => target,
+        };
+        if switch.fade.is_some_and(|(_, remaining)| remaining <= 0.0) {
+            switch.fade = None;
+        }
+        switch.orientation = orientation;
+
+        // The reflection settings ride along so the reflection task traces with them.
+        let shared_inputs = SimulationSharedInputs {
+            listener: orientation.to_phonon(*units),
+            ..reflections.shared_inputs(orientation, *units)
+        };
+        // Only bindings built with the extended inputs carry the listener's velocity.
+        #[cfg(feature = "extended-inputs")]
+        let shared_inputs = SimulationSharedInputs {
+            listener_velocity: bevy_velocity_to_phonon(listener_velocity.0, *units),
+            ..shared_inputs
+        };
+
+        if let Some(simulator) = &audio_resource.simulator {
+            simulator.set_shared_inputs(flags, &shared_inputs);
+        }
+        audio_resource.listener_orientation.store(orientation);
+        *active = ActiveListener {
+            entity: Some(entity),
+            orientation,
+        };
+    } else {
+        active.entity = None;
     }
 }
@@ -0,0 +1,184 @@
+use bevy::{
+    asset::{AssetEvent, Assets, Handle},
+    log::warn,
+    prelude::{
+        Changed, Component, Entity, EventReader, GlobalTransform, Image, Local, Or, Query, Reflect,
+        ReflectComponent, RemovedComponents, Res, ResMut, With,
+    },
+};
+
+use crate::{
+    geometry::{AudioSceneRebuilt, SteamAudioScene},
+    material::{AudioMaterial, MaterialLibrary},
+    mesh::{AudioMesh, AudioMeshError},
+};
+
+/// Where the heights of an [`AudioTerrain`] come from.
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum TerrainHeights {
+    /// The red channel of a heightmap, one sample per pixel, with fully transparent pixels left
+    /// out as holes. Load it with `is_srgb: false` so values aren't gamma decoded.
+    Image(Handle<Image>),
+    /// `rows` by `cols` heights in row-major order, NaN samples are holes.
+    Samples {
+        heights: Vec<f32>,
+        rows: u32,
+        cols: u32,
+    },
+}
+
+/// Adds a heightfield to the Steam Audio scene, for open terrain that has no render mesh worth
+/// converting.
+///
+/// The grid is centered on the entity, columns run along X and rows along Z. Uses the
+/// [`AudioMaterial`] of the entity like an [`AudioObstacle`](crate::scene::AudioObstacle).
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct AudioTerrain {
+    pub heights: TerrainHeights,
+    /// Distance between two samples.
+    pub cell_size: f32,
+    /// Multiplies every height, heightmap pixels range from 0 to 1.
+    pub height_scale: f32,
+    /// Only every `stride`th sample along each axis is kept, trading acoustic detail for
+    /// simulation time on large terrains.
+    pub stride: u32,
+}
+
+impl AudioTerrain {
+    pub fn from_image(image: Handle<Image>, cell_size: f32, height_scale: f32) -> Self {
+        Self {
+            heights: TerrainHeights::Image(image),
+            cell_size,
+            height_scale,
+            stride: 1,
+        }
+    }
+
+    pub fn from_samples(heights: Vec<f32>, rows: u32, cols: u32, cell_size: f32) -> Self {
+        Self {
+            heights: TerrainHeights::Samples {
+                heights,
+                rows,
+                cols,
+            },
+            cell_size,
+            height_scale: 1.0,
+            stride: 1,
+        }
+    }
+
+    pub fn with_stride(mut self, stride: u32) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// The decimated terrain in world space, `None` while the heightmap is loading.
+    pub fn audio_mesh(
+        &self,
+        images: &Assets<Image>,
+        transform: &GlobalTransform,
+    ) -> Option<Result<AudioMesh, AudioMeshError>> {
+        let (heights, rows, cols) = match &self.heights {
+            TerrainHeights::Image(handle) => {
+                let image = images.get(handle)?;
+                let (rows, cols) = (image.height(), image.width());
+                let heights = (0..rows)
+                    .flat_map(|y| (0..cols).map(move |x| (x, y)))
+                    .map(
+                        |(x, y)| match image.get_color_at(x, y).map(|c| c.to_linear()) {
+                            Ok(color) if color.alpha > 0.0 => color.red,
+                            _ => f32::NAN,
+                        },
+                    )
+                    .collect();
+                (heights, rows, cols)
+            }
+            TerrainHeights::Samples {
+                heights,
+                rows,
+                cols,
+            } => (heights.clone(), *rows, *cols),
+        };
+        let heights: Vec<f32> = heights
+            .into_iter()
+            .map(|height| height * self.height_scale)
+            .collect();
+
+        Some(
+            AudioMesh::from_heightfield_strided(
+                &heights,
+                rows as usize,
+                cols as usize,
+                self.cell_size,
+                self.stride as usize,
+            )
+            .map(|audio_mesh| audio_mesh.transformed(transform)),
+        )
+    }
+}
+
+/// Adds new and changed [`AudioTerrain`] to the scene, once their heightmap has loaded and again
+/// after the scene is rebuilt.
+///
+/// Terrain that can't be converted is logged and left out.
+#[allow(clippy::too_many_arguments)]
+pub fn update_audio_terrain(
+    mut scene: ResMut<SteamAudioScene>,
+    images: Res<Assets<Image>>,
+    library: Res<MaterialLibrary>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut rebuilt: EventReader<AudioSceneRebuilt>,
+    terrain: Query<(
+        Entity,
+        &AudioTerrain,
+        &GlobalTransform,
+        Option<&AudioMaterial>,
+    )>,
+    changed: Query<
+        (),
+        (
+            With<AudioTerrain>,
+            Or<(Changed<AudioTerrain>, Changed<GlobalTransform>)>,
+        ),
+    >,
+    mut removed: RemovedComponents<AudioTerrain>,
+    // Terrain whose heightmap hadn't loaded yet.
+    mut pending: Local<Vec<Entity>>,
+) {
+    let modified: Vec<_> = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let all = rebuilt.read().count() > 0;
+
+    let waiting = std::mem::take(&mut *pending);
+    for (entity, terrain, transform, material) in terrain.iter() {
+        let image_modified = matches!(
+            &terrain.heights,
+            TerrainHeights::Image(handle) if modified.contains(&handle.id())
+        );
+        if !(all || image_modified || changed.contains(entity) || waiting.contains(&entity)) {
+            continue;
+        }
+
+        match terrain.audio_mesh(&images, transform) {
+            Some(Ok(audio_mesh)) => {
+                let material = material.cloned().unwrap_or_default().resolve(&library);
+                scene.insert(entity, &audio_mesh.with_material(material));
+            }
+            Some(Err(err)) => {
+                warn!("Could not add audio terrain {entity:?} to the scene: {err}");
+                scene.remove(entity);
+            }
+            None => pending.push(entity),
+        }
+    }
+
+    for entity in removed.read() {
+        scene.remove(entity);
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::{
+    Component, GlobalTransform, Query, Reflect, ReflectComponent, ReflectResource, Res, Resource,
+    With,
+};
+
+use crate::{playback::SpatialAudioSource, scene::AudioSceneMesh, source::PrimaryListener};
+
+/// Whether sources without a [`Transmission`] component are muffled by the obstacles between
+/// them and the listener.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct TransmissionConfig {
+    pub enabled: bool,
+}
+
+impl Default for TransmissionConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Overrides [`TransmissionConfig::enabled`] for one source.
+///
+/// The occluded part of a source, see [`Occlusion`](crate::occlusion::Occlusion), isn't
+/// silenced, it's filtered by the 3-band transmission coefficients of the first
+/// [`AudioObstacle`](crate::scene::AudioObstacle) surface in the way.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct Transmission(pub bool);
+
+pub fn update_transmission(
+    config: Res<TransmissionConfig>,
+    scene_mesh: Res<AudioSceneMesh>,
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    sources: Query<(&SpatialAudioSource, &GlobalTransform, Option<&Transmission>)>,
+) {
+    let listener = listener.iter().next().map(GlobalTransform::translation);
+
+    for (source, transform, transmission) in sources.iter() {
+        let enabled = transmission.map_or(config.enabled, |transmission| transmission.0);
+        let bands = match (enabled, &scene_mesh.0, listener) {
+            (true, Some(mesh), Some(listener)) => mesh
+                .first_hit(transform.translation(), listener)
+                .map(|material| material.transmission),
+            _ => None,
+        };
+
+        source.voice.transmission.store(bands);
+    }
+}
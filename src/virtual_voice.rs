@@ -0,0 +1,107 @@
+use bevy::{
+    math::Vec3,
+    prelude::{
+        Component, Entity, GlobalTransform, Query, Reflect, ReflectComponent, ReflectResource, Res,
+        ResMut, Resource, With,
+    },
+};
+use std::sync::atomic::Ordering;
+
+use crate::{playback::SpatialAudioSource, source::PrimaryListener};
+
+/// The most voices that run the full effect chain at once, the rest are virtualized: they keep
+/// advancing through their source but output silence until a slot frees up.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct MaxVoices(pub usize);
+
+impl Default for MaxVoices {
+    fn default() -> Self {
+        Self(usize::MAX)
+    }
+}
+
+/// Voices quieter than this gain are virtualized too, however many voices are playing.
+///
+/// The gain is the voice's volume after distance attenuation and directivity, so far away
+/// ambient emitters cost next to nothing. They fade back in when they get loud enough again.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct VirtualVoiceThreshold(pub f32);
+
+impl Default for VirtualVoiceThreshold {
+    fn default() -> Self {
+        // -60 dB
+        Self(0.001)
+    }
+}
+
+/// Voices with a higher priority keep running when [`MaxVoices`] is exceeded, ties go to the
+/// voice closest to the listener.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[reflect(Component)]
+pub struct SourcePriority(pub u8);
+
+/// How many voices ran the effect chain or were virtualized during the last update.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct VoiceCounts {
+    pub active: usize,
+    pub virtualized: usize,
+}
+
+pub fn limit_voices(
+    max_voices: Res<MaxVoices>,
+    threshold: Res<VirtualVoiceThreshold>,
+    mut counts: ResMut<VoiceCounts>,
+    listener: Query<&GlobalTransform, With<PrimaryListener>>,
+    sources: Query<(
+        Entity,
+        &SpatialAudioSource,
+        Option<&SourcePriority>,
+        Option<&GlobalTransform>,
+    )>,
+) {
+    let listener = listener
+        .iter()
+        .next()
+        .map_or(Vec3::ZERO, GlobalTransform::translation);
+
+    let mut voices: Vec<_> = sources
+        .iter()
+        .filter(|(_, source, _, _)| !source.voice.finished.load(Ordering::Relaxed))
+        .map(|(entity, source, priority, transform)| {
+            let distance = transform.map_or(0.0, |transform| {
+                transform.translation().distance_squared(listener)
+            });
+            (
+                entity,
+                source,
+                priority.copied().unwrap_or_default(),
+                distance,
+            )
+        })
+        .collect();
+
+    voices.sort_by(|a, b| b.2.cmp(&a.2).then(a.3.total_cmp(&b.3)).then(a.0.cmp(&b.0)));
+
+    *counts = VoiceCounts::default();
+    // Quiet voices don't take up a slot.
+    let mut slots = max_voices.0;
+    for (_, source, _, _) in voices.iter() {
+        let virtualized = slots == 0 || source.voice.gain() < threshold.0;
+        if !virtualized {
+            slots -= 1;
+        }
+        source
+            .voice
+            .virtualized
+            .store(virtualized, Ordering::Relaxed);
+
+        if virtualized {
+            counts.virtualized += 1;
+        } else {
+            counts.active += 1;
+        }
+    }
+}
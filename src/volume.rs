@@ -0,0 +1,108 @@
+use bevy::{
+    audio::GlobalVolume,
+    prelude::{
+        Added, Changed, Component, DetectChanges, Or, Query, Ref, Reflect, ReflectComponent,
+        ReflectResource, Res, Resource,
+    },
+    utils::{Duration, HashMap},
+};
+use std::sync::atomic::Ordering;
+
+use crate::playback::SpatialAudioSource;
+
+/// Linear gain applied to a source before the direct effect, on top of bevy's `GlobalVolume` and
+/// the [`BusVolumes`] of its [`AudioBus`].
+///
+/// Negative values are treated as silence. Changes are ramped over the start of the next block
+/// so they don't click.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct VolumeScale(pub f32);
+
+impl Default for VolumeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The mixer group of a source, for volume sliders in a settings menu.
+///
+/// Sources without it are on the default bus, [`AudioBus::Sfx`].
+#[derive(Component, Reflect, Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub enum AudioBus {
+    #[default]
+    Sfx,
+    Music,
+    Voice,
+    Ambient,
+    Custom(String),
+}
+
+/// Linear gain of each [`AudioBus`], buses without an entry play at full volume.
+///
+/// Changes reach playing voices at their next block, ramped like [`VolumeScale`].
+#[derive(Resource, Reflect, Debug, Default, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct BusVolumes(pub HashMap<AudioBus, f32>);
+
+impl BusVolumes {
+    pub fn get(&self, bus: &AudioBus) -> f32 {
+        self.0.get(bus).copied().unwrap_or(1.0)
+    }
+
+    pub fn set(&mut self, bus: AudioBus, volume: f32) {
+        self.0.insert(bus, volume);
+    }
+}
+
+/// Ramps a source up from silence to its [`VolumeScale`] over the given time once it starts.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct FadeIn(pub Duration);
+
+pub fn update_volume_scale(
+    query: Query<
+        (&SpatialAudioSource, &VolumeScale),
+        Or<(Changed<VolumeScale>, Added<SpatialAudioSource>)>,
+    >,
+) {
+    for (source, volume) in query.iter() {
+        source.voice.volume.store(volume.0.max(0.0));
+    }
+}
+
+/// Applies [`BusVolumes`] and changes to bevy's `GlobalVolume` to playing voices.
+///
+/// Bevy only applies the `GlobalVolume` when a sound starts, so voices are scaled by how much it
+/// changed since theirs did.
+pub fn update_bus_volumes(
+    global: Res<GlobalVolume>,
+    buses: Res<BusVolumes>,
+    query: Query<(Ref<SpatialAudioSource>, Option<Ref<AudioBus>>)>,
+) {
+    let all = global.is_changed() || buses.is_changed();
+    for (source, bus) in query.iter() {
+        if !all && !source.is_added() && !bus.as_ref().is_some_and(|bus| bus.is_changed()) {
+            continue;
+        }
+
+        let bus = bus.map_or(buses.get(&AudioBus::default()), |bus| buses.get(&bus));
+        let started = source.voice.start_global_volume;
+        let global = if started > 0.0 {
+            global.volume.get() / started
+        } else {
+            1.0
+        };
+        source.voice.bus_volume.store((bus * global).max(0.0));
+    }
+}
+
+pub fn update_fade_in(query: Query<(&SpatialAudioSource, &FadeIn), Added<SpatialAudioSource>>) {
+    for (source, fade_in) in query.iter() {
+        source
+            .voice
+            .fade_in
+            .store(fade_in.0.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
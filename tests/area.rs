@@ -0,0 +1,45 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    area::{AudioArea, ListenerReverbState},
+    source::SpatialAudioPlugin,
+};
+
+fn spawn_area(app: &mut App, x: f32) -> Entity {
+    app.world_mut()
+        .spawn((
+            AudioArea {
+                reverb_ir: Handle::default(),
+                half_extents: Vec3::splat(2.0),
+                blend_radius: 2.0,
+                priority: 0,
+            },
+            Transform::from_xyz(x, 0.0, 0.0),
+        ))
+        .id()
+}
+
+#[test]
+fn listener_between_two_areas_is_partially_in_both() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    // A gap of 2 between the boxes, so the listener is halfway into each blend radius.
+    let left = spawn_area(&mut app, -3.0);
+    let right = spawn_area(&mut app, 3.0);
+    common::spawn_listener(&mut app, Transform::default());
+    app.update();
+    app.update();
+
+    let state = app.world().resource::<ListenerReverbState>();
+    let blends: Vec<_> = state.blends.iter().map(|(entity, _)| *entity).collect();
+    assert_eq!(blends.len(), 2);
+    assert!(blends.contains(&left) && blends.contains(&right));
+    for (_, share) in &state.blends {
+        assert!(*share > 0.0 && *share < 1.0, "blends {:?}", state.blends);
+    }
+    assert!(state.wet > 0.0 && state.wet < 1.0, "wet {}", state.wet);
+    let total: f32 = state.blends.iter().map(|(_, share)| share).sum();
+    assert!((total - state.wet).abs() < 1e-5);
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    probe::{
+        BakeFinished, BakeReflections, BakeReflectionsTask, BakeVariation, BakedDataAsset,
+        BakedProbeVolume, ProbeVolume,
+    },
+    reflections::ReflectionConfig,
+    scene::AudioObstacle,
+    source::SpatialAudioPlugin,
+};
+use std::time::Duration;
+
+#[test]
+fn baking_a_probe_volume_stores_its_data() {
+    let mut app = common::app(SpatialAudioPlugin {
+        reflections: ReflectionConfig {
+            enabled: true,
+            rays: 1024,
+            bounces: 2,
+            duration: 0.5,
+            ..default()
+        },
+        ..default()
+    });
+    // Probes are placed on the floor.
+    let floor = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Plane3d::new(Vec3::Y, Vec2::splat(10.0)));
+    app.world_mut()
+        .spawn((Mesh3d(floor), Transform::default(), AudioObstacle));
+    let volume = app
+        .world_mut()
+        .spawn((
+            ProbeVolume {
+                half_extents: Vec3::new(3.0, 2.0, 3.0),
+                spacing: 2.0,
+            },
+            Transform::default(),
+        ))
+        .id();
+    app.update();
+
+    app.world_mut().send_event(BakeReflections {
+        volume,
+        variation: BakeVariation::default(),
+    });
+
+    let mut finished = Vec::new();
+    for _ in 0..6000 {
+        app.update();
+        finished.extend(common::events::<BakeFinished>(&app));
+        if !finished.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let [BakeFinished {
+        volume: baked,
+        succeeded,
+    }] = finished[..]
+    else {
+        panic!("bake didn't finish once: {finished:?}");
+    };
+    assert_eq!(baked, volume);
+    assert!(succeeded);
+    assert_eq!(
+        app.world().resource::<BakeReflectionsTask>().progress(),
+        1.0
+    );
+
+    app.update();
+    let handle = &app.world().get::<BakedProbeVolume>(volume).unwrap().0;
+    let asset = app
+        .world()
+        .resource::<Assets<BakedDataAsset>>()
+        .get(handle)
+        .unwrap();
+    assert!(!asset.data.is_empty());
+}
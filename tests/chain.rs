@@ -0,0 +1,50 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    chain::{AudioEffect, EffectChain},
+    eq::{HeadphoneEq, HeadphoneEqPreset},
+    source::SpatialAudioPlugin,
+};
+
+/// The frames of a tone ahead and to the right rendered through `chain`, heard by a listener
+/// with in-ear headphone EQ.
+fn render_chain(chain: Vec<AudioEffect>) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let listener = common::spawn_listener(&mut app, Transform::default());
+    app.world_mut().entity_mut(listener).insert(HeadphoneEq {
+        preset: HeadphoneEqPreset::InEar,
+    });
+    let handle = app
+        .world_mut()
+        .resource_mut::<Assets<_>>()
+        .add(common::tone(1.0));
+    let entity = app
+        .world_mut()
+        .spawn((
+            AudioPlayer(handle),
+            PlaybackSettings::LOOP,
+            Transform::from_xyz(2.0, 0.0, -2.0),
+            EffectChain(chain),
+        ))
+        .id();
+    app.update();
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192)
+}
+
+#[test]
+fn reversing_the_stages_changes_the_output() {
+    let chain = vec![AudioEffect::Direct, AudioEffect::Binaural, AudioEffect::Eq];
+    let forward = render_chain(chain.clone());
+    assert!(common::channel_rms(&forward).iter().all(|rms| *rms > 0.0));
+
+    // Headphone EQ only runs on stereo, so before the binaural stage it's skipped.
+    let reversed = render_chain(chain.into_iter().rev().collect());
+    assert!(common::channel_rms(&reversed).iter().all(|rms| *rms > 0.0));
+    assert_ne!(forward, reversed);
+}
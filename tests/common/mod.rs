@@ -0,0 +1,112 @@
+//! Helpers shared by the integration tests. They render through Steam Audio, so they only run
+//! with the `native-tests` feature: `cargo test --features native-tests`.
+#![allow(dead_code)]
+
+use bevy::{
+    audio::{Decodable, GlobalVolume},
+    ecs::event::Events,
+    prelude::*,
+};
+use bevy_steam_audio::source::{Listener, SpatialAudioPlugin, SteamAudio, SteamDecoder};
+
+/// Sampling rate of the test tones, the rate voices render at.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// A headless app with `plugin` and no output device. Nothing plays on its own, tests pull the
+/// samples of a voice from [`decoder`].
+pub fn app(plugin: SpatialAudioPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin))
+        .init_asset::<SteamAudio>()
+        .init_asset::<Mesh>()
+        .init_resource::<GlobalVolume>()
+        .add_plugins(plugin);
+    app
+}
+
+/// A mono sine tone at 440 Hz lasting `seconds`.
+pub fn tone(seconds: f32) -> SteamAudio {
+    SteamAudio::from_samples(tone_samples(seconds), SAMPLE_RATE, 1)
+}
+
+/// The samples of [`tone`].
+pub fn tone_samples(seconds: f32) -> Vec<f32> {
+    let len = (seconds * SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|index| {
+            let time = index as f32 / SAMPLE_RATE as f32;
+            (time * 440.0 * std::f32::consts::TAU).sin() * 0.5
+        })
+        .collect()
+}
+
+pub fn spawn_listener(app: &mut App, transform: Transform) -> Entity {
+    app.world_mut().spawn((Listener, transform)).id()
+}
+
+/// Spawns a player of `audio` at `transform` and runs a frame so its voice is queued.
+pub fn play(
+    app: &mut App,
+    audio: SteamAudio,
+    transform: Transform,
+    settings: PlaybackSettings,
+) -> Entity {
+    let handle = app
+        .world_mut()
+        .resource_mut::<Assets<SteamAudio>>()
+        .add(audio);
+    let entity = app
+        .world_mut()
+        .spawn((AudioPlayer(handle), settings, transform))
+        .id();
+    app.update();
+    entity
+}
+
+/// Runs frames for `seconds` of real time, so simulation ticks are due and their runs on the
+/// task pool finish.
+pub fn run_for(app: &mut App, seconds: f32) {
+    let until = std::time::Instant::now() + std::time::Duration::from_secs_f32(seconds);
+    while std::time::Instant::now() < until {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        app.update();
+    }
+}
+
+/// The decoder bevy would play for the `AudioPlayer<SteamAudio>` of `entity`.
+pub fn decoder(app: &App, entity: Entity) -> SteamDecoder {
+    let player = app
+        .world()
+        .get::<AudioPlayer<SteamAudio>>(entity)
+        .expect("entity has no AudioPlayer<SteamAudio>");
+    app.world()
+        .resource::<Assets<SteamAudio>>()
+        .get(&player.0)
+        .expect("audio isn't loaded")
+        .decoder()
+}
+
+/// Up to `frames` stereo frames of `decoder`, fewer once it ends.
+pub fn render(decoder: &mut SteamDecoder, frames: usize) -> Vec<[f32; 2]> {
+    (0..frames)
+        .map_while(|_| Some([decoder.next()?, decoder.next()?]))
+        .collect()
+}
+
+pub fn rms(samples: impl IntoIterator<Item = f32>) -> f32 {
+    let (sum, count) = samples.into_iter().fold((0.0, 0), |(sum, count), sample| {
+        (sum + sample * sample, count + 1)
+    });
+    (sum / count.max(1) as f32).sqrt()
+}
+
+/// The RMS of the left and right channel.
+pub fn channel_rms(frames: &[[f32; 2]]) -> [f32; 2] {
+    [0, 1].map(|channel| rms(frames.iter().map(|frame| frame[channel])))
+}
+
+/// Every `E` sent in the last two frames.
+pub fn events<E: Event + Clone>(app: &App) -> Vec<E> {
+    let events = app.world().resource::<Events<E>>();
+    events.get_cursor().read(events).cloned().collect()
+}
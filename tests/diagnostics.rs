@@ -0,0 +1,95 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore},
+    prelude::*,
+};
+use bevy_steam_audio::{diagnostics::SteamAudioDiagnosticsPlugin, source::SpatialAudioPlugin};
+use std::time::Duration;
+
+fn app() -> App {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.add_plugins(SteamAudioDiagnosticsPlugin);
+    common::spawn_listener(&mut app, Transform::default());
+    app
+}
+
+fn measurement(app: &App, path: &DiagnosticPath) -> Option<f64> {
+    app.world()
+        .resource::<DiagnosticsStore>()
+        .get(path)
+        .unwrap_or_else(|| panic!("{path} isn't registered"))
+        .value()
+}
+
+#[test]
+fn simulation_time_is_measured_after_a_tick() {
+    let mut app = app();
+    common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+
+    // Ticks follow real time, the simulation runs on another thread.
+    for _ in 0..100 {
+        if measurement(&app, &SteamAudioDiagnosticsPlugin::SIM_TIME).is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        app.update();
+    }
+
+    let sim_time = measurement(&app, &SteamAudioDiagnosticsPlugin::SIM_TIME);
+    assert!(sim_time.is_some_and(|us| us > 0.0), "{sim_time:?}");
+    assert_eq!(
+        measurement(&app, &SteamAudioDiagnosticsPlugin::ACTIVE_SOURCES),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn block_timings_are_measured_after_playing_a_clip() {
+    let mut app = app();
+    for path in [
+        SteamAudioDiagnosticsPlugin::ACTIVE_VOICES,
+        SteamAudioDiagnosticsPlugin::VIRTUAL_VOICES,
+        SteamAudioDiagnosticsPlugin::ACTIVE_SOURCES,
+        SteamAudioDiagnosticsPlugin::SIM_TIME,
+        SteamAudioDiagnosticsPlugin::BLOCKS_PER_SECOND,
+        SteamAudioDiagnosticsPlugin::BLOCK_TIME,
+        SteamAudioDiagnosticsPlugin::UNDERRUNS,
+        SteamAudioDiagnosticsPlugin::REFLECTION_LATENCY,
+    ] {
+        assert!(
+            app.world()
+                .resource::<DiagnosticsStore>()
+                .get(&path)
+                .is_some(),
+            "{path} isn't registered"
+        );
+    }
+
+    let entity = common::play(
+        &mut app,
+        common::tone(0.25),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+    let mut decoder = common::decoder(&app, entity);
+    assert!(!common::render(&mut decoder, 4096).is_empty());
+    std::thread::sleep(Duration::from_millis(10));
+    app.update();
+
+    let block_time = measurement(&app, &SteamAudioDiagnosticsPlugin::BLOCK_TIME);
+    assert!(block_time.is_some_and(|us| us > 0.0), "{block_time:?}");
+    let blocks = measurement(&app, &SteamAudioDiagnosticsPlugin::BLOCKS_PER_SECOND);
+    assert!(blocks.is_some_and(|rate| rate > 0.0), "{blocks:?}");
+    assert_eq!(
+        measurement(&app, &SteamAudioDiagnosticsPlugin::ACTIVE_VOICES),
+        Some(1.0)
+    );
+}
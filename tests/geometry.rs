@@ -0,0 +1,241 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::{prelude::*, state::app::StatesPlugin};
+use bevy_steam_audio::{
+    geometry::{AudioSceneRebuilt, AudioSceneReloadPlugin, AudioSceneState, SteamAudioScene},
+    material::AudioMaterial,
+    occlusion::{Occlusion, OcclusionMode},
+    portal::{AudioPortal, DoorOpen},
+    scene::{AudioObstacle, AudioSceneMesh},
+    simulation::SimulationSource,
+    source::SpatialAudioPlugin,
+};
+use steam_audio::prelude::SimulationFlags;
+
+/// Spawns `mesh` at `transform` as an [`AudioObstacle`].
+fn spawn_obstacle(app: &mut App, mesh: Mesh, transform: Transform) -> Entity {
+    let mesh = app.world_mut().resource_mut::<Assets<Mesh>>().add(mesh);
+    app.world_mut()
+        .spawn((Mesh3d(mesh), transform, AudioObstacle))
+        .id()
+}
+
+/// Static meshes in the scene, and triangles in the merged scene mesh.
+fn counts(app: &App) -> (usize, usize) {
+    let triangles = app
+        .world()
+        .resource::<AudioSceneMesh>()
+        .0
+        .as_ref()
+        .map_or(0, |mesh| mesh.triangles.len());
+    (app.world().resource::<SteamAudioScene>().len(), triangles)
+}
+
+#[test]
+fn obstacles_are_added_to_and_removed_from_the_scene() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.update();
+    assert_eq!(counts(&app), (0, 0));
+
+    let cuboid = spawn_obstacle(&mut app, Cuboid::default().into(), Transform::default());
+    app.update();
+    assert_eq!(counts(&app), (1, 12));
+
+    spawn_obstacle(
+        &mut app,
+        Plane3d::default().into(),
+        Transform::from_xyz(0.0, -1.0, 0.0),
+    );
+    app.update();
+    assert_eq!(counts(&app), (2, 14));
+
+    app.world_mut().entity_mut(cuboid).remove::<AudioObstacle>();
+    app.update();
+    assert_eq!(counts(&app), (1, 2));
+}
+
+/// The RMS of a tone 4 units ahead of the listener with a 4 by 4 portal halfway.
+fn rms_through_portal(open: bool) -> f32 {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let portal = app
+        .world_mut()
+        .spawn((
+            AudioPortal {
+                open: true,
+                width: 4.0,
+                height: 4.0,
+            },
+            Transform::from_xyz(0.0, 0.0, -2.0),
+        ))
+        .id();
+    if !open {
+        app.world_mut().entity_mut(portal).insert(DoorOpen);
+        app.update();
+        app.world_mut().entity_mut(portal).remove::<DoorOpen>();
+    }
+
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -4.0),
+        PlaybackSettings::LOOP,
+    );
+    app.world_mut().entity_mut(entity).insert(Occlusion {
+        smoothing: 0.0,
+        ..default()
+    });
+    common::run_for(&mut app, 0.5);
+    assert_eq!(app.world().get::<AudioPortal>(portal).unwrap().open, open);
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    common::rms(frames[4096..].iter().map(|[left, right]| left + right))
+}
+
+#[test]
+fn closing_a_portal_makes_it_quieter() {
+    let open = rms_through_portal(true);
+    let closed = rms_through_portal(false);
+    assert!(open > 0.0);
+    assert!(closed < open * 0.5, "open {open}, closed {closed}");
+}
+
+/// The occlusion simulated for a source 4 units ahead with `mode`, behind a thin pillar halfway.
+fn occlusion_behind_a_pillar(mode: OcclusionMode) -> f32 {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    spawn_obstacle(
+        &mut app,
+        Cuboid::new(0.3, 4.0, 0.3).into(),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+    );
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -4.0),
+        PlaybackSettings::LOOP,
+    );
+    app.world_mut().entity_mut(entity).insert(Occlusion {
+        mode,
+        smoothing: 0.0,
+    });
+    common::run_for(&mut app, 0.5);
+
+    let source = app.world().get::<SimulationSource>(entity).unwrap();
+    source
+        .source()
+        .get_outputs(SimulationFlags::DIRECT)
+        .direct
+        .occlusion
+}
+
+#[test]
+fn thin_pillars_partially_occlude_volumetric_sources() {
+    let volumetric = occlusion_behind_a_pillar(OcclusionMode::default());
+    assert!(
+        volumetric > 0.0 && volumetric < 1.0,
+        "volumetric occlusion {volumetric}"
+    );
+
+    // The single ray is blocked outright.
+    let raycast = occlusion_behind_a_pillar(OcclusionMode::Raycast);
+    assert_eq!(raycast, 0.0);
+}
+
+/// The transmission bands of a wall of `material` between the listener and a tone 4 units ahead,
+/// and the RMS of the tone heard through it.
+fn heard_through_a_wall(material: AudioMaterial) -> ([f32; 3], f32) {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let wall = spawn_obstacle(
+        &mut app,
+        Cuboid::new(6.0, 6.0, 0.2).into(),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+    );
+    app.world_mut().entity_mut(wall).insert(material);
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -4.0),
+        PlaybackSettings::LOOP,
+    );
+    app.world_mut().entity_mut(entity).insert(Occlusion {
+        smoothing: 0.0,
+        ..default()
+    });
+    common::run_for(&mut app, 0.5);
+
+    let transmission = app
+        .world()
+        .resource::<AudioSceneMesh>()
+        .0
+        .as_ref()
+        .and_then(|mesh| mesh.first_hit(Vec3::new(0.0, 0.0, -4.0), Vec3::ZERO))
+        .expect("the wall is in the way")
+        .transmission;
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    let rms = common::rms(frames[4096..].iter().map(|[left, right]| left + right));
+    (transmission, rms)
+}
+
+#[test]
+fn glass_transmits_more_than_concrete() {
+    let (glass, through_glass) = heard_through_a_wall(AudioMaterial::Glass);
+    let (concrete, through_concrete) = heard_through_a_wall(AudioMaterial::Concrete);
+
+    assert_ne!(glass[2], concrete[2], "high band transmission");
+    assert!(glass[2] > concrete[2]);
+    assert!(
+        through_glass > through_concrete,
+        "{through_glass} through glass, {through_concrete} through concrete"
+    );
+}
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Level {
+    #[default]
+    First,
+    Second,
+}
+
+#[test]
+fn loading_a_level_rebuilds_the_scene_without_the_old_one() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.add_plugins((StatesPlugin, AudioSceneReloadPlugin::<Level>::default()))
+        .init_state::<Level>()
+        .enable_state_scoped_entities::<Level>();
+    let old = spawn_obstacle(&mut app, Cuboid::default().into(), Transform::default());
+    app.world_mut()
+        .entity_mut(old)
+        .insert(StateScoped(Level::First));
+    app.update();
+    assert_eq!(counts(&app), (1, 12));
+
+    app.world_mut()
+        .resource_mut::<NextState<Level>>()
+        .set(Level::Second);
+    let new = spawn_obstacle(
+        &mut app,
+        Plane3d::default().into(),
+        Transform::from_xyz(0.0, -1.0, 0.0),
+    );
+    app.world_mut()
+        .entity_mut(new)
+        .insert(StateScoped(Level::Second));
+    app.update();
+
+    assert!(app.world().get_entity(old).is_err());
+    assert_eq!(
+        common::events::<AudioSceneRebuilt>(&app),
+        [AudioSceneRebuilt { obstacles: 1 }]
+    );
+    assert_eq!(counts(&app), (1, 2));
+    assert_eq!(
+        *app.world().resource::<AudioSceneState>(),
+        AudioSceneState::Clean
+    );
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::source::{HrtfFallback, HrtfSource, SpatialAudioPlugin};
+use std::path::PathBuf;
+
+/// The fallbacks sent while building the plugin with `hrtf`, and the channel RMS of a tone to
+/// the right rendered through the HRTF that was built.
+fn build_with(hrtf: HrtfSource) -> (Vec<HrtfFallback>, [f32; 2]) {
+    let mut app = common::app(SpatialAudioPlugin { hrtf, ..default() });
+    // Sent while building, before the first update.
+    let fallbacks = common::events::<HrtfFallback>(&app);
+
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(3.0, 0.0, 0.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    let rms = common::channel_rms(&common::render(&mut decoder, 8192));
+    (fallbacks, rms)
+}
+
+#[test]
+#[ignore = "needs a SOFA file, point BEVY_STEAM_AUDIO_TEST_SOFA at one"]
+fn sofa_files_build_an_hrtf() {
+    let path = PathBuf::from(
+        std::env::var_os("BEVY_STEAM_AUDIO_TEST_SOFA").expect("BEVY_STEAM_AUDIO_TEST_SOFA"),
+    );
+    let data = std::fs::read(&path).unwrap();
+
+    for hrtf in [HrtfSource::SofaFile(path), HrtfSource::SofaData(data)] {
+        let (fallbacks, rms) = build_with(hrtf);
+        assert!(fallbacks.is_empty(), "fell back: {fallbacks:?}");
+        assert!(rms[1] > rms[0], "heard {rms:?}");
+    }
+}
+
+#[test]
+fn unreadable_sofa_files_fall_back_to_the_default_hrtf() {
+    for hrtf in [
+        HrtfSource::SofaFile(PathBuf::from("does/not/exist.sofa")),
+        HrtfSource::SofaData(b"not a sofa file".to_vec()),
+    ] {
+        let (fallbacks, rms) = build_with(hrtf);
+        assert_eq!(fallbacks.len(), 1, "fell back: {fallbacks:?}");
+        assert!(rms[1] > rms[0] * 2.0, "heard {rms:?}");
+    }
+}
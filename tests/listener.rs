@@ -0,0 +1,219 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::{
+    ecs::schedule::ExecutorKind,
+    log::tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry, Layer,
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::tracing::{
+        self,
+        field::{Field, Visit},
+        Level, Subscriber,
+    },
+};
+use bevy_steam_audio::{
+    eq::{HeadphoneEq, HeadphoneEqPreset},
+    source::{PrimaryListener, SpatialAudioPlugin, LISTENER_CROSSFADE},
+};
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    fmt::{Debug, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The channel RMS of a tone 3 units along +X, heard by whichever listener is primary.
+fn tone_on_the_right(app: &mut App) -> [f32; 2] {
+    let entity = common::play(
+        app,
+        common::tone(1.0),
+        Transform::from_xyz(3.0, 0.0, 0.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    let mut decoder = common::decoder(app, entity);
+    common::channel_rms(&common::render(&mut decoder, 8192))
+}
+
+#[test]
+fn first_listener_becomes_primary() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let first = common::spawn_listener(&mut app, Transform::default());
+    // Turned around, it would hear the tone on its left.
+    let second = common::spawn_listener(
+        &mut app,
+        Transform::from_rotation(Quat::from_rotation_y(PI)),
+    );
+
+    let rms = tone_on_the_right(&mut app);
+    assert!(app.world().get::<PrimaryListener>(first).is_some());
+    assert!(app.world().get::<PrimaryListener>(second).is_none());
+    assert!(rms[1] > rms[0] * 2.0, "heard {rms:?}");
+}
+
+/// Collects the messages of every warning logged while it's the subscriber.
+#[derive(Clone, Default)]
+struct Warnings(Arc<Mutex<Vec<String>>>);
+
+impl<S: Subscriber> Layer<S> for Warnings {
+    fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+        struct Message(String);
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                if field.name() == "message" {
+                    let _ = write!(self.0, "{value:?}");
+                }
+            }
+        }
+
+        if *event.metadata().level() == Level::WARN {
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            self.0.lock().unwrap().push(message.0);
+        }
+    }
+}
+
+/// The warnings logged by `frames` updates of `app`.
+fn warnings(app: &mut App, frames: usize) -> Vec<String> {
+    // On this thread so the scoped subscriber sees the systems log.
+    app.edit_schedule(PostUpdate, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+    let warnings = Warnings::default();
+    tracing::subscriber::with_default(registry().with(warnings.clone()), || {
+        for _ in 0..frames {
+            app.update();
+        }
+    });
+    let messages = warnings.0.lock().unwrap().clone();
+    messages
+}
+
+#[test]
+fn a_second_listener_is_warned_about_once() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    assert!(warnings(&mut app, 2)
+        .iter()
+        .all(|message| !message.contains("steam audio Listeners found")));
+
+    common::spawn_listener(&mut app, Transform::from_xyz(1.0, 0.0, 0.0));
+    let messages = warnings(&mut app, 3);
+    let listeners: Vec<_> = messages
+        .iter()
+        .filter(|message| message.contains("steam audio Listeners found"))
+        .collect();
+    // Only the one the plugin marked is primary, so that warning stays quiet.
+    assert_eq!(
+        listeners,
+        ["2 steam audio Listeners found, only the PrimaryListener is heard."],
+        "{messages:?}"
+    );
+}
+
+#[test]
+fn only_one_of_several_primary_listeners_is_heard() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let turned = Transform::from_rotation(Quat::from_rotation_y(PI));
+    for transform in [Transform::default(), turned] {
+        let listener = common::spawn_listener(&mut app, transform);
+        app.world_mut().entity_mut(listener).insert(PrimaryListener);
+    }
+
+    // Warned about, the lowest entity is used for both the orientation and the directions.
+    let rms = tone_on_the_right(&mut app);
+    assert!(rms[1] > rms[0] * 2.0, "heard {rms:?}");
+}
+
+#[test]
+fn switching_listeners_turns_the_direction_gradually() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        LISTENER_CROSSFADE / 4.0,
+    )));
+    let first = common::spawn_listener(&mut app, Transform::default());
+    // Facing +X, the tone that is on the right of the first listener is straight ahead.
+    let second = common::spawn_listener(
+        &mut app,
+        Transform::from_rotation(Quat::from_rotation_y(-FRAC_PI_2)),
+    );
+    let entity = common::play(
+        &mut app,
+        common::tone(2.0),
+        Transform::from_xyz(3.0, 0.0, 0.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    // Share of the right channel, from the second block so the effects settled on the params.
+    let mut decoder = common::decoder(&app, entity);
+    let mut balance = || {
+        let [left, right] = common::channel_rms(&common::render(&mut decoder, 2048)[1024..]);
+        right / (left + right)
+    };
+    balance();
+    let before = balance();
+
+    app.world_mut()
+        .entity_mut(first)
+        .remove::<PrimaryListener>();
+    app.world_mut().entity_mut(second).insert(PrimaryListener);
+    let balances: Vec<f32> = (0..6)
+        .map(|_| {
+            app.update();
+            balance()
+        })
+        .collect();
+
+    let after = balances[5];
+    assert!(before > after + 0.1, "{before} then {balances:?}");
+    // A quarter of the way over after the first frame, all the way once the crossfade is done.
+    let margin = (before - after) * 0.1;
+    assert!(before - margin > balances[0] && balances[0] > after + margin);
+    for pair in balances.windows(2) {
+        assert!(pair[0] >= pair[1] - 1e-3, "{before} then {balances:?}");
+    }
+}
+
+/// The frames of a tone ahead and to the right, heard by a listener with `components`.
+fn heard_by(components: impl Bundle) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let listener = common::spawn_listener(&mut app, Transform::default());
+    app.world_mut().entity_mut(listener).insert(components);
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192)
+}
+
+#[test]
+fn flat_headphone_eq_leaves_the_output_untouched() {
+    let reference = heard_by(());
+    assert!(common::rms(reference.iter().map(|[left, _]| *left)) > 0.0);
+
+    for preset in [
+        HeadphoneEqPreset::Flat,
+        HeadphoneEqPreset::Custom([1.0, 1.0, 1.0]),
+    ] {
+        assert_eq!(heard_by(HeadphoneEq { preset }), reference, "{preset:?}");
+    }
+    assert_ne!(
+        heard_by(HeadphoneEq {
+            preset: HeadphoneEqPreset::InEar
+        }),
+        reference
+    );
+}
@@ -0,0 +1,251 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::{audio::Decodable, prelude::*};
+use bevy_steam_audio::{
+    ambisonics::AmbisonicsBed,
+    convolution::{ConvolutionReverbIR, ConvolutionReverbSend},
+    mix::{SourceMix, SpatialBlend, SteamAudioMixer},
+    reflections::ReflectionConfig,
+    scene::AudioObstacle,
+    settings::FrameSize,
+    source::SpatialAudioPlugin,
+    volume::{AudioBus, BusVolumes, VolumeScale},
+};
+
+/// A looping tone at `transform` with `components` added to it, ready to render.
+fn play_with(app: &mut App, transform: Transform, components: impl Bundle) -> Entity {
+    let entity = common::play(app, common::tone(1.0), transform, PlaybackSettings::LOOP);
+    app.world_mut().entity_mut(entity).insert(components);
+    app.update();
+    entity
+}
+
+/// The frames of a looping tone ahead and to the right with `components`, in an app of its own.
+fn render_with(components: impl Bundle) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(&mut app, Transform::from_xyz(2.0, 0.0, -2.0), components);
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192)
+}
+
+/// Asserts every sample of `frames` is `gain` times the one in `reference`, past the first
+/// block where volume changes are ramped.
+fn assert_scaled(frames: &[[f32; 2]], reference: &[[f32; 2]], gain: f32) {
+    assert_eq!(frames.len(), reference.len());
+    assert!(common::rms(reference.iter().map(|[left, _]| *left)) > 0.0);
+    for (frame, reference) in frames.iter().zip(reference).skip(4096) {
+        for channel in 0..2 {
+            assert!(
+                (frame[channel] - reference[channel] * gain).abs() < 1e-5,
+                "{frame:?} isn't {gain} times {reference:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn unspatialized_sources_play_the_same_in_both_ears() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(
+        &mut app,
+        Transform::from_xyz(3.0, 0.0, -1.0),
+        SpatialBlend(0.0),
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    assert!(common::rms(frames.iter().map(|[left, _]| *left)) > 0.0);
+    for [left, right] in frames {
+        assert_eq!(left, right);
+    }
+}
+
+#[test]
+fn volume_scale_multiplies_every_sample() {
+    let full = render_with(VolumeScale(1.0));
+    let half = render_with(VolumeScale(0.5));
+    assert_scaled(&half, &full, 0.5);
+}
+
+#[test]
+fn dry_bypass_plays_the_decoded_samples() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(
+        &mut app,
+        Transform::from_xyz(3.0, 0.0, -1.0),
+        SourceMix {
+            dry_bypass: 1.0,
+            ..default()
+        },
+    );
+
+    let samples = common::tone_samples(1.0);
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, samples.len());
+    assert_eq!(frames.len(), samples.len());
+    for (frame, sample) in frames.into_iter().zip(samples) {
+        assert_eq!(frame, [sample; 2]);
+    }
+}
+
+/// The frames of [`render_with`] on the music bus, with the bus at `volume`.
+fn render_on_bus(volume: f32) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.world_mut()
+        .resource_mut::<BusVolumes>()
+        .set(AudioBus::Music, volume);
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(
+        &mut app,
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        AudioBus::Music,
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192)
+}
+
+#[test]
+fn bus_volume_halves_the_rms() {
+    let full = render_on_bus(1.0);
+    let half = render_on_bus(0.5);
+    let [full_rms, half_rms] =
+        [&full, &half].map(|frames| common::rms(frames[4096..].iter().map(|[left, _]| *left)));
+    let ratio = half_rms / full_rms;
+    assert!((0.49..0.51).contains(&ratio), "rms ratio {ratio}");
+    assert_scaled(&half, &full, 0.5);
+}
+
+#[test]
+fn bus_volume_changes_reach_playing_voices() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(
+        &mut app,
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        AudioBus::Music,
+    );
+    let mut decoder = common::decoder(&app, entity);
+    let before = common::channel_rms(&common::render(&mut decoder, 4096)[2048..]);
+
+    app.world_mut()
+        .resource_mut::<BusVolumes>()
+        .set(AudioBus::Music, 0.5);
+    app.update();
+    // Ramped over the next block, at the new volume after it.
+    let block = FrameSize::default().samples() as usize;
+    let after = common::channel_rms(&common::render(&mut decoder, block * 3)[block..]);
+    for channel in 0..2 {
+        let ratio = after[channel] / before[channel];
+        assert!((0.45..0.55).contains(&ratio), "rms ratio {ratio}");
+    }
+}
+
+/// The frames of [`render_with`] past its first half, with the mixer's reverb at `reverb_level`
+/// and `send` on the source.
+fn render_reverb(reverb_level: f32, send: bool) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.insert_resource(SteamAudioMixer {
+        reverb_level,
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    // A short decaying echo, gone well within the first half.
+    let ir = ConvolutionReverbIR {
+        samples: (0..256).map(|index| 0.9f32.powi(index)).collect(),
+        sample_rate: common::SAMPLE_RATE,
+    };
+    let ir = app
+        .world_mut()
+        .resource_mut::<Assets<ConvolutionReverbIR>>()
+        .add(ir);
+    let send = send.then_some(ConvolutionReverbSend(ir, 1.0));
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+    if let Some(send) = send {
+        app.world_mut().entity_mut(entity).insert(send);
+    }
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192).split_off(4096)
+}
+
+#[test]
+fn muted_reverb_level_matches_no_reverb() {
+    let dry = render_reverb(1.0, false);
+    assert!(common::rms(dry.iter().map(|[left, _]| *left)) > 0.0);
+    assert_ne!(render_reverb(1.0, true), dry);
+    assert_eq!(render_reverb(0.0, true), dry);
+}
+
+/// The RMS of the reverb bed while a tone sends `wet_gain` of itself into it, in a closed room.
+fn reverb_bed_rms(wet_gain: f32) -> f32 {
+    let mut app = common::app(SpatialAudioPlugin {
+        reflections: ReflectionConfig {
+            enabled: true,
+            rays: 1024,
+            bounces: 2,
+            duration: 0.5,
+            ..default()
+        },
+        ..default()
+    });
+    let room = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::new(10.0, 4.0, 10.0));
+    app.world_mut()
+        .spawn((Mesh3d(room), Transform::default(), AudioObstacle));
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = play_with(
+        &mut app,
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        SourceMix {
+            wet_gain,
+            ..default()
+        },
+    );
+    // Long enough for a few reflection simulations.
+    common::run_for(&mut app, 1.0);
+
+    let bed = app
+        .world_mut()
+        .query::<&AudioPlayer<AmbisonicsBed>>()
+        .single(app.world())
+        .0
+        .clone();
+    let mut bed = app
+        .world()
+        .resource::<Assets<AmbisonicsBed>>()
+        .get(&bed)
+        .unwrap()
+        .decoder();
+    let mut voice = common::decoder(&app, entity);
+
+    // The voice mixes a block into the bus, then the bed takes it, like on the audio thread.
+    let block = FrameSize::default().samples() as usize;
+    let mut reverb = Vec::new();
+    for _ in 0..16 {
+        assert_eq!(common::render(&mut voice, block).len(), block);
+        reverb.extend(bed.by_ref().take(block * 2));
+    }
+    common::rms(reverb.split_off(reverb.len() / 2))
+}
+
+#[test]
+fn sources_without_a_send_leave_the_reverb_silent() {
+    let sent = reverb_bed_rms(1.0);
+    assert!(sent > 0.0, "no reverb with a full send");
+    assert_eq!(reverb_bed_rms(0.0), 0.0);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    params::SourceParams,
+    settings::FrameSize,
+    source::{render_offline, SteamAudio},
+};
+use rodio::Source;
+
+use common::SAMPLE_RATE;
+
+const BLOCKS: usize = 32;
+
+/// The parameters of a source at `position` heard by a listener at the origin facing -Z.
+fn params_at(position: Vec3) -> SourceParams {
+    SourceParams::new(
+        &GlobalTransform::from_translation(position),
+        &GlobalTransform::IDENTITY,
+    )
+}
+
+/// The second half of `frames`, past the volume ramp at the start.
+fn settled(frames: &[[f32; 2]]) -> &[[f32; 2]] {
+    &frames[frames.len() / 2..]
+}
+
+#[test]
+fn doubling_the_distance_halves_the_gain() {
+    let tone = common::tone(5.0);
+    let [near, far] = [2.0, 4.0].map(|distance| {
+        let frames = render_offline(&tone, [params_at(Vec3::new(0.0, 0.0, -distance))], BLOCKS);
+        assert!(!frames.is_empty());
+        common::rms(settled(&frames).iter().map(|[left, right]| left + right))
+    });
+
+    // The default model is inverse distance, air absorption takes a little more.
+    let ratio = near / far;
+    assert!((1.9..2.2).contains(&ratio), "gain ratio {ratio}");
+}
+
+#[test]
+fn sources_pan_towards_their_side() {
+    let tone = common::tone(5.0);
+    let [right, left] = [3.0, -3.0].map(|x| {
+        let frames = render_offline(&tone, [params_at(Vec3::new(x, 0.0, 0.0))], BLOCKS);
+        common::channel_rms(settled(&frames))
+    });
+
+    assert!(right[1] > right[0] * 2.0, "right source: {right:?}");
+    assert!(left[0] > left[1] * 2.0, "left source: {left:?}");
+    // The head is symmetric.
+    let mirrored = right[1] / left[0];
+    assert!((0.9..1.1).contains(&mirrored), "mirrored gain {mirrored}");
+}
+
+#[test]
+fn looping_sources_repeat_every_period() {
+    let period = SAMPLE_RATE as usize / 10;
+    let samples = common::tone_samples(0.1);
+    let looping = SteamAudio::from_source(move || {
+        Box::new(
+            rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, samples.clone()).repeat_infinite(),
+        )
+    });
+
+    let params = [params_at(Vec3::new(1.0, 0.0, -2.0))];
+    let frames = render_offline(&looping, params, BLOCKS);
+    assert!(
+        frames.len() > period * 4,
+        "looped for {} frames",
+        frames.len()
+    );
+
+    // Steady input and parameters give the same output every time around.
+    let looped = settled(&frames);
+    for (frame, repeated) in looped.iter().zip(&looped[period..]) {
+        for channel in 0..2 {
+            assert!(
+                (frame[channel] - repeated[channel]).abs() < 1e-4,
+                "{frame:?} came back as {repeated:?}"
+            );
+        }
+    }
+
+    // Without looping rendering stops once the clip ends.
+    let once = render_offline(&common::tone(0.1), params, BLOCKS);
+    assert!(
+        once.len() < frames.len(),
+        "played for {} frames",
+        once.len()
+    );
+}
+
+#[test]
+fn sine_tones_play_endlessly_at_full_scale() {
+    let params = [params_at(Vec3::new(0.0, 0.0, -2.0))];
+    let frames = render_offline(&SteamAudio::sine(440.0), params, BLOCKS);
+    let block = FrameSize::default().samples() as usize;
+    assert_eq!(frames.len(), BLOCKS * block);
+
+    // The test tone is the same sine at half the amplitude.
+    let tone = render_offline(&common::tone(5.0), params, BLOCKS);
+    let [sine, tone] = [&frames, &tone]
+        .map(|frames| common::rms(settled(frames).iter().map(|[left, right]| left + right)));
+    let ratio = sine / tone;
+    assert!((1.9..2.1).contains(&ratio), "gain ratio {ratio}");
+}
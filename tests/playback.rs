@@ -0,0 +1,491 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::{
+    audio::{PlaybackMode, Source},
+    prelude::*,
+};
+use bevy_steam_audio::{
+    mix::SourceMix,
+    pitch::PitchShift,
+    playback::{
+        AudioErrorPolicy, AudioFinished, KeepOnFinish, PlaybackPosition, SeekAudio,
+        SpatialAudioBundle, SpatialAudioCommands, SpatialAudioError, SpatialAudioSource,
+        SpatialPlaybackControl, SpatialPlaybackStarted, TailBlocks, WarmupBlocks,
+    },
+    samples::SpatialAudioErrorKind,
+    settings::FrameSize,
+    source::{SpatialAudioPlugin, SteamAudio},
+};
+use std::time::{Duration, Instant};
+
+use common::SAMPLE_RATE;
+
+#[test]
+fn decoder_ends_after_the_source() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(0.25),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    let limit = SAMPLE_RATE as usize * 5;
+    let frames = common::render(&mut decoder, limit);
+    assert!(
+        frames.len() < limit,
+        "a quarter second tone played for {} frames",
+        frames.len()
+    );
+    assert!(common::channel_rms(&frames).iter().all(|rms| *rms > 0.0));
+
+    // The last block isn't repeated once the voice has ended.
+    for _ in 0..1024 {
+        assert_eq!(decoder.next(), None);
+    }
+    assert_eq!(decoder.current_frame_len(), Some(0));
+}
+
+#[test]
+fn one_handle_plays_at_each_entity() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let handle = app
+        .world_mut()
+        .resource_mut::<Assets<SteamAudio>>()
+        .add(common::tone(1.0));
+    let [right, left] = [3.0, -3.0].map(|x| {
+        app.world_mut()
+            .spawn((
+                AudioPlayer(handle.clone()),
+                PlaybackSettings::LOOP,
+                Transform::from_xyz(x, 0.0, 0.0),
+            ))
+            .id()
+    });
+    app.update();
+    app.update();
+
+    let [right, left] = [right, left].map(|entity| {
+        let mut decoder = common::decoder(&app, entity);
+        common::channel_rms(&common::render(&mut decoder, 8192))
+    });
+    assert!(right[1] > right[0] * 2.0, "right source: {right:?}");
+    assert!(left[0] > left[1] * 2.0, "left source: {left:?}");
+}
+
+#[test]
+fn finished_is_sent_after_a_short_clip() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let [despawned, kept] = [false, true].map(|keep| {
+        let entity = common::play(
+            &mut app,
+            common::tone(0.1),
+            Transform::from_xyz(0.0, 0.0, -2.0),
+            PlaybackSettings::ONCE,
+        );
+        if keep {
+            app.world_mut().entity_mut(entity).insert(KeepOnFinish);
+        }
+        entity
+    });
+
+    for entity in [despawned, kept] {
+        let mut decoder = common::decoder(&app, entity);
+        while decoder.next().is_some() {}
+    }
+
+    let mut finished = Vec::new();
+    for _ in 0..3 {
+        app.update();
+        finished.extend(
+            common::events::<AudioFinished>(&app)
+                .into_iter()
+                .map(|event| event.entity),
+        );
+        if finished.contains(&despawned) && finished.contains(&kept) {
+            break;
+        }
+    }
+    assert!(finished.contains(&despawned), "finished: {finished:?}");
+    assert!(finished.contains(&kept), "finished: {finished:?}");
+
+    assert!(app.world().get_entity(despawned).is_err());
+    assert!(app.world().get_entity(kept).is_ok());
+}
+
+#[test]
+fn seeking_skips_to_the_block_at_the_position() {
+    let frame_size = 1024;
+    let mut app = common::app(SpatialAudioPlugin {
+        frame_size: FrameSize::new(frame_size).unwrap(),
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(10.0),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+    let mut decoder = common::decoder(&app, entity);
+
+    app.world_mut()
+        .entity_mut(entity)
+        .insert(SeekAudio(Duration::from_secs(5)));
+    app.update();
+
+    // The seek happens when the decoder reads its next block, which then plays.
+    assert!(decoder.next().is_some());
+    app.update();
+    app.update();
+
+    let target_block = 5 * SAMPLE_RATE as u64 / frame_size as u64;
+    let position = app.world().get::<PlaybackPosition>(entity).unwrap();
+    assert_eq!(position.frames, (target_block + 1) * frame_size as u64);
+    assert!(app.world().get::<SeekAudio>(entity).is_none());
+}
+
+#[test]
+fn seeking_counts_frames_at_the_rate_of_the_source() {
+    let (frame_size, rate) = (1024, 22_050);
+    let mut app = common::app(SpatialAudioPlugin {
+        frame_size: FrameSize::new(frame_size).unwrap(),
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        SteamAudio::from_samples(vec![0.25; 10 * rate as usize], rate, 1),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+    let mut decoder = common::decoder(&app, entity);
+
+    app.world_mut()
+        .entity_mut(entity)
+        .insert(SeekAudio(Duration::from_secs(5)));
+    app.update();
+    assert!(decoder.next().is_some());
+    app.update();
+    app.update();
+
+    // Resampled to the output rate, a block reads about half a frame size of the source.
+    let target = 5 * rate as u64 / frame_size as u64 * frame_size as u64;
+    let position = app.world().get::<PlaybackPosition>(entity).unwrap();
+    assert!(
+        (target..target + frame_size as u64).contains(&position.frames),
+        "{position:?}"
+    );
+    let block = Duration::from_secs_f64(frame_size as f64 / rate as f64);
+    assert!(
+        position.elapsed.abs_diff(Duration::from_secs(5)) < block,
+        "{position:?}"
+    );
+}
+
+#[test]
+fn warmed_up_voices_start_audible() {
+    let frame_size = 1024;
+    let mut app = common::app(SpatialAudioPlugin {
+        frame_size: FrameSize::new(frame_size).unwrap(),
+        warmup_blocks: WarmupBlocks(4),
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(1.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    let first_block = common::render(&mut decoder, frame_size as usize);
+    assert_eq!(first_block.len(), frame_size as usize);
+    let rms = common::channel_rms(&first_block);
+    assert!(rms.iter().all(|rms| *rms > 0.0), "first block: {rms:?}");
+}
+
+/// Frames a one second tone plays for at `pitch`.
+fn frames_at_pitch(pitch: f32) -> usize {
+    let mut app = common::app(SpatialAudioPlugin {
+        frame_size: FrameSize::new(256).unwrap(),
+        tail_blocks: TailBlocks(0),
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+    app.world_mut().entity_mut(entity).insert(PitchShift(pitch));
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, SAMPLE_RATE as usize * 4).len()
+}
+
+#[test]
+fn pitch_shifts_change_the_length() {
+    let unshifted = frames_at_pitch(1.0) as f32;
+
+    // The rate eases in over the first blocks, and the last one is padded.
+    let up = frames_at_pitch(2.0) as f32 / unshifted;
+    assert!(
+        (0.48..0.52).contains(&up),
+        "an octave up plays {up} as long"
+    );
+    let down = frames_at_pitch(0.5) as f32 / unshifted;
+    assert!(
+        (1.96..2.04).contains(&down),
+        "an octave down plays {down} as long"
+    );
+}
+
+#[test]
+fn loaded_assets_decode_samples() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let handle: Handle<SteamAudio> = app.world().resource::<AssetServer>().load("eduardo.ogg");
+
+    let until = Instant::now() + Duration::from_secs(5);
+    while !app.world().resource::<AssetServer>().is_loaded(&handle) {
+        assert!(Instant::now() < until, "eduardo.ogg didn't load");
+        std::thread::sleep(Duration::from_millis(5));
+        app.update();
+    }
+    let entity = app
+        .world_mut()
+        .spawn((
+            AudioPlayer(handle),
+            PlaybackSettings::ONCE,
+            Transform::from_xyz(0.0, 0.0, -2.0),
+        ))
+        .id();
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    assert_eq!(frames.len(), 8192);
+    assert!(common::channel_rms(&frames).iter().all(|rms| *rms > 0.0));
+}
+
+#[test]
+fn short_clips_ring_out_to_silence() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    // A click, all of what's heard after the first samples is the effects' tail.
+    let mut click = vec![0.0; 256];
+    click[0] = 1.0;
+    let entity = common::play(
+        &mut app,
+        SteamAudio::from_samples(click, SAMPLE_RATE, 1),
+        Transform::from_xyz(1.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, SAMPLE_RATE as usize);
+    let block = FrameSize::default().samples() as usize;
+    assert!(frames.len() > block, "ended after {} frames", frames.len());
+    assert!(common::channel_rms(&frames).iter().all(|rms| *rms > 0.0));
+
+    let peak = |frames: &[[f32; 2]]| {
+        frames
+            .iter()
+            .flatten()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+    };
+    let last = peak(&frames[frames.len() - 64..]);
+    assert!(last < 1e-3, "ended on a peak of {last}");
+    assert!(last <= peak(&frames[..block]));
+}
+
+#[test]
+fn spawned_bundles_become_spatial_sources() {
+    let default = SpatialAudioBundle::default();
+    assert_eq!(default.transform, Transform::IDENTITY);
+    assert!(matches!(default.playback.mode, PlaybackMode::Once));
+
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let transform = Transform::from_xyz(1.0, 0.0, -2.0);
+    let bundle = SpatialAudioBundle::new(
+        &mut app.world_mut().resource_mut::<Assets<SteamAudio>>(),
+        "eduardo.ogg",
+        transform,
+    );
+    let entity = app.world_mut().spawn(bundle).id();
+    app.update();
+
+    let entity = app.world().entity(entity);
+    assert_eq!(entity.get::<Transform>(), Some(&transform));
+    assert!(entity.contains::<GlobalTransform>());
+    assert!(entity.contains::<AudioPlayer<SteamAudio>>());
+    assert!(entity.contains::<SpatialAudioSource>());
+    assert!(entity.contains::<SpatialPlaybackControl>());
+}
+
+#[test]
+fn modified_assets_restart_from_the_first_block() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+    // Dry, so the samples are the tone's own and easy to place.
+    app.world_mut().entity_mut(entity).insert(SourceMix {
+        dry_bypass: 1.0,
+        ..default()
+    });
+    app.update();
+
+    let mut old = common::decoder(&app, entity);
+    assert_eq!(common::render(&mut old, 4096).len(), 4096);
+
+    let asset = app
+        .world()
+        .get::<AudioPlayer<SteamAudio>>(entity)
+        .unwrap()
+        .0
+        .clone();
+    let id = app
+        .world()
+        .resource::<Assets<SteamAudio>>()
+        .iter()
+        .map(|(id, _)| id)
+        .find(|id| *id != asset.id())
+        .expect("the spawned asset was copied for the voice");
+    app.world_mut().send_event(AssetEvent::Modified { id });
+    // Removed, queued again and handed its mix.
+    for _ in 0..3 {
+        app.update();
+    }
+
+    // The old decoder stops at its next block.
+    let block = FrameSize::default().samples() as usize;
+    assert!(common::render(&mut old, block * 2).len() <= block);
+    assert!(app.world().get::<SpatialAudioSource>(entity).is_some());
+
+    let samples = common::tone_samples(1.0);
+    let mut new = common::decoder(&app, entity);
+    let frames = common::render(&mut new, block * 2);
+    assert_eq!(frames.len(), block * 2);
+    for (frame, sample) in frames.into_iter().zip(samples) {
+        assert_eq!(frame, [sample; 2]);
+    }
+}
+
+/// Plays the file at `path`, which can't be played, and returns the frames its decoder yields
+/// and the errors sent for it.
+fn play_broken(path: &std::path::Path) -> (Vec<[f32; 2]>, Vec<SpatialAudioErrorKind>) {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    // Absolute paths aren't joined onto the asset folder.
+    let entity = common::play(
+        &mut app,
+        SteamAudio::from_asset_path(path.to_str().unwrap()),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        PlaybackSettings::ONCE,
+    );
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, SAMPLE_RATE as usize);
+    app.update();
+    let errors = common::events::<SpatialAudioError>(&app)
+        .into_iter()
+        .map(|error| {
+            assert_eq!(error.entity, entity);
+            error.kind
+        })
+        .collect();
+    (frames, errors)
+}
+
+#[test]
+fn unplayable_files_play_silence_and_report_an_error() {
+    let dir = std::env::temp_dir().join(format!("bevy_steam_audio_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let empty = dir.join("empty.ogg");
+    std::fs::write(&empty, []).unwrap();
+    let text = dir.join("text.ogg");
+    std::fs::write(&text, "not audio at all").unwrap();
+
+    let silence = AudioErrorPolicy::default().silence;
+    let silent_frames = (silence.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+    for (path, open) in [
+        (dir.join("missing.ogg"), true),
+        (empty, false),
+        (text, false),
+    ] {
+        let (frames, errors) = play_broken(&path);
+        assert!(
+            frames.len() >= silent_frames && frames.len() < SAMPLE_RATE as usize,
+            "{path:?} played for {} frames",
+            frames.len()
+        );
+        assert!(frames.iter().flatten().all(|sample| *sample == 0.0));
+        match errors.as_slice() {
+            [SpatialAudioErrorKind::Open { .. }] if open => {}
+            [SpatialAudioErrorKind::Decode { .. }] if !open => {}
+            errors => panic!("{path:?} reported {errors:?}"),
+        }
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[derive(Resource)]
+struct Clip(Handle<SteamAudio>);
+
+/// Plays the clip whenever F is pressed, like the `basic` example.
+fn play_on_f(keys: Res<ButtonInput<KeyCode>>, clip: Res<Clip>, mut commands: Commands) {
+    if keys.just_pressed(KeyCode::KeyF) {
+        commands.play_spatial(clip.0.clone(), Vec3::new(2.0, 0.0, 0.0));
+    }
+}
+
+#[test]
+fn pressing_f_plays_a_sound_that_starts_and_finishes() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let clip = app
+        .world_mut()
+        .resource_mut::<Assets<SteamAudio>>()
+        .add(common::tone(0.1));
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(Clip(clip))
+        .add_systems(Update, play_on_f);
+    common::spawn_listener(&mut app, Transform::default());
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyF);
+    app.update();
+    app.update();
+    let entity = app
+        .world_mut()
+        .query_filtered::<Entity, With<AudioPlayer<SteamAudio>>>()
+        .single(app.world());
+
+    let mut decoder = common::decoder(&app, entity);
+    assert!(decoder.next().is_some());
+    app.update();
+    let started = common::events::<SpatialPlaybackStarted>(&app);
+    assert_eq!(started, [SpatialPlaybackStarted { entity }]);
+
+    while decoder.next().is_some() {}
+    app.update();
+    let finished = common::events::<AudioFinished>(&app);
+    assert_eq!(finished, [AudioFinished { entity }]);
+}
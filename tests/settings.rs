@@ -0,0 +1,77 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    geometry::{AudioSceneRebuilt, SteamAudioScene},
+    ray_tracer::{GpuSimulationAvailable, RayTracerBackend, RayTracerFallback, SteamAudioInfo},
+    scene::AudioObstacle,
+    settings::ContextConfig,
+    source::SpatialAudioPlugin,
+};
+
+#[test]
+fn context_change_rebuilds_the_scene() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    let mesh = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::default());
+    app.world_mut()
+        .spawn((Mesh3d(mesh), Transform::default(), AudioObstacle));
+    app.update();
+    assert_eq!(app.world().resource::<SteamAudioScene>().len(), 1);
+
+    app.world_mut()
+        .resource_mut::<ContextConfig>()
+        .set_changed();
+    app.update();
+
+    assert_eq!(
+        common::events::<AudioSceneRebuilt>(&app),
+        [AudioSceneRebuilt { obstacles: 1 }]
+    );
+    assert_eq!(app.world().resource::<SteamAudioScene>().len(), 1);
+}
+
+#[test]
+fn unavailable_gpu_falls_back_to_the_cpu() {
+    // No machine running the tests has a hundredth OpenCL device.
+    let mut app = common::app(SpatialAudioPlugin::default().with_gpu_simulation(Some(99)));
+    let fallbacks = common::events::<RayTracerFallback>(&app);
+    if cfg!(feature = "radeon-rays") {
+        assert_eq!(fallbacks.len(), 1, "{fallbacks:?}");
+    }
+    assert_eq!(
+        *app.world().resource::<GpuSimulationAvailable>(),
+        GpuSimulationAvailable(false)
+    );
+    assert_eq!(
+        app.world().resource::<SteamAudioInfo>().ray_tracer,
+        RayTracerBackend::Phonon
+    );
+
+    // Simulates and renders on the CPU like any other app.
+    let mesh = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::default());
+    app.world_mut().spawn((
+        Mesh3d(mesh),
+        Transform::from_xyz(0.0, 0.0, -2.0),
+        AudioObstacle,
+    ));
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+    common::run_for(&mut app, 0.2);
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    assert!(common::channel_rms(&frames).iter().all(|rms| *rms > 0.0));
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    reflections::ReflectionConfig,
+    scene::{AudioObstacle, AudioSceneMesh},
+    simulation::{SimulationSource, SimulationSources},
+    source::{SpatialAudioPlugin, SpatialAudioSettings},
+};
+use std::time::{Duration, Instant};
+use steam_audio::prelude::SimulationFlags;
+
+#[test]
+fn direct_simulation_runs_after_the_commit() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -4.0),
+        PlaybackSettings::LOOP,
+    );
+    common::run_for(&mut app, 0.5);
+
+    assert_eq!(app.world().resource::<SimulationSources>().len(), 1);
+    let source = app
+        .world()
+        .get::<SimulationSource>(entity)
+        .expect("source wasn't registered with the simulator");
+    // Left at its defaults until a run simulated it, 4 meters away it's attenuated.
+    let direct = source.source().get_outputs(SimulationFlags::DIRECT).direct;
+    assert!(
+        direct.distance_attenuation > 0.0 && direct.distance_attenuation < 1.0,
+        "distance attenuation {}",
+        direct.distance_attenuation
+    );
+}
+
+/// The median time of a frame with a few sources in a closed room, simulated with `reflections`.
+fn median_frame_time(reflections: ReflectionConfig) -> Duration {
+    let mut app = common::app(SpatialAudioPlugin {
+        reflections,
+        ..default()
+    });
+    let room = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::new(20.0, 6.0, 20.0));
+    app.world_mut()
+        .spawn((Mesh3d(room), Transform::default(), AudioObstacle));
+    common::spawn_listener(&mut app, Transform::default());
+    for x in [-6.0, -2.0, 2.0, 6.0] {
+        common::play(
+            &mut app,
+            common::tone(1.0),
+            Transform::from_xyz(x, 0.0, -4.0),
+            PlaybackSettings::LOOP,
+        );
+    }
+    // Past the first runs, which commit the scene and sources.
+    common::run_for(&mut app, 0.5);
+    median_update(&mut app)
+}
+
+/// The median time `app` takes to update, over 60 frames.
+fn median_update(app: &mut App) -> Duration {
+    let mut frames: Vec<_> = (0..60)
+        .map(|_| {
+            std::thread::sleep(Duration::from_millis(5));
+            let started = Instant::now();
+            app.update();
+            started.elapsed()
+        })
+        .collect();
+    frames.sort();
+    frames[frames.len() / 2]
+}
+
+#[test]
+fn reflections_stay_off_the_main_thread() {
+    let direct = median_frame_time(ReflectionConfig::default());
+    let reflections = median_frame_time(ReflectionConfig {
+        enabled: true,
+        rays: 8192,
+        update_hz: 60.0,
+        ..default()
+    });
+
+    // Tracing this many rays takes several milliseconds, the frame only starts the task.
+    assert!(
+        reflections < direct * 2 + Duration::from_millis(1),
+        "{direct:?} without reflections, {reflections:?} with"
+    );
+}
+
+#[test]
+fn large_scenes_stay_off_the_main_thread() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    for x in [-6.0, -2.0, 2.0, 6.0] {
+        common::play(
+            &mut app,
+            common::tone(1.0),
+            Transform::from_xyz(x, 0.0, -4.0),
+            PlaybackSettings::LOOP,
+        );
+    }
+    common::run_for(&mut app, 0.5);
+    let empty = median_update(&mut app);
+
+    // 2 * 224 * 224 triangles, just over 100k.
+    let ground = Plane3d::default()
+        .mesh()
+        .size(100.0, 100.0)
+        .subdivisions(223);
+    let ground = app.world_mut().resource_mut::<Assets<Mesh>>().add(ground);
+    app.world_mut().spawn((
+        Mesh3d(ground),
+        Transform::from_xyz(0.0, -1.0, 0.0),
+        AudioObstacle,
+    ));
+    // The commit itself happens once, on the frame after it was added.
+    common::run_for(&mut app, 0.5);
+    let triangles = app
+        .world()
+        .resource::<AudioSceneMesh>()
+        .0
+        .as_ref()
+        .map_or(0, |mesh| mesh.triangles.len());
+    assert!(triangles >= 100_000, "{triangles} triangles in the scene");
+
+    let large = median_update(&mut app);
+    assert!(
+        large < empty * 2 + Duration::from_millis(1),
+        "{empty:?} with an empty scene, {large:?} with {triangles} triangles"
+    );
+}
+
+#[test]
+fn apps_run_without_a_simulator() {
+    let mut app = common::app(SpatialAudioPlugin {
+        simulation_enabled: false,
+        ..default()
+    });
+    assert!(app
+        .world()
+        .resource::<SpatialAudioSettings>()
+        .simulator
+        .is_none());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(2.0, 0.0, -2.0),
+        PlaybackSettings::LOOP,
+    );
+    common::run_for(&mut app, 0.2);
+
+    assert!(app.world().get::<SimulationSource>(entity).is_none());
+    assert!(app.world().resource::<SimulationSources>().is_empty());
+    // Still spatialized, attenuated by the distance models alone.
+    let mut decoder = common::decoder(&app, entity);
+    let [left, right] = common::channel_rms(&common::render(&mut decoder, 8192)[4096..]);
+    assert!(right > left && left > 0.0, "left {left}, right {right}");
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    attenuation::{AirAbsorptionOverride, AudioDirectivity, SourceRadius},
+    binaural::NearFieldCorrection,
+    settings::FrameSize,
+    source::{SpatialAudioPlugin, SteamAudio},
+};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// The frames of a looping tone at `transform` with `components` past the volume ramp at the
+/// start, heard by a listener at the origin.
+fn frames_of(transform: Transform, components: impl Bundle) -> Vec<[f32; 2]> {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        transform,
+        PlaybackSettings::LOOP,
+    );
+    app.world_mut().entity_mut(entity).insert(components);
+    app.update();
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    common::render(&mut decoder, 8192).split_off(4096)
+}
+
+/// The channel RMS of [`frames_of`].
+fn rms_of(transform: Transform, components: impl Bundle) -> [f32; 2] {
+    common::channel_rms(&frames_of(transform, components))
+}
+
+#[test]
+fn turning_a_source_changes_its_directivity() {
+    let cardioid = AudioDirectivity {
+        dipole_weight: 0.5,
+        dipole_power: 1.0,
+    };
+    let position = Transform::from_xyz(3.0, 0.0, 0.0);
+
+    // Facing ahead the listener is off to the side, turned left the source faces it.
+    let [left, right] = rms_of(position, cardioid);
+    let [turned_left, turned_right] = rms_of(
+        position.with_rotation(Quat::from_rotation_y(FRAC_PI_2)),
+        cardioid,
+    );
+
+    let ratio = (turned_left + turned_right) / (left + right);
+    assert!((1.8..2.2).contains(&ratio), "gain ratio {ratio}");
+}
+
+/// The channel RMS of the first block of a tone 3 units along +X, heard by a listener turned by
+/// `rotation`.
+fn first_block_on_the_right(rotation: Quat) -> [f32; 2] {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::from_rotation(rotation));
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(3.0, 0.0, 0.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    let block = FrameSize::default().samples() as usize;
+    common::channel_rms(&common::render(&mut decoder, block))
+}
+
+#[test]
+fn sources_along_x_are_heard_on_the_right() {
+    let [left, right] = first_block_on_the_right(Quat::IDENTITY);
+    assert!(right > left, "left {left}, right {right}");
+
+    // Turned around, +X is on the listener's left.
+    let [left, right] = first_block_on_the_right(Quat::from_rotation_y(PI));
+    assert!(left > right, "left {left}, right {right}");
+}
+
+fn move_sources_right(mut sources: Query<&mut Transform, With<AudioPlayer<SteamAudio>>>) {
+    for mut transform in &mut sources {
+        transform.translation = Vec3::new(3.0, 0.0, 0.0);
+    }
+}
+
+#[test]
+fn transforms_set_in_update_are_heard_the_same_frame() {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(-3.0, 0.0, 0.0),
+        PlaybackSettings::LOOP,
+    );
+    app.add_systems(Update, move_sources_right);
+    // A single frame, with a frame of lag the voice would still be on the left.
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    let [left, right] = common::channel_rms(&frames[4096..]);
+    assert!(right > left * 2.0, "left {left}, right {right}");
+}
+
+#[test]
+fn listeners_inside_a_large_source_hear_it_at_full_level() {
+    let [near, far] = [0.5, 1.5].map(|distance| {
+        let frames = frames_of(Transform::from_xyz(0.0, 0.0, -distance), SourceRadius(2.0));
+        let peak = frames
+            .iter()
+            .flatten()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak < 1.0, "clipped at {peak} from {distance} away");
+        common::rms(frames.iter().map(|[left, right]| left + right))
+    });
+
+    assert!(near > 0.0);
+    let ratio = near / far;
+    assert!((0.8..1.25).contains(&ratio), "gain ratio {ratio}");
+}
+
+#[test]
+fn maximum_air_absorption_is_much_quieter() {
+    let position = Transform::from_xyz(0.0, 0.0, -3.0);
+    let [left, right] = rms_of(position, ());
+    let [absorbed_left, absorbed_right] = rms_of(
+        position,
+        AirAbsorptionOverride {
+            low: 1.0,
+            mid: 1.0,
+            high: 1.0,
+        },
+    );
+
+    let ratio = (absorbed_left + absorbed_right) / (left + right);
+    assert!(ratio < 0.1, "gain ratio {ratio}");
+}
+
+#[test]
+fn near_field_correction_changes_close_sources() {
+    // Off to the side, where the level difference between the ears grows up close.
+    let position = Transform::from_xyz(0.3, 0.0, 0.0);
+    let plain = frames_of(position, ());
+    let corrected = frames_of(position, NearFieldCorrection::default());
+
+    assert!(common::rms(corrected.iter().map(|[_, right]| *right)) > 0.0);
+    assert_ne!(plain, corrected);
+    // Disabled, the component changes nothing.
+    let disabled = frames_of(
+        position,
+        NearFieldCorrection {
+            enabled: false,
+            ..default()
+        },
+    );
+    assert_eq!(plain, disabled);
+}
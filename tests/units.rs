@@ -0,0 +1,36 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{settings::AudioUnitsPerMeter, source::SpatialAudioPlugin};
+
+/// The RMS of a tone 8 Bevy units ahead of the listener.
+fn rms_at_eight_units(units: AudioUnitsPerMeter) -> f32 {
+    let mut app = common::app(SpatialAudioPlugin::default());
+    app.insert_resource(units);
+    common::spawn_listener(&mut app, Transform::default());
+    let entity = common::play(
+        &mut app,
+        common::tone(1.0),
+        Transform::from_xyz(0.0, 0.0, -8.0),
+        PlaybackSettings::LOOP,
+    );
+    app.update();
+
+    let mut decoder = common::decoder(&app, entity);
+    let frames = common::render(&mut decoder, 8192);
+    // Past the volume ramp at the start.
+    common::rms(frames[4096..].iter().map(|[left, right]| left + right))
+}
+
+#[test]
+fn doubling_the_scale_halves_the_attenuation_gain() {
+    // Half as many units in a meter puts the source twice as far away.
+    let meters = rms_at_eight_units(AudioUnitsPerMeter(1.0));
+    let doubled = rms_at_eight_units(AudioUnitsPerMeter(0.5));
+
+    // Air absorption takes a little more at the larger distance.
+    let ratio = meters / doubled;
+    assert!((1.9..2.2).contains(&ratio), "gain ratio {ratio}");
+}
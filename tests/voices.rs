@@ -0,0 +1,68 @@
+#![cfg(feature = "native-tests")]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_steam_audio::{
+    source::{SpatialAudioPlugin, SteamAudio},
+    virtual_voice::{MaxVoices, SourcePriority, VoiceCounts},
+};
+
+#[test]
+fn only_max_voices_run_the_effects() {
+    let mut app = common::app(SpatialAudioPlugin {
+        max_voices: MaxVoices(16),
+        ..default()
+    });
+    common::spawn_listener(&mut app, Transform::default());
+    let handle = app
+        .world_mut()
+        .resource_mut::<Assets<SteamAudio>>()
+        .add(common::tone(1.0));
+
+    let sources: Vec<Entity> = (0..64)
+        .map(|index| {
+            let angle = index as f32 / 64.0 * std::f32::consts::TAU;
+            let distance = 2.0 + index as f32 * 0.1;
+            app.world_mut()
+                .spawn((
+                    AudioPlayer(handle.clone()),
+                    PlaybackSettings::LOOP,
+                    Transform::from_xyz(angle.cos() * distance, 0.0, angle.sin() * distance),
+                ))
+                .id()
+        })
+        .collect();
+    // The farthest source outranks the closer ones.
+    let farthest = sources[63];
+    app.world_mut()
+        .entity_mut(farthest)
+        .insert(SourcePriority(1));
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert_eq!(
+        *app.world().resource::<VoiceCounts>(),
+        VoiceCounts {
+            active: 16,
+            virtualized: 48,
+        }
+    );
+
+    let audible: Vec<Entity> = sources
+        .iter()
+        .copied()
+        .filter(|&entity| {
+            let mut decoder = common::decoder(&app, entity);
+            let frames = common::render(&mut decoder, 4096);
+            common::channel_rms(&frames[2048..])
+                .iter()
+                .any(|rms| *rms > 1e-4)
+        })
+        .collect();
+    assert_eq!(audible.len(), 16);
+    assert!(audible.contains(&farthest));
+    // Ties go to the closest sources.
+    assert!(audible.contains(&sources[0]));
+}